@@ -1,23 +1,15 @@
 use std::time::Duration;
 
 use crate::ERC20::ERC20Instance;
-use alloy_network::{Ethereum, EthereumWallet, ReceiptResponse};
-use alloy_primitives::{Address, B256, Bytes, U256};
-use alloy_provider::{
-    Identity, RootProvider,
-    fillers::{
-        BlobGasFiller, ChainIdFiller, FillProvider, GasFiller, JoinFill, NonceFiller, WalletFiller,
-    },
-};
+use alloy_network::EthereumWallet;
+use alloy_primitives::{Address, U256};
 use alloy_signer_local::PrivateKeySigner;
-use alloy_sol_types::SolEvent;
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
-use pod_protocol::bridge::Bridge::{self, BridgeInstance};
-use pod_protocol::wrapped_token::WrappedToken::WrappedTokenInstance;
 use pod_sdk::{
     Provider, ProviderBuilder,
     alloy_rpc_types::BlockNumberOrTag,
+    bridge::{BridgeClient, DepositState},
     network::PodNetwork,
     provider::{PodProvider, PodProviderBuilder},
 };
@@ -29,108 +21,14 @@ alloy_sol_types::sol! {
     }
 }
 
-type SourceChainProviderType = FillProvider<
-    JoinFill<
-        JoinFill<
-            Identity,
-            JoinFill<GasFiller, JoinFill<BlobGasFiller, JoinFill<NonceFiller, ChainIdFiller>>>,
-        >,
-        WalletFiller<EthereumWallet>,
-    >,
-    RootProvider,
-    Ethereum,
->;
-
 struct PodBridgeClient {
     provider: PodProvider,
-    #[allow(dead_code)]
-    pod_bridge: BridgeInstance<PodProvider, PodNetwork>,
     token_contract: ERC20Instance<PodProvider, PodNetwork>,
 }
 
-struct SourceChainBridgeClient {
-    source_chain_contract: BridgeInstance<SourceChainProviderType>,
-    source_chain_token_contract: WrappedTokenInstance<SourceChainProviderType, Ethereum>,
-}
-
-impl SourceChainBridgeClient {
-    pub async fn new(
-        source_chain_rpc_url: String,
-        source_chain_contract_address: Address,
-        source_chain_token_contract_address: Address,
-        signer: PrivateKeySigner,
-    ) -> Result<Self> {
-        let wallet = EthereumWallet::from(signer);
-
-        let source_chain_provider = ProviderBuilder::new()
-            .wallet(wallet.clone())
-            .connect(&source_chain_rpc_url)
-            .await?;
-
-        let source_chain_contract =
-            BridgeInstance::new(source_chain_contract_address, source_chain_provider.clone());
-
-        let source_chain_token_contract = WrappedTokenInstance::new(
-            source_chain_token_contract_address,
-            source_chain_provider.clone(),
-        );
-
-        Ok(Self {
-            source_chain_contract,
-            source_chain_token_contract,
-        })
-    }
-
-    pub async fn deposit_token(&self, to: Address, amount: U256) -> Result<(B256, u64)> {
-        let approve_receipt = self
-            .source_chain_token_contract
-            .approve(*self.source_chain_contract.address(), amount)
-            .send()
-            .await
-            .context("sending approve tx to source chain")?
-            .get_receipt()
-            .await
-            .context("getting receipt for approve tx")?;
-        assert!(approve_receipt.status());
-
-        let receipt = self
-            .source_chain_contract
-            .deposit(
-                *self.source_chain_token_contract.address(),
-                amount,
-                to,
-                Address::ZERO,
-                U256::ZERO,
-                Bytes::new(),
-            )
-            .send()
-            .await
-            .context("sending deposit tx to source chain")?
-            .get_receipt()
-            .await
-            .context("getting receipt for deposit tx")?;
-
-        assert!(receipt.status());
-
-        for log in receipt.logs() {
-            let Ok(deposit) = Bridge::Deposit::decode_log(&log.inner) else {
-                continue;
-            };
-            let request_id = B256::from(deposit.data.id);
-            let block_number = receipt.block_number();
-            if let Some(block_number) = block_number {
-                return Ok((request_id, block_number));
-            }
-        }
-
-        Err(anyhow::anyhow!("Request ID not found"))
-    }
-}
-
 impl PodBridgeClient {
     pub async fn new(
         pod_rpc_url: String,
-        pod_bridge_contract_address: Address,
         pod_token_contract_address: Address,
         signer: PrivateKeySigner,
     ) -> Result<Self> {
@@ -141,14 +39,11 @@ impl PodBridgeClient {
             .on_url(pod_rpc_url)
             .await?;
 
-        let pod_bridge = BridgeInstance::new(pod_bridge_contract_address, pod_provider.clone());
-
         let pod_token_contract =
             ERC20Instance::new(pod_token_contract_address, pod_provider.clone());
 
         Ok(Self {
             provider: pod_provider,
-            pod_bridge,
             token_contract: pod_token_contract,
         })
     }
@@ -222,12 +117,21 @@ async fn main() -> Result<()> {
 
     let pod_bridge_client = PodBridgeClient::new(
         cli.pod_rpc_url,
-        cli.pod_bridge_contract_address,
         cli.pod_token_contract_address,
         cli.private_key.clone(),
     )
     .await?;
 
+    let source_chain_provider = ProviderBuilder::new()
+        .wallet(EthereumWallet::from(cli.private_key.clone()))
+        .connect(&cli.source_chain_rpc_url)
+        .await?;
+    let bridge_client = BridgeClient::new(
+        pod_bridge_client.provider.clone(),
+        source_chain_provider,
+        cli.source_chain_bridge_contract_address,
+    );
+
     let to = cli.private_key.address();
 
     match cli.command {
@@ -239,23 +143,25 @@ async fn main() -> Result<()> {
                 "Depositing {amount} of token {source_chain_token_contract_address} to Pod account {to:?}"
             );
 
-            let source_chain_bridge_client = SourceChainBridgeClient::new(
-                cli.source_chain_rpc_url,
-                cli.source_chain_bridge_contract_address,
+            let prev_balance = pod_bridge_client
+                .token_contract
+                .balanceOf(to)
+                .call()
+                .await?;
+            deposit_and_wait(
+                &bridge_client,
                 source_chain_token_contract_address,
-                cli.private_key.clone(),
-            )
-            .await?;
-
-            let new_balance = bridge_token_from_source_chain_to_pod(
-                &source_chain_bridge_client,
-                &pod_bridge_client,
                 to,
                 amount,
                 Duration::from_secs(20 * 60), // block finalization can take minutes
             )
             .await?;
-            println!("New Pod token balance: {new_balance}");
+            let new_balance = pod_bridge_client
+                .token_contract
+                .balanceOf(to)
+                .call()
+                .await?;
+            println!("Pod token balance: {prev_balance} -> {new_balance}");
             Ok(())
         }
         Commands::DepositNativeToPod {
@@ -264,23 +170,17 @@ async fn main() -> Result<()> {
         } => {
             println!("Depositing {amount} wrapped native to Pod account {to:?}");
 
-            let source_chain_bridge_client = SourceChainBridgeClient::new(
-                cli.source_chain_rpc_url,
-                cli.source_chain_bridge_contract_address,
+            let prev_balance = pod_bridge_client.provider.get_balance(to).await?;
+            deposit_and_wait(
+                &bridge_client,
                 source_chain_wrapped_native_address,
-                cli.private_key.clone(),
-            )
-            .await?;
-
-            let new_balance = bridge_native_from_source_chain_to_pod(
-                &source_chain_bridge_client,
-                &pod_bridge_client,
                 to,
                 amount,
                 Duration::from_secs(20 * 60), // block finalization can take minutes
             )
             .await?;
-            println!("New Pod native balance: {new_balance}");
+            let new_balance = pod_bridge_client.provider.get_balance(to).await?;
+            println!("Pod native balance: {prev_balance} -> {new_balance}");
             Ok(())
         }
     }
@@ -303,67 +203,27 @@ async fn wait_for_block<P: Provider>(provider: &P, block_number: u64) -> anyhow:
     }
 }
 
-async fn bridge_native_from_source_chain_to_pod(
-    source_chain_bridge_client: &SourceChainBridgeClient,
-    pod_bridge_client: &PodBridgeClient,
-    to: Address,
-    amount: U256,
-    timeout: Duration,
-) -> Result<U256> {
-    let prev_balance_pod = pod_bridge_client.provider.get_balance(to).await?;
-
-    let (request_id, source_chain_block_number) =
-        source_chain_bridge_client.deposit_token(to, amount).await?;
-
-    println!("Deposited; Request ID: {request_id}, Block Number: {source_chain_block_number}");
-
-    let end_balance = tokio::time::timeout(timeout, async {
-        loop {
-            let pod_balance = pod_bridge_client.provider.get_balance(to).await?;
-            if pod_balance > prev_balance_pod {
-                return anyhow::Ok(pod_balance);
-            }
-            tokio::time::sleep(Duration::from_secs(2)).await;
-        }
-    })
-    .await
-    .context("waiting for deposited")??;
-
-    Ok(end_balance)
-}
-
-async fn bridge_token_from_source_chain_to_pod(
-    source_chain_bridge_client: &SourceChainBridgeClient,
-    pod_bridge_client: &PodBridgeClient,
+async fn deposit_and_wait<P: Provider>(
+    bridge_client: &BridgeClient<P>,
+    token: Address,
     to: Address,
     amount: U256,
     timeout: Duration,
-) -> Result<U256> {
-    let prev_balance_pod = pod_bridge_client
-        .token_contract
-        .balanceOf(to)
-        .call()
-        .await?;
-
-    let (request_id, block_number) = source_chain_bridge_client.deposit_token(to, amount).await?;
-    println!("Deposited; request ID: {request_id}, Block Number: {block_number}");
+) -> Result<()> {
+    let deposit = bridge_client.deposit_to_pod(token, amount, to).await?;
+    if let DepositState::Submitted {
+        id, block_number, ..
+    } = &deposit
+    {
+        println!("Deposited; request ID: {id}, Block Number: {block_number}");
+    }
     println!("Waiting for confirmation on pod");
 
-    let end_balance = tokio::time::timeout(timeout, async {
-        loop {
-            let pod_balance = pod_bridge_client
-                .token_contract
-                .balanceOf(to)
-                .call()
-                .await?;
-            if pod_balance >= prev_balance_pod + amount {
-                return anyhow::Ok(pod_balance);
-            }
-            tokio::time::sleep(Duration::from_secs(2)).await;
-        }
-    })
+    tokio::time::timeout(
+        timeout,
+        bridge_client.wait_for_deposit(deposit, Duration::from_secs(2)),
+    )
     .await
     .context("waiting for deposited")??;
-
-    Ok(end_balance)
+    Ok(())
 }
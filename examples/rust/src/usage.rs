@@ -36,7 +36,7 @@ async fn main() -> Result<()> {
     //
     // for receipt in receipts.items {
     //     if receipt.transaction().to == TxKind::Call(recipient)
-    //         && receipt.verify_receipt(&committee).is_ok()
+    //         && receipt.verify(&committee).is_ok()
     //     {
     //         println!("found verified receipt {receipt:?}");
     //     }
@@ -49,7 +49,7 @@ async fn main() -> Result<()> {
     //
     // for recipient_receipt in recipient_receipts.items {
     //     if recipient_receipt.transaction().to == TxKind::Call(recipient)
-    //         && recipient_receipt.verify_receipt(&committee).is_ok()
+    //         && recipient_receipt.verify(&committee).is_ok()
     //     {
     //         println!("found verified receipt {recipient_receipt:?}");
     //     }
@@ -1,6 +1,10 @@
 use std::time::SystemTime;
 
-use crate::{network::PodNetwork, provider::PodProvider, Address, U256};
+use crate::{
+    network::PodNetwork,
+    provider::{PodProvider, WatchPolicy},
+    Address, U256,
+};
 use alloy_eips::BlockNumberOrTag;
 use anyhow::Context;
 
@@ -114,12 +118,12 @@ impl AuctionClient {
             .await
             .context("sending bid TX")?;
 
-        let receipt = pending_tx
-            .get_receipt()
+        let receipt = self
+            .auction
+            .provider()
+            .wait_for_tx(*pending_tx.tx_hash(), &WatchPolicy::default())
             .await
             .context("awaiting for bid TX confirmation")?;
-
-        anyhow::ensure!(receipt.status(), "failed to submit bid TX");
         Ok(receipt)
     }
 }
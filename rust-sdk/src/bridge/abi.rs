@@ -0,0 +1,24 @@
+//! ABI of the bridge precompile at
+//! [`BRIDGE_PRECOMPILE_ADDRESS`](crate::precompiles::BRIDGE_PRECOMPILE_ADDRESS).
+//! The bridge contract on the other chain is bound in [`pod_protocol::bridge`].
+
+alloy_sol_types::sol! {
+    #[derive(Debug, PartialEq, Eq)]
+    interface IPodBridge {
+        event Withdraw(
+            bytes32 indexed id,
+            address indexed from,
+            address indexed to,
+            address token,
+            uint256 amount,
+            uint256 chainId
+        );
+
+        function withdraw(
+            address token,
+            uint256 amount,
+            address to,
+            uint256 chainId
+        ) external returns (bytes32 id);
+    }
+}
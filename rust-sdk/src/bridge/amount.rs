@@ -0,0 +1,139 @@
+use std::fmt;
+
+use alloy_primitives::U256;
+use pod_types::Wad;
+
+/// Decimals of every token on pod.
+pub const POD_DECIMALS: u8 = 18;
+
+/// A token amount in its claim-chain units, e.g. `1_000_000` for 1 USDC.
+///
+/// pod represents every token with 18 decimals, but the bridge precompile's
+/// `amount` is in the claim chain's decimals and the orderbook's `withdraw`
+/// only accepts whole claim-chain units. Converting through `TokenAmount`
+/// keeps the two in step: an amount that does not divide evenly is refused
+/// here rather than by validators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TokenAmount {
+    raw: U256,
+    decimals: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AmountError {
+    /// The token has more decimals on the claim chain than on pod.
+    UnsupportedDecimals(u8),
+    /// The pod amount is not a whole number of claim-chain units.
+    Inexact { amount: U256, unit: U256 },
+    /// The amount does not fit in 256 bits once scaled to 18 decimals.
+    Overflow,
+}
+
+impl fmt::Display for AmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedDecimals(decimals) => {
+                write!(f, "tokens with {decimals} decimals cannot be bridged")
+            }
+            Self::Inexact { amount, unit } => {
+                write!(f, "amount {amount} is not a multiple of {unit}")
+            }
+            Self::Overflow => write!(f, "amount overflows 18 decimals"),
+        }
+    }
+}
+
+impl std::error::Error for AmountError {}
+
+impl TokenAmount {
+    /// `raw` units of a token with `decimals` decimals on the claim chain.
+    pub fn new(raw: U256, decimals: u8) -> Result<Self, AmountError> {
+        raw.checked_mul(unit(decimals)?)
+            .ok_or(AmountError::Overflow)?;
+        Ok(Self { raw, decimals })
+    }
+
+    /// Converts an 18-decimal pod amount, refusing one that is not a whole
+    /// number of claim-chain units.
+    pub fn from_pod(amount: U256, decimals: u8) -> Result<Self, AmountError> {
+        let unit = unit(decimals)?;
+        let (raw, dust) = amount.div_rem(unit);
+        if !dust.is_zero() {
+            return Err(AmountError::Inexact { amount, unit });
+        }
+        Ok(Self { raw, decimals })
+    }
+
+    /// Converts an 18-decimal pod amount, dropping what does not make up a
+    /// whole claim-chain unit — e.g. to withdraw an entire balance.
+    pub fn from_pod_truncating(amount: U256, decimals: u8) -> Result<Self, AmountError> {
+        Ok(Self {
+            raw: amount / unit(decimals)?,
+            decimals,
+        })
+    }
+
+    /// The amount in claim-chain units.
+    pub fn raw(&self) -> U256 {
+        self.raw
+    }
+
+    /// The token's decimals on the claim chain.
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    /// The amount in pod's 18 decimals.
+    pub fn to_pod(&self) -> U256 {
+        // `new` and `from_pod*` guarantee this fits.
+        self.raw * unit(self.decimals).expect("checked on construction")
+    }
+
+    pub fn to_wad(&self) -> Wad {
+        Wad::from_raw(self.to_pod())
+    }
+}
+
+/// Human-readable, e.g. `1.5` for `1_500_000` of a 6-decimal token.
+impl fmt::Display for TokenAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.to_wad(), f)
+    }
+}
+
+/// One claim-chain unit in pod's 18 decimals: `10^(18 - decimals)`.
+fn unit(decimals: u8) -> Result<U256, AmountError> {
+    if decimals > POD_DECIMALS {
+        return Err(AmountError::UnsupportedDecimals(decimals));
+    }
+    Ok(U256::from(10).pow(U256::from(POD_DECIMALS - decimals)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_between_pod_and_claim_chain_units() {
+        let usdc = TokenAmount::new(U256::from(1_500_000), 6).unwrap();
+        assert_eq!(usdc.to_pod(), U256::from(1_500_000_000_000_000_000u128));
+        assert_eq!(usdc.to_string(), "1.5");
+        assert_eq!(TokenAmount::from_pod(usdc.to_pod(), 6).unwrap(), usdc);
+
+        let dusty = usdc.to_pod() + U256::from(1);
+        assert_eq!(
+            TokenAmount::from_pod(dusty, 6),
+            Err(AmountError::Inexact {
+                amount: dusty,
+                unit: U256::from(1_000_000_000_000u64),
+            })
+        );
+        assert_eq!(TokenAmount::from_pod_truncating(dusty, 6).unwrap(), usdc);
+
+        assert_eq!(
+            TokenAmount::new(U256::from(1), 19),
+            Err(AmountError::UnsupportedDecimals(19))
+        );
+        assert_eq!(TokenAmount::new(U256::MAX, 6), Err(AmountError::Overflow));
+    }
+}
@@ -0,0 +1,332 @@
+//! Assembling the `proof` argument of the claim-chain bridge's `claim` and
+//! `batchClaim`.
+//!
+//! The first byte of a proof is its type. A certificate follows it with 65-byte
+//! `(r, s, v)` validator signatures in strictly ascending signer order; a
+//! merkle proof with the 4-byte version it was committed under and the
+//! ABI-encoded `bytes32[]` path.
+
+use std::{collections::HashSet, fmt};
+
+use alloy_primitives::{keccak256, Address, Bytes, Signature, U256};
+use alloy_sol_types::{SolCall, SolValue};
+use pod_protocol::bridge::Bridge;
+use pod_types::{rpc::bridge::BridgeClaimProof, Hash};
+use serde::{Deserialize, Serialize};
+
+use super::abi::IPodBridge;
+
+/// Selector of the pod bridge precompile's `withdraw`, which the claim hash
+/// commits to.
+const WITHDRAW_SELECTOR: [u8; 4] = IPodBridge::withdrawCall::SELECTOR;
+
+/// What a claim-chain bridge contract binds its proofs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClaimDomain {
+    /// Chain the bridge contract is deployed on and claims are submitted to.
+    pub claim_chain_id: u64,
+    /// The contract's `CHAIN_ID`: pod's chain id.
+    pub pod_chain_id: u64,
+    /// The contract's `BRIDGE_CONTRACT`: the bridge on pod withdrawals are
+    /// made through.
+    pub pod_bridge: Address,
+    /// The contract's current `version`. Certificates are only valid under it.
+    pub version: u64,
+}
+
+impl ClaimDomain {
+    /// `Bridge._computeDomainSeparator(version)`.
+    pub fn domain_separator(&self, version: u64) -> Hash {
+        keccak256(
+            (
+                keccak256("pod network"),
+                keccak256("attest_tx_bridge"),
+                U256::from(self.pod_chain_id),
+                U256::from(version),
+            )
+                .abi_encode(),
+        )
+    }
+}
+
+/// One withdrawal to claim, as passed to `claim`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claim {
+    /// The token paid out on the claim chain.
+    pub token: Address,
+    /// The same token on pod, which is what the withdrawal was signed for.
+    pub mirror_token: Address,
+    /// In the token's claim-chain decimals.
+    pub amount: U256,
+    pub to: Address,
+    pub aux_tx_suffix: Bytes,
+}
+
+impl Claim {
+    /// `Bridge._claimTxHash`: the hash validators sign and the contract marks
+    /// as processed. Certificates sign it under the domain's current version,
+    /// merkle proofs under the version they carry.
+    pub fn tx_hash(&self, domain: &ClaimDomain, version: u64) -> Hash {
+        let mut data = Vec::with_capacity(4 + 4 * 32);
+        data.extend_from_slice(&WITHDRAW_SELECTOR);
+        data.extend_from_slice(&self.mirror_token.into_word()[..]);
+        data.extend_from_slice(&self.amount.to_be_bytes::<32>());
+        data.extend_from_slice(&self.to.into_word()[..]);
+        data.extend_from_slice(&U256::from(domain.claim_chain_id).to_be_bytes::<32>());
+
+        let mut tx = Vec::with_capacity(3 * 32 + self.aux_tx_suffix.len());
+        tx.extend_from_slice(&domain.domain_separator(version)[..]);
+        tx.extend_from_slice(&domain.pod_bridge.into_word()[..]);
+        tx.extend_from_slice(&keccak256(&data)[..]);
+        tx.extend_from_slice(&self.aux_tx_suffix);
+        keccak256(&tx)
+    }
+
+    pub fn claim_call(&self, proof: &ClaimProof) -> Bridge::claimCall {
+        Bridge::claimCall {
+            token: self.token,
+            amount: self.amount,
+            to: self.to,
+            proof: proof.to_bytes(),
+            auxTxSuffix: self.aux_tx_suffix.clone(),
+        }
+    }
+
+    fn params(&self, proof: &ClaimProof) -> Bridge::ClaimParams {
+        Bridge::ClaimParams {
+            amount: self.amount,
+            to: self.to,
+            proof: proof.to_bytes(),
+            auxTxSuffix: self.aux_tx_suffix.clone(),
+        }
+    }
+}
+
+/// A `batchClaim` of claims that all pay out `token`.
+pub fn batch_claim_call(token: Address, claims: &[(Claim, ClaimProof)]) -> Bridge::batchClaimCall {
+    Bridge::batchClaimCall {
+        token,
+        claims: claims
+            .iter()
+            .map(|(claim, proof)| claim.params(proof))
+            .collect(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimProof {
+    /// Validator signatures, sorted by signer.
+    Certificate(Vec<Signature>),
+    /// Inclusion in the merkle root committed for an earlier `version`.
+    Merkle { version: u32, path: Vec<Hash> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClaimProofError {
+    /// The signatures are not a whole number of 65-byte chunks.
+    MalformedSignatures(usize),
+    /// Fewer validators signed the locally computed claim hash than the
+    /// contract requires; the claim would revert. Usually the claim's
+    /// parameters or domain differ from what was withdrawn.
+    InsufficientSignatures { valid: usize, quorum: usize },
+}
+
+impl fmt::Display for ClaimProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MalformedSignatures(len) => {
+                write!(f, "{len} signature bytes are not a multiple of 65")
+            }
+            Self::InsufficientSignatures { valid, quorum } => write!(
+                f,
+                "{valid} of the required {quorum} validators signed the claim hash"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ClaimProofError {}
+
+impl ClaimProof {
+    pub const CERTIFICATE: u8 = 0;
+    pub const MERKLE: u8 = 1;
+
+    /// A certificate from concatenated `(r, s, v)` signatures over `tx_hash`.
+    ///
+    /// Signatures are ordered by recovered signer as the contract requires.
+    /// Those that don't recover to one of `validators`, and repeats of a
+    /// signer, are dropped rather than failing the whole claim. At least
+    /// `quorum` must remain.
+    pub fn certificate(
+        tx_hash: Hash,
+        signatures: &[u8],
+        validators: &HashSet<Address>,
+        quorum: usize,
+    ) -> Result<Self, ClaimProofError> {
+        let mut signed = recover_signers(tx_hash, signatures)?;
+        signed.retain(|(signer, _)| validators.contains(signer));
+        signed.sort_by_key(|(signer, _)| *signer);
+        signed.dedup_by_key(|(signer, _)| *signer);
+
+        if signed.len() < quorum {
+            return Err(ClaimProofError::InsufficientSignatures {
+                valid: signed.len(),
+                quorum,
+            });
+        }
+        Ok(Self::Certificate(
+            signed.into_iter().map(|(_, signature)| signature).collect(),
+        ))
+    }
+
+    /// Who signed `tx_hash`, one entry per recoverable signature. The bridge
+    /// contract's `activeValidators` tells which of them count.
+    pub fn signers(tx_hash: Hash, signatures: &[u8]) -> Result<Vec<Address>, ClaimProofError> {
+        Ok(recover_signers(tx_hash, signatures)?
+            .into_iter()
+            .map(|(signer, _)| signer)
+            .collect())
+    }
+
+    /// A certificate from a `pod_getBridgeClaimProof` response, checked
+    /// against the claim hash computed locally from `claim` and `domain`.
+    pub fn from_response(
+        response: &BridgeClaimProof,
+        claim: &Claim,
+        domain: &ClaimDomain,
+        validators: &HashSet<Address>,
+        quorum: usize,
+    ) -> Result<Self, ClaimProofError> {
+        let tx_hash = claim.tx_hash(domain, domain.version);
+        Self::certificate(tx_hash, &response.signatures, validators, quorum)
+    }
+
+    /// The version whose domain separator the claim hash is computed under.
+    pub fn version(&self, domain: &ClaimDomain) -> u64 {
+        match self {
+            Self::Certificate(_) => domain.version,
+            Self::Merkle { version, .. } => u64::from(*version),
+        }
+    }
+
+    /// The `proof` bytes `claim` and `batchClaim` take.
+    pub fn to_bytes(&self) -> Bytes {
+        let mut bytes = Vec::new();
+        match self {
+            Self::Certificate(signatures) => {
+                bytes.reserve(1 + 65 * signatures.len());
+                bytes.push(Self::CERTIFICATE);
+                for signature in signatures {
+                    bytes.extend_from_slice(&signature.as_bytes());
+                }
+            }
+            Self::Merkle { version, path } => {
+                bytes.push(Self::MERKLE);
+                bytes.extend_from_slice(&version.to_be_bytes());
+                bytes.extend_from_slice(&path.abi_encode());
+            }
+        }
+        bytes.into()
+    }
+}
+
+fn recover_signers(
+    tx_hash: Hash,
+    signatures: &[u8],
+) -> Result<Vec<(Address, Signature)>, ClaimProofError> {
+    if !signatures.len().is_multiple_of(65) {
+        return Err(ClaimProofError::MalformedSignatures(signatures.len()));
+    }
+    Ok(signatures
+        .chunks_exact(65)
+        .filter_map(|chunk| {
+            let signature = Signature::try_from(chunk).ok()?;
+            let signer = signature.recover_address_from_prehash(&tx_hash).ok()?;
+            Some((signer, signature))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_signer::SignerSync;
+    use alloy_signer_local::PrivateKeySigner;
+
+    use super::*;
+
+    fn domain() -> ClaimDomain {
+        ClaimDomain {
+            claim_chain_id: 42161,
+            pod_chain_id: 1293,
+            pod_bridge: Address::repeat_byte(0xb0),
+            version: 3,
+        }
+    }
+
+    fn claim() -> Claim {
+        Claim {
+            token: Address::repeat_byte(0x01),
+            mirror_token: Address::repeat_byte(0x02),
+            amount: U256::from(1_000_000),
+            to: Address::repeat_byte(0x03),
+            aux_tx_suffix: Bytes::new(),
+        }
+    }
+
+    #[test]
+    fn certificate_is_sorted_by_signer_and_filtered() {
+        let tx_hash = claim().tx_hash(&domain(), domain().version);
+        let validators: Vec<PrivateKeySigner> =
+            (0..4).map(|_| PrivateKeySigner::random()).collect();
+        let outsider = PrivateKeySigner::random();
+
+        let mut signatures = Vec::new();
+        for signer in validators.iter().rev().chain([&outsider, &validators[0]]) {
+            let signature = signer.sign_hash_sync(&tx_hash).unwrap();
+            signatures.extend_from_slice(&signature.as_bytes());
+        }
+        let committee: HashSet<Address> = validators.iter().map(|v| v.address()).collect();
+
+        let proof = ClaimProof::certificate(tx_hash, &signatures, &committee, 3).unwrap();
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes[0], ClaimProof::CERTIFICATE);
+        assert_eq!(bytes.len(), 1 + 4 * 65);
+
+        let signers: Vec<Address> = bytes[1..]
+            .chunks_exact(65)
+            .map(|chunk| {
+                Signature::try_from(chunk)
+                    .unwrap()
+                    .recover_address_from_prehash(&tx_hash)
+                    .unwrap()
+            })
+            .collect();
+        assert!(signers.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let other_hash = Claim {
+            amount: U256::from(2_000_000),
+            ..claim()
+        }
+        .tx_hash(&domain(), domain().version);
+        assert_eq!(
+            ClaimProof::certificate(other_hash, &signatures, &committee, 3),
+            Err(ClaimProofError::InsufficientSignatures {
+                valid: 0,
+                quorum: 3
+            })
+        );
+    }
+
+    #[test]
+    fn merkle_proof_layout() {
+        let path = vec![Hash::repeat_byte(0xaa), Hash::repeat_byte(0xbb)];
+        let bytes = ClaimProof::Merkle { version: 2, path }.to_bytes();
+
+        assert_eq!(bytes[0], ClaimProof::MERKLE);
+        assert_eq!(bytes[1..5], 2u32.to_be_bytes());
+        // offset, length, then the two elements
+        assert_eq!(bytes.len(), 5 + 4 * 32);
+        assert_eq!(bytes[5 + 3 * 32..], [0xbb; 32]);
+    }
+}
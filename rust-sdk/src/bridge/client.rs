@@ -0,0 +1,681 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+    time::Duration,
+};
+
+use alloy_primitives::{Address, Bytes, U256};
+use alloy_provider::Provider;
+use alloy_sol_types::SolEvent;
+use anyhow::Context;
+use pod_protocol::{
+    bridge::Bridge::{self, BridgeInstance},
+    wrapped_token::WrappedToken::WrappedTokenInstance,
+};
+use pod_types::Hash;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    amount::TokenAmount,
+    claim::{Claim, ClaimDomain, ClaimProof, ClaimProofError},
+    withdraw_request,
+};
+use crate::provider::PodProvider;
+
+/// Progress of a deposit from the source chain to pod.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum DepositState {
+    /// Included on the source chain. pod credits it once that block is
+    /// finalized, which can take minutes.
+    Submitted {
+        id: U256,
+        tx_hash: Hash,
+        block_number: u64,
+    },
+    /// Credited on pod.
+    Processed { id: U256, tx_hash: Hash },
+}
+
+impl DepositState {
+    pub fn id(&self) -> U256 {
+        match self {
+            Self::Submitted { id, .. } | Self::Processed { id, .. } => *id,
+        }
+    }
+
+    pub fn is_processed(&self) -> bool {
+        matches!(self, Self::Processed { .. })
+    }
+}
+
+/// Progress of a withdrawal from pod to the source chain. `tx_hash` is always
+/// the withdrawal on pod.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum WithdrawalState {
+    /// Burned on pod; validators are still signing the claim.
+    Withdrawn { tx_hash: Hash, claim: Claim },
+    /// Enough validators signed for the claim to be accepted.
+    Claimable {
+        tx_hash: Hash,
+        claim: Claim,
+        proof: ClaimProof,
+    },
+    /// Paid out on the source chain. `claim_tx` is `None` when someone else,
+    /// such as the relayer, submitted the claim.
+    Claimed {
+        tx_hash: Hash,
+        claim: Claim,
+        claim_tx: Option<Hash>,
+    },
+}
+
+impl WithdrawalState {
+    pub fn claim(&self) -> &Claim {
+        match self {
+            Self::Withdrawn { claim, .. }
+            | Self::Claimable { claim, .. }
+            | Self::Claimed { claim, .. } => claim,
+        }
+    }
+
+    pub fn is_claimed(&self) -> bool {
+        matches!(self, Self::Claimed { .. })
+    }
+}
+
+/// Moves tokens between pod and the chain its bridge contract is deployed on,
+/// called the source chain here.
+///
+/// Every step takes and returns a [`DepositState`] or [`WithdrawalState`].
+/// Both serialize, so a service can persist them between steps and pick a
+/// transfer up where it left off after a restart.
+pub struct BridgeClient<P> {
+    pub pod: PodProvider,
+    /// The bridge contract on the source chain. Its provider needs a wallet to
+    /// deposit and claim.
+    pub source: BridgeInstance<P>,
+    active_validators: Mutex<ActiveValidators>,
+}
+
+/// The contract's `activeValidators` answers for one pod committee epoch and
+/// contract version.
+#[derive(Default)]
+struct ActiveValidators {
+    key: Option<(u64, u64)>,
+    known: HashMap<Address, bool>,
+}
+
+impl<P: Provider> BridgeClient<P> {
+    pub fn new(pod: PodProvider, source: P, source_bridge: Address) -> Self {
+        Self {
+            pod,
+            source: BridgeInstance::new(source_bridge, source),
+            active_validators: Default::default(),
+        }
+    }
+
+    /// What the source-chain contract currently binds claim proofs to.
+    pub async fn claim_domain(&self) -> anyhow::Result<ClaimDomain> {
+        let claim_chain_id = self
+            .source
+            .provider()
+            .get_chain_id()
+            .await
+            .context("getting source chain id")?;
+        let pod_chain_id = self.source.CHAIN_ID().call().await?;
+        let pod_bridge = self.source.BRIDGE_CONTRACT().call().await?;
+        let version = self.source.version().call().await?;
+        Ok(ClaimDomain {
+            claim_chain_id,
+            pod_chain_id: pod_chain_id.try_into().context("CHAIN_ID overflows u64")?,
+            pod_bridge,
+            version: version.try_into().context("version overflows u64")?,
+        })
+    }
+
+    /// Approve and deposit `amount` of the source-chain `token` for `to` on
+    /// pod.
+    #[tracing::instrument(skip(self))]
+    pub async fn deposit_to_pod(
+        &self,
+        token: Address,
+        amount: U256,
+        to: Address,
+    ) -> anyhow::Result<DepositState> {
+        let approve_receipt = WrappedTokenInstance::new(token, self.source.provider())
+            .approve(*self.source.address(), amount)
+            .send()
+            .await
+            .context("sending approve TX")?
+            .get_receipt()
+            .await
+            .context("awaiting for approve TX confirmation")?;
+        anyhow::ensure!(approve_receipt.status(), "approve TX reverted");
+
+        let receipt = self
+            .source
+            .deposit(token, amount, to, Address::ZERO, U256::ZERO, Bytes::new())
+            .send()
+            .await
+            .context("sending deposit TX")?
+            .get_receipt()
+            .await
+            .context("awaiting for deposit TX confirmation")?;
+        anyhow::ensure!(receipt.status(), "deposit TX reverted");
+
+        let id = receipt
+            .logs()
+            .iter()
+            .find_map(|log| Bridge::Deposit::decode_log(&log.inner).ok())
+            .context("deposit TX emitted no Deposit event")?
+            .data
+            .id;
+        Ok(DepositState::Submitted {
+            id,
+            tx_hash: receipt.transaction_hash,
+            block_number: receipt
+                .block_number
+                .context("deposit receipt has no block number")?,
+        })
+    }
+
+    /// Check once whether pod has credited the deposit.
+    pub async fn deposit_state(&self, state: DepositState) -> anyhow::Result<DepositState> {
+        let DepositState::Submitted { id, tx_hash, .. } = state else {
+            return Ok(state);
+        };
+        let processed = self
+            .pod
+            .get_processed_deposits()
+            .await
+            .context("getting processed deposits")?;
+        Ok(if processed.contains(id) {
+            DepositState::Processed { id, tx_hash }
+        } else {
+            state
+        })
+    }
+
+    /// Poll every `interval` until pod has credited the deposit. Wrap in a
+    /// timeout to bound the wait.
+    pub async fn wait_for_deposit(
+        &self,
+        mut state: DepositState,
+        interval: Duration,
+    ) -> anyhow::Result<DepositState> {
+        loop {
+            state = self.deposit_state(state).await?;
+            if state.is_processed() {
+                return Ok(state);
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    /// Withdraw `amount` of the pod `token` to `to` on the source chain,
+    /// through the bridge precompile. `source_token` is the token the claim
+    /// pays out there.
+    #[tracing::instrument(skip(self))]
+    pub async fn withdraw_from_pod(
+        &self,
+        token: Address,
+        source_token: Address,
+        amount: TokenAmount,
+        to: Address,
+    ) -> anyhow::Result<WithdrawalState> {
+        let chain_id = self
+            .source
+            .provider()
+            .get_chain_id()
+            .await
+            .context("getting source chain id")?;
+        let pending = self
+            .pod
+            .send_transaction(withdraw_request(token, amount, to, chain_id))
+            .await
+            .context("sending withdraw TX")?;
+        let receipt = self
+            .pod
+            .get_verified_receipt(pending)
+            .await
+            .context("awaiting for withdraw TX confirmation")?;
+        anyhow::ensure!(receipt.status(), "withdraw TX reverted");
+
+        Ok(WithdrawalState::Withdrawn {
+            tx_hash: receipt.transaction_hash,
+            claim: Claim {
+                token: source_token,
+                mirror_token: token,
+                amount: amount.raw(),
+                to,
+                aux_tx_suffix: Bytes::new(),
+            },
+        })
+    }
+
+    /// Assemble the proof for the withdrawal made by `tx_hash`, or `None`
+    /// while fewer signers than the contract requires are among its
+    /// `activeValidators`.
+    ///
+    /// The node answers an error both while it has not seen the withdrawal and
+    /// while too few validators signed it, and no error code tells those apart
+    /// from each other, so any error it answers reads as not ready yet. Only a
+    /// failure to reach it fails.
+    pub async fn claim_proof(
+        &self,
+        tx_hash: Hash,
+        claim: &Claim,
+    ) -> anyhow::Result<Option<ClaimProof>> {
+        let response = match self.pod.get_bridge_claim_proof(tx_hash).await {
+            Ok(response) => response,
+            Err(err) if err.as_error_resp().is_some() => {
+                tracing::debug!(%tx_hash, %err, "bridge claim proof not ready");
+                return Ok(None);
+            }
+            Err(err) => return Err(err).context("getting bridge claim proof"),
+        };
+
+        let domain = self.claim_domain().await?;
+        let claim_hash = claim.tx_hash(&domain, domain.version);
+        let signers = ClaimProof::signers(claim_hash, &response.signatures)?;
+        let validators = self
+            .active_validators((response.committee_epoch, domain.version), &signers)
+            .await?;
+        let validator_count = self.source.validatorCount().call().await?;
+        let resilience = self.source.adversarialResilience().call().await?;
+        let quorum = usize::try_from(validator_count.saturating_sub(resilience))?;
+
+        match ClaimProof::certificate(claim_hash, &response.signatures, &validators, quorum) {
+            Ok(proof) => Ok(Some(proof)),
+            Err(ClaimProofError::InsufficientSignatures { valid, quorum }) => {
+                tracing::debug!(%tx_hash, valid, quorum, "bridge claim proof below quorum");
+                Ok(None)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Which of `signers` the contract lists in `activeValidators`. Answers
+    /// are kept until `key`, pod's committee epoch and the contract's version,
+    /// changes, so each signer is asked about once per epoch.
+    async fn active_validators(
+        &self,
+        key: (u64, u64),
+        signers: &[Address],
+    ) -> anyhow::Result<HashSet<Address>> {
+        let mut known = {
+            let mut cache = self.active_validators.lock().unwrap();
+            if cache.key != Some(key) {
+                *cache = ActiveValidators {
+                    key: Some(key),
+                    known: HashMap::new(),
+                };
+            }
+            cache.known.clone()
+        };
+
+        let mut fetched = HashMap::new();
+        for &signer in signers {
+            if !known.contains_key(&signer) && !fetched.contains_key(&signer) {
+                let active = self.source.activeValidators(signer).call().await?;
+                fetched.insert(signer, active);
+            }
+        }
+
+        if !fetched.is_empty() {
+            let mut cache = self.active_validators.lock().unwrap();
+            if cache.key == Some(key) {
+                cache.known.extend(&fetched);
+            }
+        }
+        known.extend(fetched);
+        Ok(signers
+            .iter()
+            .copied()
+            .filter(|signer| known.get(signer) == Some(&true))
+            .collect())
+    }
+
+    /// Submit a claimable withdrawal on the source chain. A claim someone else
+    /// already submitted is recognized instead of reverting.
+    #[tracing::instrument(skip(self))]
+    pub async fn claim_on_source(&self, state: WithdrawalState) -> anyhow::Result<WithdrawalState> {
+        let WithdrawalState::Claimable {
+            tx_hash,
+            claim,
+            proof,
+        } = state
+        else {
+            return Ok(state);
+        };
+
+        let domain = self.claim_domain().await?;
+        let claim_hash = claim.tx_hash(&domain, proof.version(&domain));
+        if self.source.processedRequests(claim_hash).call().await? {
+            return Ok(WithdrawalState::Claimed {
+                tx_hash,
+                claim,
+                claim_tx: None,
+            });
+        }
+
+        let receipt = self
+            .source
+            .claim(
+                claim.token,
+                claim.amount,
+                claim.to,
+                proof.to_bytes(),
+                claim.aux_tx_suffix.clone(),
+            )
+            .send()
+            .await
+            .context("sending claim TX")?
+            .get_receipt()
+            .await
+            .context("awaiting for claim TX confirmation")?;
+        anyhow::ensure!(receipt.status(), "claim TX reverted");
+
+        Ok(WithdrawalState::Claimed {
+            tx_hash,
+            claim,
+            claim_tx: Some(receipt.transaction_hash),
+        })
+    }
+
+    /// Take the withdrawal one step further, if it can be. Returns the state
+    /// unchanged while the proof is not ready yet.
+    pub async fn advance(&self, state: WithdrawalState) -> anyhow::Result<WithdrawalState> {
+        match state {
+            WithdrawalState::Withdrawn { tx_hash, claim } => {
+                Ok(match self.claim_proof(tx_hash, &claim).await? {
+                    Some(proof) => WithdrawalState::Claimable {
+                        tx_hash,
+                        claim,
+                        proof,
+                    },
+                    None => WithdrawalState::Withdrawn { tx_hash, claim },
+                })
+            }
+            WithdrawalState::Claimable { .. } => self.claim_on_source(state).await,
+            WithdrawalState::Claimed { .. } => Ok(state),
+        }
+    }
+
+    /// Drive the withdrawal until it is claimed, checking for its proof every
+    /// `interval`. Wrap in a timeout to bound the wait.
+    pub async fn complete_withdrawal(
+        &self,
+        mut state: WithdrawalState,
+        interval: Duration,
+    ) -> anyhow::Result<WithdrawalState> {
+        loop {
+            let next = self.advance(state.clone()).await?;
+            if next.is_claimed() {
+                return Ok(next);
+            }
+            if next == state {
+                tokio::time::sleep(interval).await;
+            }
+            state = next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_network::Ethereum;
+    use alloy_primitives::U64;
+    use alloy_provider::{Identity, ProviderBuilder, RootProvider};
+    use alloy_signer::SignerSync;
+    use alloy_signer_local::PrivateKeySigner;
+    use alloy_sol_types::SolValue;
+    use alloy_transport::mock::Asserter;
+    use pod_types::rpc::bridge::{BridgeClaimProof, ProcessedDeposits};
+
+    use super::*;
+    use crate::test_utils::{provider, rpc_error};
+
+    const TX: Hash = Hash::repeat_byte(0x11);
+
+    fn client(pod: &Asserter, source: &Asserter) -> BridgeClient<RootProvider> {
+        BridgeClient::new(
+            provider(pod),
+            ProviderBuilder::<Identity, Identity, Ethereum>::default()
+                .connect_mocked_client(source.clone()),
+            Address::repeat_byte(0xb1),
+        )
+    }
+
+    fn domain() -> ClaimDomain {
+        ClaimDomain {
+            claim_chain_id: 42161,
+            pod_chain_id: 1293,
+            pod_bridge: Address::repeat_byte(0xb0),
+            version: 3,
+        }
+    }
+
+    fn claim() -> Claim {
+        Claim {
+            token: Address::repeat_byte(0x01),
+            mirror_token: Address::repeat_byte(0x02),
+            amount: U256::from(1_000_000),
+            to: Address::repeat_byte(0x03),
+            aux_tx_suffix: Bytes::new(),
+        }
+    }
+
+    /// Answer an `eth_call` with `value`.
+    fn push_call(source: &Asserter, value: impl SolValue) {
+        source.push_success(&Bytes::from(value.abi_encode()));
+    }
+
+    fn push_domain(source: &Asserter) {
+        let domain = domain();
+        source.push_success(&U64::from(domain.claim_chain_id));
+        push_call(source, U256::from(domain.pod_chain_id));
+        push_call(source, domain.pod_bridge);
+        push_call(source, U256::from(domain.version));
+    }
+
+    #[tokio::test]
+    async fn deposit_is_processed_once_credited() {
+        let (pod, source) = (Asserter::new(), Asserter::new());
+        let client = client(&pod, &source);
+        let submitted = DepositState::Submitted {
+            id: U256::from(7),
+            tx_hash: TX,
+            block_number: 100,
+        };
+
+        pod.push_success(&ProcessedDeposits {
+            watermark: U256::from(5),
+            above_watermark: vec![U256::from(6)],
+        });
+        let state = client.deposit_state(submitted.clone()).await.unwrap();
+        assert_eq!(state, submitted);
+
+        pod.push_success(&ProcessedDeposits {
+            watermark: U256::from(5),
+            above_watermark: vec![U256::from(7)],
+        });
+        let processed = client.deposit_state(state).await.unwrap();
+        assert_eq!(
+            processed,
+            DepositState::Processed {
+                id: U256::from(7),
+                tx_hash: TX
+            }
+        );
+        // Nothing left to ask the node.
+        assert_eq!(
+            client.deposit_state(processed.clone()).await.unwrap(),
+            processed
+        );
+    }
+
+    #[tokio::test]
+    async fn withdrawal_waits_for_attestations() {
+        let (pod, source) = (Asserter::new(), Asserter::new());
+        let client = client(&pod, &source);
+        let withdrawn = WithdrawalState::Withdrawn {
+            tx_hash: TX,
+            claim: claim(),
+        };
+
+        pod.push_failure(rpc_error(-32000, "Insufficient attestations", None));
+        assert_eq!(client.advance(withdrawn.clone()).await.unwrap(), withdrawn);
+        pod.push_failure(rpc_error(-32602, "receipt not found", None));
+        assert_eq!(client.advance(withdrawn.clone()).await.unwrap(), withdrawn);
+
+        // Answered, but one of the two signers is not active on the source
+        // chain, which requires both.
+        let claim_hash = claim().tx_hash(&domain(), domain().version);
+        let signatures: Vec<u8> = (0..2)
+            .flat_map(|_| {
+                PrivateKeySigner::random()
+                    .sign_hash_sync(&claim_hash)
+                    .unwrap()
+                    .as_bytes()
+            })
+            .collect();
+        pod.push_success(&BridgeClaimProof {
+            signatures: signatures.into(),
+            committee_epoch: 0,
+            proof: TX,
+        });
+        push_domain(&source);
+        push_call(&source, true);
+        push_call(&source, false);
+        push_call(&source, 2u64);
+        push_call(&source, 0u64);
+        assert_eq!(client.advance(withdrawn.clone()).await.unwrap(), withdrawn);
+        assert!(source.read_q().is_empty());
+
+        // No answer at all is not mistaken for one.
+        assert!(client.advance(withdrawn).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn withdrawal_becomes_claimable_then_claimed() {
+        let (pod, source) = (Asserter::new(), Asserter::new());
+        let client = client(&pod, &source);
+        let claim_hash = claim().tx_hash(&domain(), domain().version);
+        let validators: Vec<PrivateKeySigner> =
+            (0..3).map(|_| PrivateKeySigner::random()).collect();
+        let signatures: Vec<u8> = validators
+            .iter()
+            .flat_map(|v| v.sign_hash_sync(&claim_hash).unwrap().as_bytes())
+            .collect();
+
+        pod.push_success(&BridgeClaimProof {
+            signatures: signatures.clone().into(),
+            committee_epoch: 0,
+            proof: TX,
+        });
+        push_domain(&source);
+        for _ in &validators {
+            push_call(&source, true);
+        }
+        push_call(&source, 4u64);
+        push_call(&source, 1u64);
+        let claimable = client
+            .advance(WithdrawalState::Withdrawn {
+                tx_hash: TX,
+                claim: claim(),
+            })
+            .await
+            .unwrap();
+        let committee = validators.iter().map(|v| v.address()).collect();
+        let proof = ClaimProof::certificate(claim_hash, &signatures, &committee, 3).unwrap();
+        assert_eq!(
+            claimable,
+            WithdrawalState::Claimable {
+                tx_hash: TX,
+                claim: claim(),
+                proof,
+            }
+        );
+
+        // Signers already asked about in this epoch are not asked again.
+        pod.push_success(&BridgeClaimProof {
+            signatures: signatures.clone().into(),
+            committee_epoch: 0,
+            proof: TX,
+        });
+        push_domain(&source);
+        push_call(&source, 4u64);
+        push_call(&source, 1u64);
+        assert!(client.claim_proof(TX, &claim()).await.unwrap().is_some());
+        assert!(source.read_q().is_empty());
+
+        // Already claimed by someone else, so nothing is sent.
+        push_domain(&source);
+        push_call(&source, true);
+        let claimed = client.advance(claimable).await.unwrap();
+        assert_eq!(
+            claimed,
+            WithdrawalState::Claimed {
+                tx_hash: TX,
+                claim: claim(),
+                claim_tx: None,
+            }
+        );
+        assert_eq!(client.advance(claimed.clone()).await.unwrap(), claimed);
+    }
+
+    #[test]
+    fn states_round_trip() {
+        let proof = ClaimProof::Merkle {
+            version: 2,
+            path: vec![Hash::repeat_byte(0xaa)],
+        };
+        let deposits = [
+            DepositState::Submitted {
+                id: U256::from(1),
+                tx_hash: TX,
+                block_number: 9,
+            },
+            DepositState::Processed {
+                id: U256::from(1),
+                tx_hash: TX,
+            },
+        ];
+        for (state, tag) in deposits.into_iter().zip(["submitted", "processed"]) {
+            let json = serde_json::to_value(&state).unwrap();
+            assert_eq!(json["state"], tag);
+            assert_eq!(serde_json::from_value::<DepositState>(json).unwrap(), state);
+        }
+
+        let withdrawals = [
+            WithdrawalState::Withdrawn {
+                tx_hash: TX,
+                claim: claim(),
+            },
+            WithdrawalState::Claimable {
+                tx_hash: TX,
+                claim: claim(),
+                proof,
+            },
+            WithdrawalState::Claimed {
+                tx_hash: TX,
+                claim: claim(),
+                claim_tx: Some(Hash::repeat_byte(0x22)),
+            },
+        ];
+        for (state, tag) in withdrawals
+            .into_iter()
+            .zip(["withdrawn", "claimable", "claimed"])
+        {
+            let json = serde_json::to_value(&state).unwrap();
+            assert_eq!(json["state"], tag);
+            assert_eq!(
+                serde_json::from_value::<WithdrawalState>(json).unwrap(),
+                state
+            );
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! Moving tokens between pod and the bridge's claim chain.
+
+pub mod abi;
+pub mod amount;
+pub mod claim;
+pub mod client;
+pub mod tracker;
+
+use alloy_primitives::{Address, Bytes, U256};
+use alloy_sol_types::SolCall;
+
+pub use amount::{AmountError, TokenAmount};
+pub use claim::{batch_claim_call, Claim, ClaimDomain, ClaimProof, ClaimProofError};
+pub use client::{BridgeClient, DepositState, WithdrawalState};
+pub use tracker::{WithdrawalTracker, WithdrawalTransition};
+
+use crate::{
+    network::PodTransactionRequest,
+    precompiles::{BRIDGE_PRECOMPILE_ADDRESS, NATIVE_TOKEN_ADDRESS},
+    TransactionBuilder,
+};
+
+/// A bridge precompile `withdraw` of `amount` of `token` to `to` on the chain
+/// `chain_id`. The call takes claim-chain units; a native withdrawal also
+/// carries the amount scaled to 18 decimals as its value, which validators
+/// require to match.
+pub fn withdraw_request(
+    token: Address,
+    amount: TokenAmount,
+    to: Address,
+    chain_id: u64,
+) -> PodTransactionRequest {
+    let call = abi::IPodBridge::withdrawCall {
+        token,
+        amount: amount.raw(),
+        to,
+        chainId: U256::from(chain_id),
+    };
+    let value = if token == NATIVE_TOKEN_ADDRESS {
+        amount.to_pod()
+    } else {
+        U256::ZERO
+    };
+    PodTransactionRequest::default()
+        .with_to(BRIDGE_PRECOMPILE_ADDRESS)
+        .with_input(Bytes::from(call.abi_encode()))
+        .with_value(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_network::TransactionBuilder as _;
+
+    use super::*;
+
+    #[test]
+    fn native_withdrawals_carry_the_scaled_value() {
+        let amount = TokenAmount::new(U256::from(1_000_000), 6).unwrap();
+        let to = Address::repeat_byte(0x11);
+
+        let native = withdraw_request(NATIVE_TOKEN_ADDRESS, amount, to, 1);
+        assert_eq!(native.value(), Some(U256::from(10).pow(U256::from(18))));
+        let call = abi::IPodBridge::withdrawCall::abi_decode(native.input().unwrap()).unwrap();
+        assert_eq!(call.amount, U256::from(1_000_000));
+
+        let erc20 = withdraw_request(Address::repeat_byte(0x22), amount, to, 1);
+        assert_eq!(erc20.value(), Some(U256::ZERO));
+    }
+}
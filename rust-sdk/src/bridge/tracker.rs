@@ -0,0 +1,525 @@
+//! Following orderbook withdrawals from pod onto the claim chain.
+//!
+//! The claim hash is not known when a withdrawal executes. It is fetched from
+//! pod's REST endpoint `GET /v1/bridge/withdrawals/by-id/{id}` once the
+//! certificate exists, and never recomputed here: it folds in the pod chain id
+//! and the bridge version, and a local copy would silently stop matching after
+//! a version bump. The claim chain is then scanned for the bridge's `Claim`
+//! event indexed by that hash.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+
+use alloy_primitives::Address;
+use alloy_provider::Provider;
+use alloy_rpc_types::Filter;
+use alloy_sol_types::SolEvent;
+use futures::Stream;
+use pod_protocol::bridge::Bridge;
+use pod_types::{
+    rpc::bridge::{ClaimStatus, WithdrawalDetail},
+    Hash,
+};
+use reqwest::header::ACCEPT;
+use serde::{Deserialize, Serialize};
+
+/// topic0 of the claim-chain bridge's
+/// `Claim(bytes32 indexed txHash, address token, address mirrorToken, uint256 amount, address indexed to)`.
+pub const CLAIM_TOPIC: Hash = Bridge::Claim::SIGNATURE_HASH;
+
+/// How far before the claim chain's head a newly tracked withdrawal is
+/// searched, to catch a relayer that claimed before tracking began.
+pub const DEFAULT_LOOKBACK_BLOCKS: u64 = 5_000;
+
+/// The most blocks one `eth_getLogs` spans. Public providers commonly reject
+/// wider ranges.
+pub const DEFAULT_MAX_BLOCK_RANGE: u64 = 2_000;
+
+/// A withdrawal that moved to a new state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum WithdrawalTransition {
+    /// The certificate exists and the withdrawal can be claimed.
+    Claimable {
+        withdrawal_id: Hash,
+        claim_hash: Hash,
+    },
+    /// Paid out by the claim-chain transaction `transaction_hash`. Terminal.
+    Claimed {
+        withdrawal_id: Hash,
+        claim_hash: Hash,
+        transaction_hash: Hash,
+    },
+    /// Refused at execution with nothing debited. Terminal.
+    Refused { withdrawal_id: Hash },
+}
+
+impl WithdrawalTransition {
+    pub fn withdrawal_id(&self) -> Hash {
+        match self {
+            Self::Claimable { withdrawal_id, .. }
+            | Self::Claimed { withdrawal_id, .. }
+            | Self::Refused { withdrawal_id } => *withdrawal_id,
+        }
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        !matches!(self, Self::Claimable { .. })
+    }
+}
+
+struct Tracked {
+    claim_hash: Option<Hash>,
+    /// Oldest block still to be searched for this withdrawal. Pinned at the
+    /// first head read after it is tracked, and only raised by a scan that
+    /// carried its claim hash.
+    floor: Option<u64>,
+    lookback: u64,
+}
+
+/// Follows any number of withdrawals until they are claimed or refused.
+///
+/// Each [`poll`](Self::poll) asks pod for the claim hash of every withdrawal
+/// still without one, then searches the claim chain for all known hashes with
+/// one `eth_getLogs` per [`max_block_range`](Self::with_max_block_range)
+/// blocks. Errors on either side are logged and retried on the next poll; a
+/// range is only considered searched once its query succeeded.
+pub struct WithdrawalTracker<P> {
+    http: reqwest::Client,
+    rest_url: String,
+    /// Provider for the chain claims are submitted to.
+    pub claim_chain: P,
+    bridge: Option<Address>,
+    max_block_range: u64,
+    tracked: HashMap<Hash, Tracked>,
+    last_head: Option<u64>,
+}
+
+impl<P: Provider> WithdrawalTracker<P> {
+    /// `rest_url` is pod's REST base, including the `/v1`.
+    pub fn new(rest_url: &str, claim_chain: P) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            // A trailing slash would request `/v1//bridge/...`, whose 404 looks
+            // exactly like a certificate not assembled yet.
+            rest_url: rest_url.trim_end_matches('/').to_owned(),
+            claim_chain,
+            bridge: None,
+            max_block_range: DEFAULT_MAX_BLOCK_RANGE,
+            tracked: HashMap::new(),
+            last_head: None,
+        }
+    }
+
+    /// Only accept `Claim` events from the bridge contract at `bridge`.
+    pub fn with_bridge(mut self, bridge: Address) -> Self {
+        self.bridge = Some(bridge);
+        self
+    }
+
+    pub fn with_max_block_range(mut self, blocks: u64) -> Self {
+        self.max_block_range = blocks.max(1);
+        self
+    }
+
+    /// Follow `withdrawal_id`, searching from `lookback_blocks` before the
+    /// current claim-chain head. Ignored if it is already followed.
+    pub fn track(&mut self, withdrawal_id: Hash, lookback_blocks: u64) {
+        let floor = self
+            .last_head
+            .map(|head| head.saturating_sub(lookback_blocks));
+        self.tracked.entry(withdrawal_id).or_insert(Tracked {
+            claim_hash: None,
+            floor,
+            lookback: lookback_blocks,
+        });
+    }
+
+    pub fn untrack(&mut self, withdrawal_id: Hash) {
+        self.tracked.remove(&withdrawal_id);
+    }
+
+    /// How many withdrawals are still outstanding.
+    pub fn len(&self) -> usize {
+        self.tracked.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tracked.is_empty()
+    }
+
+    /// Ask pod where one withdrawal is. Any failure to get an answer, a 404
+    /// included, is [`ClaimStatus::Pending`]: none of them says anything about
+    /// the withdrawal.
+    pub async fn claim_status(&self, withdrawal_id: Hash) -> ClaimStatus {
+        let url = format!("{}/bridge/withdrawals/by-id/{withdrawal_id}", self.rest_url);
+        let response = match self
+            .http
+            .get(url)
+            .header(ACCEPT, "application/json")
+            .send()
+            .await
+        {
+            Ok(response) if response.status().is_success() => response,
+            Ok(_) => return ClaimStatus::Pending,
+            Err(err) => {
+                tracing::warn!(%withdrawal_id, %err, "fetching withdrawal detail");
+                return ClaimStatus::Pending;
+            }
+        };
+        match response.json::<WithdrawalDetail>().await {
+            Ok(detail) => detail.claim_status(),
+            Err(err) => {
+                tracing::warn!(%withdrawal_id, %err, "decoding withdrawal detail");
+                ClaimStatus::Pending
+            }
+        }
+    }
+
+    /// Move every tracked withdrawal as far as it has got. Terminal ones are
+    /// no longer tracked afterwards.
+    pub async fn poll(&mut self) -> Vec<WithdrawalTransition> {
+        let mut transitions = Vec::new();
+
+        let unresolved: Vec<Hash> = self
+            .tracked
+            .iter()
+            .filter(|(_, tracked)| tracked.claim_hash.is_none())
+            .map(|(id, _)| *id)
+            .collect();
+        let statuses =
+            futures::future::join_all(unresolved.iter().map(|id| self.claim_status(*id))).await;
+        for (withdrawal_id, status) in unresolved.into_iter().zip(statuses) {
+            match status {
+                ClaimStatus::Pending => {}
+                ClaimStatus::Claimable { claim_hash } => {
+                    if let Some(tracked) = self.tracked.get_mut(&withdrawal_id) {
+                        tracked.claim_hash = Some(claim_hash);
+                    }
+                    transitions.push(WithdrawalTransition::Claimable {
+                        withdrawal_id,
+                        claim_hash,
+                    });
+                }
+                ClaimStatus::Refused => {
+                    self.tracked.remove(&withdrawal_id);
+                    transitions.push(WithdrawalTransition::Refused { withdrawal_id });
+                }
+            }
+        }
+        if self.tracked.is_empty() {
+            return transitions;
+        }
+
+        // Read every poll, even with no hash to search for yet: it is what
+        // pins a newly tracked withdrawal's floor to the block it joined at.
+        let head = match self.claim_chain.get_block_number().await {
+            Ok(head) => head,
+            Err(err) => {
+                tracing::warn!(%err, "reading claim chain head");
+                return transitions;
+            }
+        };
+        self.last_head = Some(head);
+        for tracked in self.tracked.values_mut() {
+            tracked
+                .floor
+                .get_or_insert(head.saturating_sub(tracked.lookback));
+        }
+
+        let searched: Vec<(Hash, u64)> = self
+            .tracked
+            .values()
+            .filter_map(|tracked| Some((tracked.claim_hash?, tracked.floor?)))
+            .collect();
+        let Some(mut from) = searched.iter().map(|(_, floor)| *floor).min() else {
+            return transitions;
+        };
+        let claim_hashes: Vec<Hash> = searched.into_iter().map(|(hash, _)| hash).collect();
+
+        // The upper bound is the head read above, named explicitly: with
+        // `latest` the node picks the end, and blocks mined between that and
+        // the next head read would be stepped over for good.
+        while from <= head {
+            let to = head.min(from.saturating_add(self.max_block_range - 1));
+            let mut filter = Filter::new()
+                .event_signature(CLAIM_TOPIC)
+                .topic1(claim_hashes.clone())
+                .from_block(from)
+                .to_block(to);
+            if let Some(bridge) = self.bridge {
+                filter = filter.address(bridge);
+            }
+            let logs = match self.claim_chain.get_logs(&filter).await {
+                Ok(logs) => logs,
+                Err(err) => {
+                    tracing::warn!(from, to, %err, "scanning claim chain for Claim events");
+                    break;
+                }
+            };
+
+            for log in logs {
+                let (Some(&claim_hash), Some(transaction_hash)) =
+                    (log.topics().get(1), log.transaction_hash)
+                else {
+                    continue;
+                };
+                let claimed: Vec<Hash> = self
+                    .tracked
+                    .iter()
+                    .filter(|(_, tracked)| tracked.claim_hash == Some(claim_hash))
+                    .map(|(id, _)| *id)
+                    .collect();
+                for withdrawal_id in claimed {
+                    self.tracked.remove(&withdrawal_id);
+                    transitions.push(WithdrawalTransition::Claimed {
+                        withdrawal_id,
+                        claim_hash,
+                        transaction_hash,
+                    });
+                }
+            }
+            // A withdrawal still waiting for its claim hash keeps its floor, so
+            // the blocks that passed meanwhile are searched once it has one.
+            for tracked in self.tracked.values_mut() {
+                if let (Some(_), Some(floor)) = (tracked.claim_hash, tracked.floor.as_mut()) {
+                    *floor = (*floor).max(to + 1);
+                }
+            }
+            from = to + 1;
+        }
+        transitions
+    }
+
+    /// Poll every `interval` and yield each transition, ending once nothing is
+    /// tracked anymore.
+    pub fn into_stream(self, interval: Duration) -> impl Stream<Item = WithdrawalTransition> {
+        futures::stream::unfold(
+            (self, VecDeque::new()),
+            move |(mut tracker, mut queue)| async move {
+                loop {
+                    if let Some(transition) = queue.pop_front() {
+                        return Some((transition, (tracker, queue)));
+                    }
+                    if tracker.is_empty() {
+                        return None;
+                    }
+                    queue.extend(tracker.poll().await);
+                    if queue.is_empty() {
+                        tokio::time::sleep(interval).await;
+                    }
+                }
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use alloy_primitives::{b256, Bytes, U64};
+    use alloy_provider::{Identity, ProviderBuilder, ProviderCall, RootProvider};
+    use alloy_rpc_types::Log;
+    use alloy_transport::{mock::Asserter, TransportErrorKind, TransportResult};
+
+    use super::*;
+
+    const ID: Hash = Hash::repeat_byte(0x11);
+    const CLAIM_HASH: Hash = Hash::repeat_byte(0x22);
+    const ID2: Hash = Hash::repeat_byte(0x44);
+    const CLAIM_HASH2: Hash = Hash::repeat_byte(0x55);
+    const L1_TX: Hash = Hash::repeat_byte(0x33);
+
+    /// A claim chain answering from scripts, recording every scan. The last
+    /// head repeats, and scans past the scripted replies find nothing.
+    struct FakeChain {
+        root: RootProvider,
+        heads: Mutex<VecDeque<u64>>,
+        logs: Mutex<VecDeque<TransportResult<Vec<Log>>>>,
+        scans: Mutex<Vec<Filter>>,
+    }
+
+    impl FakeChain {
+        fn new(heads: &[u64], logs: Vec<TransportResult<Vec<Log>>>) -> Self {
+            Self {
+                root: ProviderBuilder::<Identity, Identity>::default()
+                    .connect_mocked_client(Asserter::new()),
+                heads: Mutex::new(heads.iter().copied().collect()),
+                logs: Mutex::new(logs.into()),
+                scans: Mutex::new(Vec::new()),
+            }
+        }
+
+        /// `(from, to)` of each scan so far.
+        fn ranges(&self) -> Vec<(u64, u64)> {
+            self.scans
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|filter| {
+                    (
+                        filter.get_from_block().unwrap(),
+                        filter.get_to_block().unwrap(),
+                    )
+                })
+                .collect()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Provider for FakeChain {
+        fn root(&self) -> &RootProvider {
+            &self.root
+        }
+
+        fn get_block_number(&self) -> ProviderCall<[(); 0], U64, u64> {
+            let mut heads = self.heads.lock().unwrap();
+            let head = if heads.len() > 1 {
+                heads.pop_front()
+            } else {
+                heads.front().copied()
+            };
+            ProviderCall::ready(Ok(head.unwrap()))
+        }
+
+        async fn get_logs(&self, filter: &Filter) -> TransportResult<Vec<Log>> {
+            self.scans.lock().unwrap().push(filter.clone());
+            self.logs
+                .lock()
+                .unwrap()
+                .pop_front()
+                .unwrap_or(Ok(Vec::new()))
+        }
+    }
+
+    fn claim_log(claim_hash: Hash, transaction_hash: Hash) -> Log {
+        Log {
+            inner: alloy_primitives::Log::new_unchecked(
+                Address::ZERO,
+                vec![CLAIM_TOPIC, claim_hash],
+                Bytes::new(),
+            ),
+            transaction_hash: Some(transaction_hash),
+            ..Default::default()
+        }
+    }
+
+    /// Nothing listens on the REST url, so withdrawals without a claim hash
+    /// stay pending until a test sets one.
+    fn tracker(chain: FakeChain) -> WithdrawalTracker<FakeChain> {
+        WithdrawalTracker::new("http://127.0.0.1:9/v1", chain)
+    }
+
+    fn resolve(tracker: &mut WithdrawalTracker<FakeChain>, withdrawal_id: Hash, claim_hash: Hash) {
+        tracker.tracked.get_mut(&withdrawal_id).unwrap().claim_hash = Some(claim_hash);
+    }
+
+    #[tokio::test]
+    async fn pages_the_scan_and_retries_a_failed_chunk() {
+        let chain = FakeChain::new(
+            &[1_000, 1_150],
+            vec![
+                Ok(Vec::new()),
+                Ok(vec![claim_log(CLAIM_HASH, L1_TX)]),
+                Ok(Vec::new()),
+                Ok(Vec::new()),
+                Err(TransportErrorKind::custom_str("range too wide")),
+            ],
+        );
+        let mut tracker = tracker(chain).with_max_block_range(100);
+        tracker.track(ID, 250);
+        tracker.track(ID2, 0);
+        resolve(&mut tracker, ID, CLAIM_HASH);
+        resolve(&mut tracker, ID2, CLAIM_HASH2);
+
+        assert_eq!(
+            tracker.poll().await,
+            vec![WithdrawalTransition::Claimed {
+                withdrawal_id: ID,
+                claim_hash: CLAIM_HASH,
+                transaction_hash: L1_TX,
+            }]
+        );
+        assert_eq!(
+            tracker.claim_chain.ranges(),
+            vec![(750, 849), (850, 949), (950, 1_000)]
+        );
+        assert_eq!(
+            tracker.claim_chain.scans.lock().unwrap()[0].topics[1],
+            vec![CLAIM_HASH, CLAIM_HASH2].into()
+        );
+
+        // The claimed withdrawal no longer holds the scan back: the next one
+        // starts past what was searched, at the other's floor. Its second
+        // chunk fails, and is retried on the next poll rather than skipped.
+        assert!(tracker.poll().await.is_empty());
+        assert!(tracker.poll().await.is_empty());
+        assert_eq!(
+            tracker.claim_chain.ranges()[3..],
+            [(1_001, 1_100), (1_101, 1_150), (1_101, 1_150)]
+        );
+        assert_eq!(
+            tracker.claim_chain.scans.lock().unwrap()[3].topics[1],
+            vec![CLAIM_HASH2].into()
+        );
+    }
+
+    #[tokio::test]
+    async fn floors_are_kept_per_withdrawal() {
+        let mut tracker = tracker(FakeChain::new(&[10_000, 10_016, 10_032], Vec::new()));
+        tracker.track(ID, 0);
+        tracker.track(ID2, 0);
+        resolve(&mut tracker, ID, CLAIM_HASH);
+
+        // Only the first withdrawal is searched for while the second waits for
+        // its certificate.
+        tracker.poll().await;
+        tracker.poll().await;
+        assert_eq!(
+            tracker.claim_chain.ranges(),
+            vec![(10_000, 10_000), (10_001, 10_016)]
+        );
+
+        // The scan once the second has its hash reaches back to the block it
+        // was tracked at, not to where the first one's scans had got.
+        resolve(&mut tracker, ID2, CLAIM_HASH2);
+        tracker.poll().await;
+        assert_eq!(tracker.claim_chain.ranges()[2], (10_000, 10_032));
+        assert_eq!(
+            tracker.claim_chain.scans.lock().unwrap()[2].topics[1],
+            vec![CLAIM_HASH, CLAIM_HASH2].into()
+        );
+        assert_eq!(tracker.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn a_later_withdrawal_reaches_back_its_own_lookback() {
+        let mut tracker = tracker(FakeChain::new(&[10_000], Vec::new()));
+        tracker.track(ID, 0);
+        resolve(&mut tracker, ID, CLAIM_HASH);
+        tracker.poll().await;
+
+        tracker.track(ID2, 500);
+        resolve(&mut tracker, ID2, CLAIM_HASH2);
+        tracker.poll().await;
+        assert_eq!(
+            tracker.claim_chain.ranges(),
+            vec![(10_000, 10_000), (9_500, 10_000)]
+        );
+    }
+
+    #[test]
+    fn claim_topic_matches_bridge_event() {
+        assert_eq!(
+            Bridge::Claim::SIGNATURE,
+            "Claim(bytes32,address,address,uint256,address)"
+        );
+        assert_eq!(
+            CLAIM_TOPIC,
+            b256!("0xa0b99c0fdfc395724d7b81a88a6d74852461f7691371afe2839bb8688475788c")
+        );
+    }
+}
@@ -77,11 +77,39 @@
 //! pod_provider.transfer(to, amount).await.unwrap();
 //! # })
 //! ```
+//!
+//! ## Verifying responses as a light client
+//!
+//! A light client checks the committee attestations on receipts and logs
+//! instead of trusting the RPC node. Check out
+//! [provider::PodProvider::into_light_client] for what is verified.
+//!
+//! ```no_run
+//! use pod_sdk::{provider::PodProviderBuilder, Hash, Provider};
+//!
+//! # tokio_test::block_on(async {
+//! let pod_provider = PodProviderBuilder::new()
+//!     .light_client()
+//!     .on_url("ws://localhost:8545")
+//!     .await
+//!     .unwrap();
+//!
+//! // fails with a `VerificationError` unless a quorum attested the receipt
+//! let receipt = pod_provider.get_transaction_receipt(Hash::ZERO).await.unwrap();
+//! # })
+//! ```
 
 pub mod auctions;
+pub mod bridge;
 pub mod network;
+pub mod orderbook;
 pub mod precompiles;
 pub mod provider;
+pub mod sender;
+pub mod subscription;
+
+#[cfg(test)]
+mod test_utils;
 
 // Re-export external dependencies used in public API
 pub use alloy_consensus::TxEip1559;
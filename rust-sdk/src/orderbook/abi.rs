@@ -0,0 +1,69 @@
+//! ABI of the orderbook precompile at
+//! [`ORDERBOOK_PRECOMPILE_ADDRESS`](crate::precompiles::ORDERBOOK_PRECOMPILE_ADDRESS).
+//!
+//! Only the current overload of each function is bound; `submitOrder` takes the
+//! `flags` bitfield (see [`super::OrderFlags`]).
+
+alloy_sol_types::sol! {
+    #[derive(Debug, PartialEq, Eq)]
+    interface IOrderbook {
+        enum OrderType { Limit, Market }
+        enum TriggerType { TakeProfit, StopLoss }
+        enum TriggerGrouping { None, Asset }
+
+        function submitOrder(
+            bytes32 orderbookId,
+            int256 size,
+            uint256 price,
+            OrderType orderType,
+            uint128 deadline,
+            uint128 ttl,
+            uint8 flags
+        ) external;
+
+        function cancel(bytes32 orderbookId, bytes32 canceledOrder, uint128 deadline) external;
+
+        function update(
+            bytes32 orderbookId,
+            bytes32 updatedOrder,
+            uint256 newSize,
+            uint256 newPrice,
+            address token,
+            uint128 deadline
+        ) external;
+
+        function balanceOf(address token, address account) external view returns (int256);
+
+        function withdrawableBalance(address token, address account) external view returns (uint256);
+
+        function deposit(address token, address recipient, uint256 amount, uint128 deadline) external;
+
+        function withdraw(address token, address recipient, uint256 amount, uint128 deadline) external;
+
+        function submitTrigger(
+            bytes32 orderbookId,
+            int256 size,
+            uint256 limitPrice,
+            uint256 triggerPrice,
+            TriggerType triggerType,
+            TriggerGrouping grouping,
+            uint128 deadline,
+            uint128 ttl,
+            bool reduceOnly,
+            bool ioc
+        ) external;
+
+        function cancelTrigger(bytes32 orderbookId, bytes32 triggerOrder, uint128 deadline) external;
+
+        function updateTrigger(
+            bytes32 orderbookId,
+            bytes32 triggerOrder,
+            int256 newSize,
+            uint256 newLimitPrice,
+            uint256 newTriggerPrice,
+            uint128 deadline
+        ) external;
+
+        function submitBatch(bytes[] inner) external;
+    }
+}
@@ -0,0 +1,320 @@
+use alloy_primitives::{Address, Bytes, I256, U256};
+use alloy_sol_types::SolCall;
+use pod_types::{ledger::intent::intent_id, Hash, Timestamp};
+
+use super::{
+    abi::IOrderbook,
+    client::{Order, OrderFlagsError},
+    trigger::Trigger,
+};
+use crate::{
+    bridge::TokenAmount, network::PodTransactionRequest, precompiles::ORDERBOOK_PRECOMPILE_ADDRESS,
+    TransactionBuilder,
+};
+
+/// Most intents a `submitBatch` envelope may carry by default. Operators can
+/// configure a lower cap.
+pub const MAX_BATCH_SIZE: usize = 64;
+
+/// Accumulates orderbook intents into one atomic `submitBatch` transaction.
+///
+/// Every intent in a batch must carry the same deadline, so the builder is
+/// created with it: cancels, updates, deposits and withdrawals take it
+/// implicitly, and orders and triggers are refused if theirs differs.
+#[derive(Debug, Clone)]
+pub struct BatchBuilder {
+    deadline: Timestamp,
+    inner: Vec<Bytes>,
+}
+
+/// A `submitBatch` transaction ready to sign, with the ids of its intents.
+#[derive(Debug, Clone)]
+pub struct Batch {
+    pub request: PodTransactionRequest,
+    /// One id per intent, in the order they were added, cancels, updates,
+    /// deposits and withdrawals included. For orders and triggers it is the
+    /// `order_id` later calls refer to them by; for the rest it names the
+    /// intent itself, not the order it acts on.
+    pub order_ids: Vec<Hash>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchError {
+    Empty,
+    /// Adding another intent would exceed [`MAX_BATCH_SIZE`].
+    Full,
+    DeadlineMismatch {
+        expected: Timestamp,
+        got: Timestamp,
+    },
+    InvalidFlags(OrderFlagsError),
+}
+
+impl std::fmt::Display for BatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "batch has no intents"),
+            Self::Full => write!(f, "batch already holds {MAX_BATCH_SIZE} intents"),
+            Self::DeadlineMismatch { expected, got } => write!(
+                f,
+                "intent deadline {got} differs from the batch deadline {expected}"
+            ),
+            Self::InvalidFlags(e) => write!(f, "invalid order: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for BatchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidFlags(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<OrderFlagsError> for BatchError {
+    fn from(err: OrderFlagsError) -> Self {
+        Self::InvalidFlags(err)
+    }
+}
+
+impl BatchBuilder {
+    /// An empty batch whose intents all expire at `deadline`.
+    pub fn new(deadline: Timestamp) -> Self {
+        Self {
+            deadline,
+            inner: Vec::new(),
+        }
+    }
+
+    pub fn deadline(&self) -> Timestamp {
+        self.deadline
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    fn push(&mut self, deadline: Timestamp, call: impl SolCall) -> Result<&mut Self, BatchError> {
+        if deadline != self.deadline {
+            return Err(BatchError::DeadlineMismatch {
+                expected: self.deadline,
+                got: deadline,
+            });
+        }
+        if self.inner.len() >= MAX_BATCH_SIZE {
+            return Err(BatchError::Full);
+        }
+        self.inner.push(call.abi_encode().into());
+        Ok(self)
+    }
+
+    pub fn order(&mut self, order: &Order) -> Result<&mut Self, BatchError> {
+        self.push(order.deadline, order.to_call()?)
+    }
+
+    pub fn cancel(&mut self, orderbook_id: Hash, order_id: Hash) -> Result<&mut Self, BatchError> {
+        self.push(
+            self.deadline,
+            IOrderbook::cancelCall {
+                orderbookId: orderbook_id,
+                canceledOrder: order_id,
+                deadline: self.deadline.as_micros(),
+            },
+        )
+    }
+
+    pub fn update(
+        &mut self,
+        orderbook_id: Hash,
+        order_id: Hash,
+        new_size: U256,
+        new_price: U256,
+        token: Address,
+    ) -> Result<&mut Self, BatchError> {
+        self.push(
+            self.deadline,
+            IOrderbook::updateCall {
+                orderbookId: orderbook_id,
+                updatedOrder: order_id,
+                newSize: new_size,
+                newPrice: new_price,
+                token,
+                deadline: self.deadline.as_micros(),
+            },
+        )
+    }
+
+    pub fn trigger(&mut self, trigger: &Trigger) -> Result<&mut Self, BatchError> {
+        self.push(trigger.deadline, trigger.to_call())
+    }
+
+    pub fn cancel_trigger(
+        &mut self,
+        orderbook_id: Hash,
+        trigger_id: Hash,
+    ) -> Result<&mut Self, BatchError> {
+        self.push(
+            self.deadline,
+            IOrderbook::cancelTriggerCall {
+                orderbookId: orderbook_id,
+                triggerOrder: trigger_id,
+                deadline: self.deadline.as_micros(),
+            },
+        )
+    }
+
+    pub fn update_trigger(
+        &mut self,
+        orderbook_id: Hash,
+        trigger_id: Hash,
+        new_size: I256,
+        new_limit_price: U256,
+        new_trigger_price: U256,
+    ) -> Result<&mut Self, BatchError> {
+        self.push(
+            self.deadline,
+            IOrderbook::updateTriggerCall {
+                orderbookId: orderbook_id,
+                triggerOrder: trigger_id,
+                newSize: new_size,
+                newLimitPrice: new_limit_price,
+                newTriggerPrice: new_trigger_price,
+                deadline: self.deadline.as_micros(),
+            },
+        )
+    }
+
+    pub fn deposit(
+        &mut self,
+        token: Address,
+        recipient: Address,
+        amount: U256,
+    ) -> Result<&mut Self, BatchError> {
+        self.push(
+            self.deadline,
+            IOrderbook::depositCall {
+                token,
+                recipient,
+                amount,
+                deadline: self.deadline.as_micros(),
+            },
+        )
+    }
+
+    pub fn withdraw(
+        &mut self,
+        token: Address,
+        recipient: Address,
+        amount: TokenAmount,
+    ) -> Result<&mut Self, BatchError> {
+        self.push(
+            self.deadline,
+            IOrderbook::withdrawCall {
+                token,
+                recipient,
+                amount: amount.to_pod(),
+                deadline: self.deadline.as_micros(),
+            },
+        )
+    }
+
+    /// The `submitBatch` calldata.
+    pub fn calldata(&self) -> Result<Bytes, BatchError> {
+        if self.inner.is_empty() {
+            return Err(BatchError::Empty);
+        }
+        let call = IOrderbook::submitBatchCall {
+            inner: self.inner.clone(),
+        };
+        Ok(call.abi_encode().into())
+    }
+
+    /// The transaction `from` must send with `nonce` for the returned ids to
+    /// match the ones the orderbook assigns.
+    pub fn build(&self, from: Address, nonce: u64) -> Result<Batch, BatchError> {
+        let request = PodTransactionRequest::default()
+            .with_from(from)
+            .with_nonce(nonce)
+            .with_to(ORDERBOOK_PRECOMPILE_ADDRESS)
+            .with_input(self.calldata()?);
+        let order_ids = (0..self.inner.len() as u32)
+            .map(|sequence| intent_id(from, nonce, sequence))
+            .collect();
+        Ok(Batch { request, order_ids })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::orderbook::{abi::IOrderbook::OrderType, OrderFlags};
+
+    fn order(deadline: Timestamp) -> Order {
+        Order {
+            orderbook_id: Hash::left_padding_from(&[7]),
+            size: I256::ONE,
+            price: U256::from(140),
+            order_type: OrderType::Limit,
+            deadline,
+            ttl: Duration::from_secs(60),
+            flags: OrderFlags::NONE,
+        }
+    }
+
+    #[test]
+    fn builds_batch_with_intent_ids() {
+        let deadline = Timestamp::from_seconds(10);
+        let from = Address::repeat_byte(0xaa);
+        let mut builder = BatchBuilder::new(deadline);
+        builder
+            .order(&order(deadline))
+            .unwrap()
+            .cancel(Hash::left_padding_from(&[7]), Hash::repeat_byte(1))
+            .unwrap();
+
+        let batch = builder.build(from, 3).unwrap();
+        assert_eq!(
+            batch.order_ids,
+            vec![intent_id(from, 3, 0), intent_id(from, 3, 1)]
+        );
+
+        let input = batch.request.input().unwrap();
+        let decoded = IOrderbook::submitBatchCall::abi_decode(input).unwrap();
+        assert_eq!(decoded.inner.len(), 2);
+    }
+
+    #[test]
+    fn enforces_batch_invariants() {
+        let deadline = Timestamp::from_seconds(10);
+        let mut builder = BatchBuilder::new(deadline);
+        assert_eq!(
+            builder.build(Address::ZERO, 0).unwrap_err(),
+            BatchError::Empty
+        );
+
+        let other = Timestamp::from_seconds(11);
+        assert_eq!(
+            builder.order(&order(other)).unwrap_err(),
+            BatchError::DeadlineMismatch {
+                expected: deadline,
+                got: other
+            }
+        );
+
+        for _ in 0..MAX_BATCH_SIZE {
+            builder.order(&order(deadline)).unwrap();
+        }
+        assert_eq!(
+            builder.order(&order(deadline)).unwrap_err(),
+            BatchError::Full
+        );
+    }
+}
@@ -0,0 +1,485 @@
+use std::collections::BTreeMap;
+
+use alloy_primitives::U256;
+use alloy_transport::TransportError;
+use pod_types::{
+    rpc::{
+        orderbook::{Candle, CandleResolution, CandlesQuery},
+        subscription::{CandleTick, SubscriptionParams},
+    },
+    wad::Wad,
+    Hash, Timestamp,
+};
+
+use crate::provider::PodProvider;
+
+/// `pod_candles` replays `since` from a ring of about 2.3 hours of batches.
+/// An hour fits, so buckets up to an hour replay whole; longer ones seed their
+/// forming bar from settled hourly bars first.
+const REPLAY_SAFE_SECS: u64 = 60 * 60;
+const SEED_RESOLUTION: CandleResolution = CandleResolution::OneHour;
+/// Bars `ob_getCandles` returns at most.
+const MAX_CANDLES: u64 = 500;
+/// Forming bars kept; older ones are dropped as new buckets open.
+const LIVE_BARS: usize = 8;
+
+/// The bars of one market at one resolution, as the UI draws them.
+///
+/// Settled bars come from `ob_getCandles` in epoch-anchored pages of
+/// [`CandleResolution::page_buckets`], never asking for a bucket still open,
+/// so every client sees the same bars. The forming bar is folded from
+/// `pod_candles` ticks.
+///
+/// Load with [`Self::load_window`], subscribe with the params of
+/// [`Self::live_params`] and [`Self::apply_tick`] each tick. If the
+/// subscription fails, load the window and take the live params again.
+///
+/// Loading is best-effort: a page that fails is reported and left out, and
+/// the next load asks for it again.
+#[derive(Debug, Clone)]
+pub struct CandleSeries {
+    orderbook_id: Hash,
+    resolution: CandleResolution,
+    /// Settled bars of each loaded page, oldest first.
+    pages: BTreeMap<u64, Vec<Candle>>,
+    /// Forming bars by bucket start.
+    live: BTreeMap<Timestamp, Candle>,
+    has_more: bool,
+    last_tick_us: u64,
+}
+
+impl CandleSeries {
+    pub fn new(orderbook_id: Hash, resolution: CandleResolution) -> Self {
+        Self {
+            orderbook_id,
+            resolution,
+            pages: BTreeMap::new(),
+            live: BTreeMap::new(),
+            has_more: true,
+            last_tick_us: 0,
+        }
+    }
+
+    pub fn orderbook_id(&self) -> Hash {
+        self.orderbook_id
+    }
+
+    pub fn resolution(&self) -> CandleResolution {
+        self.resolution
+    }
+
+    /// Whether [`Self::load_older`] may find older bars.
+    pub fn has_more(&self) -> bool {
+        self.has_more
+    }
+
+    /// Settled and forming bars, oldest first. A settled bar wins over a
+    /// forming one of the same bucket.
+    pub fn bars(&self) -> Vec<Candle> {
+        let mut merged: BTreeMap<Timestamp, &Candle> = self
+            .pages
+            .values()
+            .flatten()
+            .map(|bar| (bar.timestamp, bar))
+            .collect();
+        for (start, bar) in &self.live {
+            merged.entry(*start).or_insert(bar);
+        }
+        merged.into_values().cloned().collect()
+    }
+
+    /// Load the pages covering `from` to `to`, or to now. Pages already held
+    /// are kept, except the trailing one, which gains a bar as each bucket
+    /// closes. Returns the pages that failed to load.
+    pub async fn load_window(
+        &mut self,
+        provider: &PodProvider,
+        from: Timestamp,
+        to: Option<Timestamp>,
+    ) -> Vec<(u64, TransportError)> {
+        let now = Timestamp::now();
+        let pages = self.window_pages(from, to.unwrap_or(now), now);
+        self.fetch_pages(provider, pages, now).await
+    }
+
+    /// Load the page before the oldest one held. Returns it if it failed to
+    /// load.
+    pub async fn load_older(&mut self, provider: &PodProvider) -> Vec<(u64, TransportError)> {
+        let Some(&oldest) = self.pages.keys().next() else {
+            return Vec::new();
+        };
+        if oldest == 0 {
+            self.has_more = false;
+            return Vec::new();
+        }
+        self.fetch_pages(provider, vec![oldest - 1], Timestamp::now())
+            .await
+    }
+
+    /// Params to subscribe `pod_candles` from the start of the forming
+    /// bucket, so the node replays its ticks and the bar is whole.
+    ///
+    /// Buckets longer than the replay ring are first seeded from the settled
+    /// hourly bars since their start, and ticks replay from the last of them.
+    /// The seed is best-effort: if it fails, ticks still build the bar from
+    /// there on.
+    pub async fn live_params(&mut self, provider: &PodProvider) -> SubscriptionParams {
+        self.live_params_at(provider, Timestamp::now()).await
+    }
+
+    async fn live_params_at(
+        &mut self,
+        provider: &PodProvider,
+        now: Timestamp,
+    ) -> SubscriptionParams {
+        let bucket_start = self.bucket_of(now);
+        let since = if self.resolution.as_duration().as_secs() <= REPLAY_SAFE_SECS {
+            // The replay rebuilds these bars, which would otherwise count its
+            // ticks twice.
+            self.live.split_off(&bucket_start);
+            bucket_start
+        } else {
+            let seed_end = now.align_down(SEED_RESOLUTION.as_duration());
+            let chunk = SEED_RESOLUTION.as_duration() * MAX_CANDLES as u32;
+            let mut bars = Vec::new();
+            let mut from = bucket_start;
+            while from < seed_end {
+                let to = seed_end.min(from + chunk);
+                let query = CandlesQuery {
+                    resolution: SEED_RESOLUTION,
+                    from_ts: from,
+                    to_ts: Some(to),
+                    limit: Some(MAX_CANDLES),
+                };
+                match provider.get_candles(self.orderbook_id, &query).await {
+                    Ok(chunk) => bars.extend(chunk),
+                    Err(e) => {
+                        // A partial seed would open the bar mid-bucket.
+                        tracing::warn!(%e, "seeding forming candle failed");
+                        bars.clear();
+                        break;
+                    }
+                }
+                from = to;
+            }
+            if let Some(bar) = merge_bars(bucket_start, bars) {
+                self.live.insert(bucket_start, bar);
+            }
+            seed_end
+        };
+        // `since` replays deadlines after it, and a batch may close right on
+        // the boundary.
+        self.last_tick_us = (since.as_micros() as u64).saturating_sub(1);
+        self.subscription_params()
+    }
+
+    /// Params to resubscribe from the last tick applied.
+    pub fn subscription_params(&self) -> SubscriptionParams {
+        SubscriptionParams::default()
+            .with_orderbooks([self.orderbook_id])
+            .with_since(self.last_tick_us)
+    }
+
+    /// Fold a `pod_candles` tick into its forming bar. Ticks of other
+    /// orderbooks, and of books that never cleared, are ignored.
+    pub fn apply_tick(&mut self, tick: &CandleTick) {
+        if tick.orderbook != self.orderbook_id {
+            return;
+        }
+        self.last_tick_us = self.last_tick_us.max(tick.timestamp_us);
+        if tick.price.is_zero() {
+            return;
+        }
+        let start = self.bucket_of(Timestamp::from_micros_u64(tick.timestamp_us));
+        let price = tick.price;
+        let quote = (Wad::from_raw(tick.volume) * Wad::from_raw(price)).raw();
+        self.live
+            .entry(start)
+            .and_modify(|bar| {
+                bar.high = bar.high.max(price);
+                bar.low = bar.low.min(price);
+                bar.close = price;
+                bar.volume += tick.volume;
+                bar.quote_volume += quote;
+            })
+            .or_insert(Candle {
+                timestamp: start,
+                open: price,
+                close: price,
+                high: price,
+                low: price,
+                volume: tick.volume,
+                quote_volume: quote,
+            });
+        while self.live.len() > LIVE_BARS {
+            self.live.pop_first();
+        }
+    }
+
+    fn bucket_of(&self, at: Timestamp) -> Timestamp {
+        at.align_down(self.resolution.as_duration())
+    }
+
+    fn page_span_secs(&self) -> u64 {
+        self.resolution.as_duration().as_secs() * self.resolution.page_buckets()
+    }
+
+    fn page_of(&self, at: Timestamp) -> u64 {
+        at.as_seconds() as u64 / self.page_span_secs()
+    }
+
+    fn window_pages(&self, from: Timestamp, to: Timestamp, now: Timestamp) -> Vec<u64> {
+        let first = self.page_of(from);
+        // `to` is exclusive.
+        let second = std::time::Duration::from_secs(1);
+        let last = self.page_of(to.max(from + second) - second);
+        let trailing = self.page_of(now);
+        (first..=last)
+            .filter(|page| *page == trailing || !self.pages.contains_key(page))
+            .collect()
+    }
+
+    async fn fetch_pages(
+        &mut self,
+        provider: &PodProvider,
+        pages: Vec<u64>,
+        now: Timestamp,
+    ) -> Vec<(u64, TransportError)> {
+        let last_closed = self.bucket_of(now);
+        let mut failed = Vec::new();
+        for page in pages {
+            let (from, to) = self.page_window(page, last_closed);
+            if to <= from {
+                // Only the forming bucket so far.
+                self.pages.entry(page).or_default();
+                continue;
+            }
+            let query = CandlesQuery {
+                resolution: self.resolution,
+                from_ts: from,
+                to_ts: Some(to),
+                limit: Some(self.resolution.page_buckets()),
+            };
+            let mut bars = match provider.get_candles(self.orderbook_id, &query).await {
+                Ok(bars) => bars,
+                Err(e) => {
+                    tracing::warn!(page, %e, "loading candle page failed");
+                    failed.push((page, e));
+                    continue;
+                }
+            };
+            bars.reverse();
+            if bars.is_empty()
+                && self
+                    .pages
+                    .keys()
+                    .next()
+                    .is_none_or(|oldest| page <= *oldest)
+            {
+                self.has_more = false;
+            }
+            self.pages.insert(page, bars);
+        }
+        failed
+    }
+
+    /// The window of `page`, clamped to the last closed bucket so the node
+    /// serves it as settled.
+    fn page_window(&self, page: u64, last_closed: Timestamp) -> (Timestamp, Timestamp) {
+        let span = self.page_span_secs();
+        let from = Timestamp::from_seconds(page * span);
+        let to = Timestamp::from_seconds((page + 1) * span).min(last_closed);
+        (from, to)
+    }
+}
+
+/// One bar starting at `start` out of finer settled `bars`.
+fn merge_bars(start: Timestamp, mut bars: Vec<Candle>) -> Option<Candle> {
+    bars.sort_by_key(|bar| bar.timestamp);
+    let first = bars.first()?;
+    let last = bars.last()?;
+    Some(Candle {
+        timestamp: start,
+        open: first.open,
+        close: last.close,
+        high: bars.iter().map(|bar| bar.high).max()?,
+        low: bars.iter().map(|bar| bar.low).min()?,
+        volume: bars
+            .iter()
+            .map(|bar| bar.volume)
+            .fold(U256::ZERO, |a, b| a + b),
+        quote_volume: bars
+            .iter()
+            .map(|bar| bar.quote_volume)
+            .fold(U256::ZERO, |a, b| a + b),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_transport::mock::Asserter;
+
+    use super::*;
+    use crate::test_utils::provider;
+
+    const BOOK: Hash = Hash::repeat_byte(0x01);
+
+    fn tick(seconds: u64, price: u64, volume: u64) -> CandleTick {
+        CandleTick {
+            orderbook: BOOK,
+            timestamp_us: seconds * 1_000_000,
+            price: Wad::from_integer(price).raw(),
+            volume: Wad::from_integer(volume).raw(),
+        }
+    }
+
+    #[test]
+    fn pages_and_forming_bars() {
+        let mut series = CandleSeries::new(BOOK, CandleResolution::OneMinute);
+        // Pages of 360 minutes.
+        let now = Timestamp::from_seconds(3 * 21_600 + 90);
+        assert_eq!(
+            series.window_pages(Timestamp::from_seconds(21_600 - 1), now, now),
+            vec![0, 1, 2, 3]
+        );
+        // Up to the last closed bucket, not into the forming one.
+        assert_eq!(
+            series.page_window(3, series.bucket_of(now)),
+            (
+                Timestamp::from_seconds(3 * 21_600),
+                Timestamp::from_seconds(3 * 21_600 + 60)
+            )
+        );
+        // An empty window at the epoch still names its page.
+        assert_eq!(
+            series.window_pages(Timestamp::from_seconds(0), Timestamp::from_seconds(0), now),
+            vec![0]
+        );
+        series.pages.insert(1, Vec::new());
+        series.pages.insert(3, Vec::new());
+        assert_eq!(
+            series.window_pages(Timestamp::from_seconds(21_600), now, now),
+            vec![2, 3]
+        );
+
+        series.apply_tick(&tick(119, 10, 1));
+        series.apply_tick(&tick(120, 12, 2));
+        series.apply_tick(&tick(130, 0, 5));
+        series.apply_tick(&tick(150, 11, 1));
+        let bars = series.bars();
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0].timestamp, Timestamp::from_seconds(60));
+        assert_eq!(bars[1].timestamp, Timestamp::from_seconds(120));
+        assert_eq!(bars[1].open, Wad::from_integer(12).raw());
+        assert_eq!(bars[1].low, Wad::from_integer(11).raw());
+        assert_eq!(bars[1].close, Wad::from_integer(11).raw());
+        assert_eq!(bars[1].volume, Wad::from_integer(3).raw());
+        assert_eq!(bars[1].quote_volume, Wad::from_integer(35).raw());
+        assert_eq!(series.subscription_params().since, Some(150_000_000));
+
+        // A settled bar replaces the forming one.
+        let mut settled = bars[0].clone();
+        settled.close = Wad::from_integer(9).raw();
+        series.pages.insert(0, vec![settled.clone()]);
+        assert_eq!(series.bars()[0], settled);
+    }
+
+    #[test]
+    fn seeds_from_hourly_bars() {
+        let bar = |hour: u64, open: u64, close: u64| Candle {
+            timestamp: Timestamp::from_seconds(hour * 3_600),
+            open: U256::from(open),
+            close: U256::from(close),
+            high: U256::from(open.max(close)),
+            low: U256::from(open.min(close)),
+            volume: U256::from(1),
+            quote_volume: U256::from(open),
+        };
+        let start = Timestamp::from_seconds(4 * 3_600);
+        let merged = merge_bars(start, vec![bar(5, 7, 3), bar(4, 5, 7)]).unwrap();
+        assert_eq!(merged.timestamp, start);
+        assert_eq!(
+            (merged.open, merged.close, merged.high, merged.low),
+            (U256::from(5), U256::from(3), U256::from(7), U256::from(3))
+        );
+        assert_eq!(merged.volume, U256::from(2));
+        assert_eq!(merged.quote_volume, U256::from(12));
+        assert_eq!(merge_bars(start, Vec::new()), None);
+    }
+
+    fn bar(seconds: u64) -> Candle {
+        Candle {
+            timestamp: Timestamp::from_seconds(seconds),
+            open: U256::from(1),
+            close: U256::from(1),
+            high: U256::from(1),
+            low: U256::from(1),
+            volume: U256::from(1),
+            quote_volume: U256::from(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn replay_rebuilds_the_forming_bar() {
+        let asserter = Asserter::new();
+        let mut series = CandleSeries::new(BOOK, CandleResolution::OneMinute);
+        let bucket_start = 1_770_000_000;
+        let now = bucket_start + 30;
+        series.apply_tick(&tick(now - 3_600, 10, 1));
+        series.apply_tick(&tick(now, 11, 1));
+        series.apply_tick(&tick(now + 3_600, 12, 1));
+
+        let params = series
+            .live_params_at(&provider(&asserter), Timestamp::from_seconds(now))
+            .await;
+        let bars = series.bars();
+        assert_eq!(bars.len(), 1);
+        assert_eq!(bars[0].open, Wad::from_integer(10).raw());
+        assert_eq!(params.since, Some(bucket_start * 1_000_000 - 1));
+    }
+
+    #[tokio::test]
+    async fn keeps_the_pages_that_loaded() {
+        let asserter = Asserter::new();
+        let mut series = CandleSeries::new(BOOK, CandleResolution::OneMinute);
+        let now = Timestamp::from_seconds(3 * 21_600 + 90);
+        asserter.push_success(&vec![bar(0)]);
+        asserter.push_failure_msg("unavailable");
+        asserter.push_success(&vec![bar(2 * 21_600)]);
+        asserter.push_success(&vec![bar(3 * 21_600)]);
+
+        let failed = series
+            .fetch_pages(&provider(&asserter), vec![0, 1, 2, 3], now)
+            .await;
+        assert_eq!(
+            failed.iter().map(|(page, _)| *page).collect::<Vec<_>>(),
+            vec![1]
+        );
+        assert_eq!(series.pages.keys().copied().collect::<Vec<_>>(), [0, 2, 3]);
+        assert_eq!(series.bars().len(), 3);
+        // The failed page is asked for again.
+        assert_eq!(
+            series.window_pages(Timestamp::from_seconds(0), now, now),
+            vec![1, 3]
+        );
+    }
+
+    #[tokio::test]
+    async fn a_failed_seed_leaves_the_bar_to_ticks() {
+        let asserter = Asserter::new();
+        let mut series = CandleSeries::new(BOOK, CandleResolution::OneDay);
+        let now = 20_000 * 86_400 + 5 * 3_600 + 30;
+        asserter.push_failure_msg("unavailable");
+
+        let params = series
+            .live_params_at(&provider(&asserter), Timestamp::from_seconds(now))
+            .await;
+        assert!(series.bars().is_empty());
+        assert!(asserter.read_q().is_empty());
+        // Ticks still replay from the end of the seed window.
+        assert_eq!(
+            params.since,
+            Some((20_000 * 86_400 + 5 * 3_600) * 1_000_000 - 1)
+        );
+    }
+}
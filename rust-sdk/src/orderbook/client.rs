@@ -0,0 +1,419 @@
+use std::time::Duration;
+
+use alloy_primitives::{Address, Bytes, I256, U256};
+use alloy_sol_types::SolCall;
+use anyhow::Context;
+use pod_types::{rpc::receipt::PodReceiptResponse, Hash, Timestamp};
+
+use super::{
+    abi::IOrderbook::{self, OrderType},
+    batch::{Batch, BatchBuilder},
+    trigger::Trigger,
+};
+use crate::{
+    bridge::TokenAmount, network::PodTransactionRequest, precompiles::ORDERBOOK_PRECOMPILE_ADDRESS,
+    provider::PodProvider, Provider, TransactionBuilder,
+};
+
+/// The `uint8 flags` bitfield of `submitOrder`. Combine with `|`;
+/// [`OrderFlags::NONE`] is a plain resting limit order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct OrderFlags(u8);
+
+impl OrderFlags {
+    pub const NONE: Self = Self(0);
+    /// May only reduce the submitter's existing position. Perp markets only.
+    pub const REDUCE_ONLY: Self = Self(0x01);
+    /// Whatever does not match in the order's batch is cancelled at its end.
+    pub const IOC: Self = Self(0x02);
+    /// Rests on the book but may not trade in the batch that admitted it.
+    pub const POST_ONLY: Self = Self(0x04);
+
+    const KNOWN: u8 = 0x07;
+
+    /// Wrap raw bits. Unknown bits are kept so [`Self::validate`] can reject them.
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Apply the combination rules validators enforce, so an invalid order is
+    /// refused before it is signed rather than reverting after.
+    pub fn validate(self, order_type: OrderType) -> Result<(), OrderFlagsError> {
+        if self.0 & !Self::KNOWN != 0 {
+            return Err(OrderFlagsError::UnknownBits(self.0 & !Self::KNOWN));
+        }
+        if self.contains(Self::IOC | Self::POST_ONLY) {
+            return Err(OrderFlagsError::PostOnlyIoc);
+        }
+        if order_type == OrderType::Market {
+            if self.contains(Self::POST_ONLY) {
+                return Err(OrderFlagsError::PostOnlyMarket);
+            }
+            if !self.contains(Self::IOC) {
+                return Err(OrderFlagsError::MarketWithoutIoc);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::ops::BitOr for OrderFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for OrderFlags {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// A flag combination the orderbook rejects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderFlagsError {
+    /// Bits 3–7 are unassigned and must be zero.
+    UnknownBits(u8),
+    PostOnlyIoc,
+    PostOnlyMarket,
+    MarketWithoutIoc,
+}
+
+impl std::fmt::Display for OrderFlagsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownBits(bits) => write!(f, "unknown order flag bits {bits:#04x}"),
+            Self::PostOnlyIoc => write!(f, "post-only order cannot be immediate-or-cancel"),
+            Self::PostOnlyMarket => write!(f, "post-only is not valid for a market order"),
+            Self::MarketWithoutIoc => write!(f, "market orders must be immediate-or-cancel"),
+        }
+    }
+}
+
+impl std::error::Error for OrderFlagsError {}
+
+/// A new order, as passed to `submitOrder`.
+#[derive(Debug, Clone)]
+pub struct Order {
+    pub orderbook_id: Hash,
+    /// Positive to buy, negative to sell.
+    pub size: I256,
+    pub price: U256,
+    pub order_type: OrderType,
+    /// Latest batch the order may be included in. Must be aligned to the
+    /// market's `auction_interval`.
+    pub deadline: Timestamp,
+    /// How long the order rests on the book.
+    pub ttl: Duration,
+    pub flags: OrderFlags,
+}
+
+impl Order {
+    /// The `submitOrder` call for this order, after checking its flags.
+    pub fn to_call(&self) -> Result<IOrderbook::submitOrderCall, OrderFlagsError> {
+        self.flags.validate(self.order_type)?;
+        Ok(IOrderbook::submitOrderCall {
+            orderbookId: self.orderbook_id,
+            size: self.size,
+            price: self.price,
+            orderType: self.order_type,
+            deadline: self.deadline.as_micros(),
+            ttl: self.ttl.as_micros(),
+            flags: self.flags.bits(),
+        })
+    }
+}
+
+pub struct OrderbookClient {
+    pub provider: PodProvider,
+    pub address: Address,
+}
+
+impl OrderbookClient {
+    /// A client for the orderbook precompile.
+    pub fn new(provider: PodProvider) -> Self {
+        Self::with_address(provider, ORDERBOOK_PRECOMPILE_ADDRESS)
+    }
+
+    pub fn with_address(provider: PodProvider, address: Address) -> Self {
+        OrderbookClient { provider, address }
+    }
+
+    fn request(&self, call: &impl SolCall) -> PodTransactionRequest {
+        PodTransactionRequest::default()
+            .with_to(self.address)
+            .with_input(Bytes::from(call.abi_encode()))
+    }
+
+    async fn send(&self, call: &impl SolCall) -> anyhow::Result<PodReceiptResponse> {
+        self.send_request(self.request(call), call_name(call)).await
+    }
+
+    async fn send_request(
+        &self,
+        request: PodTransactionRequest,
+        name: &str,
+    ) -> anyhow::Result<PodReceiptResponse> {
+        let pending_tx = self
+            .provider
+            .send_transaction(request)
+            .await
+            .with_context(|| format!("sending {name} TX"))?;
+
+        let receipt = self
+            .provider
+            .get_verified_receipt(pending_tx)
+            .await
+            .with_context(|| format!("awaiting for {name} TX confirmation"))?;
+
+        anyhow::ensure!(receipt.status(), "{name} TX reverted");
+        Ok(receipt)
+    }
+
+    async fn view<C: SolCall>(&self, call: &C) -> anyhow::Result<C::Return> {
+        let output = self
+            .provider
+            .call(self.request(call))
+            .await
+            .with_context(|| format!("calling {}", call_name(call)))?;
+        C::abi_decode_returns(&output).with_context(|| format!("decoding {}", call_name(call)))
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn submit_order(&self, order: &Order) -> anyhow::Result<PodReceiptResponse> {
+        let call = order.to_call()?;
+        self.send(&call).await
+    }
+
+    /// Cancel the order identified by `order_id`, which is not the tx hash of
+    /// the `submitOrder` that placed it.
+    #[tracing::instrument(skip(self))]
+    pub async fn cancel(
+        &self,
+        orderbook_id: Hash,
+        order_id: Hash,
+        deadline: Timestamp,
+    ) -> anyhow::Result<PodReceiptResponse> {
+        self.send(&IOrderbook::cancelCall {
+            orderbookId: orderbook_id,
+            canceledOrder: order_id,
+            deadline: deadline.as_micros(),
+        })
+        .await
+    }
+
+    /// Change the size and price of a resting order. `token` covers any
+    /// additional collateral the update requires.
+    #[tracing::instrument(skip(self))]
+    pub async fn update(
+        &self,
+        orderbook_id: Hash,
+        order_id: Hash,
+        new_size: U256,
+        new_price: U256,
+        token: Address,
+        deadline: Timestamp,
+    ) -> anyhow::Result<PodReceiptResponse> {
+        self.send(&IOrderbook::updateCall {
+            orderbookId: orderbook_id,
+            updatedOrder: order_id,
+            newSize: new_size,
+            newPrice: new_price,
+            token,
+            deadline: deadline.as_micros(),
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn deposit(
+        &self,
+        token: Address,
+        recipient: Address,
+        amount: U256,
+        deadline: Timestamp,
+    ) -> anyhow::Result<PodReceiptResponse> {
+        self.send(&IOrderbook::depositCall {
+            token,
+            recipient,
+            amount,
+            deadline: deadline.as_micros(),
+        })
+        .await
+    }
+
+    /// Withdraw `amount` of `token` from this account's orderbook balance to
+    /// `recipient`, an address on the bridge's claim chain. The balance is
+    /// burned on pod; nothing is credited to a pod account.
+    ///
+    /// The receipt only means the request was accepted. The withdrawal becomes
+    /// claimable once a tick settles it and its certificate can be assembled,
+    /// or is refused with nothing debited if the balance no longer covers it.
+    /// [`WithdrawalTracker`](crate::bridge::WithdrawalTracker) follows it from
+    /// there to the claim.
+    #[tracing::instrument(skip(self))]
+    pub async fn withdraw(
+        &self,
+        token: Address,
+        recipient: Address,
+        amount: TokenAmount,
+        deadline: Timestamp,
+    ) -> anyhow::Result<PodReceiptResponse> {
+        self.send(&IOrderbook::withdrawCall {
+            token,
+            recipient,
+            amount: amount.to_pod(),
+            deadline: deadline.as_micros(),
+        })
+        .await
+    }
+
+    /// Arm a take-profit / stop-loss trigger. Perp markets only.
+    #[tracing::instrument(skip(self))]
+    pub async fn submit_trigger(&self, trigger: &Trigger) -> anyhow::Result<PodReceiptResponse> {
+        self.send(&trigger.to_call()).await
+    }
+
+    /// Cancel the trigger identified by `trigger_id`, its `order_id` rather
+    /// than the `submitTrigger` tx hash.
+    #[tracing::instrument(skip(self))]
+    pub async fn cancel_trigger(
+        &self,
+        orderbook_id: Hash,
+        trigger_id: Hash,
+        deadline: Timestamp,
+    ) -> anyhow::Result<PodReceiptResponse> {
+        self.send(&IOrderbook::cancelTriggerCall {
+            orderbookId: orderbook_id,
+            triggerOrder: trigger_id,
+            deadline: deadline.as_micros(),
+        })
+        .await
+    }
+
+    /// Change an armed trigger. Its grouping cannot be changed.
+    #[tracing::instrument(skip(self))]
+    pub async fn update_trigger(
+        &self,
+        orderbook_id: Hash,
+        trigger_id: Hash,
+        new_size: I256,
+        new_limit_price: U256,
+        new_trigger_price: U256,
+        deadline: Timestamp,
+    ) -> anyhow::Result<PodReceiptResponse> {
+        self.send(&IOrderbook::updateTriggerCall {
+            orderbookId: orderbook_id,
+            triggerOrder: trigger_id,
+            newSize: new_size,
+            newLimitPrice: new_limit_price,
+            newTriggerPrice: new_trigger_price,
+            deadline: deadline.as_micros(),
+        })
+        .await
+    }
+
+    /// Send every intent of `batch` from `from` in one atomic transaction.
+    /// Returns the receipt along with the id of each intent, as
+    /// [`Batch::order_ids`] lists them.
+    #[tracing::instrument(skip(self, batch))]
+    pub async fn submit_batch(
+        &self,
+        batch: &BatchBuilder,
+        from: Address,
+    ) -> anyhow::Result<(PodReceiptResponse, Vec<Hash>)> {
+        let nonce = self
+            .provider
+            .get_transaction_count(from)
+            .pending()
+            .await
+            .context("fetching nonce")?;
+        let Batch {
+            mut request,
+            order_ids,
+        } = batch.build(from, nonce)?;
+        request.set_to(self.address);
+
+        let receipt = self.send_request(request, "submitBatch").await?;
+        Ok((receipt, order_ids))
+    }
+
+    /// Balance of `account` in `token`. Negative for an underwater native USD
+    /// balance.
+    pub async fn balance_of(&self, token: Address, account: Address) -> anyhow::Result<I256> {
+        self.view(&IOrderbook::balanceOfCall { token, account })
+            .await
+    }
+
+    pub async fn withdrawable_balance(
+        &self,
+        token: Address,
+        account: Address,
+    ) -> anyhow::Result<U256> {
+        self.view(&IOrderbook::withdrawableBalanceCall { token, account })
+            .await
+    }
+}
+
+fn call_name<C: SolCall>(_: &C) -> &'static str {
+    C::SIGNATURE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_flag_combinations() {
+        use OrderFlags as F;
+
+        assert!(F::NONE.validate(OrderType::Limit).is_ok());
+        assert!((F::REDUCE_ONLY | F::POST_ONLY)
+            .validate(OrderType::Limit)
+            .is_ok());
+        assert_eq!(
+            (F::IOC | F::POST_ONLY).validate(OrderType::Limit),
+            Err(OrderFlagsError::PostOnlyIoc)
+        );
+        assert_eq!(
+            F::POST_ONLY.validate(OrderType::Market),
+            Err(OrderFlagsError::PostOnlyMarket)
+        );
+        assert_eq!(
+            F::NONE.validate(OrderType::Market),
+            Err(OrderFlagsError::MarketWithoutIoc)
+        );
+        assert!((F::IOC | F::REDUCE_ONLY)
+            .validate(OrderType::Market)
+            .is_ok());
+        assert_eq!(
+            F::from_bits(0x09).validate(OrderType::Limit),
+            Err(OrderFlagsError::UnknownBits(0x08))
+        );
+    }
+
+    #[test]
+    fn encodes_current_submit_order_overload() {
+        let order = Order {
+            orderbook_id: Hash::left_padding_from(&[1]),
+            size: I256::ONE,
+            price: U256::from(5000),
+            order_type: OrderType::Limit,
+            deadline: Timestamp::from_micros(1_000_000),
+            ttl: Duration::from_secs(60),
+            flags: OrderFlags::POST_ONLY,
+        };
+        let calldata = order.to_call().unwrap().abi_encode();
+        assert_eq!(calldata[..4], [0x1e, 0x41, 0x62, 0x75]);
+    }
+}
@@ -0,0 +1,491 @@
+//! Key delegation: a master account authorizes an ephemeral key with one
+//! off-chain EIP-712 signature, and that key then performs orderbook calls on
+//! the master's behalf by wrapping them in the gas-exempt `delegated` envelope.
+//!
+//! The master signs `DelegationAuth { delegate, validUntil }` under the domain
+//! `{ name: "pod delegation", version: "1", chainId }`. The resulting signature
+//! travels in every delegated transaction; there is no on-chain registration
+//! and no revocation, so keep `validUntil` short.
+
+use alloy_consensus::{SignableTransaction, Transaction as _, TxEnvelope, TypedTransaction};
+use alloy_network::{NetworkWallet, TxSigner};
+use alloy_primitives::{Address, Bytes, Signature, B256};
+use alloy_signer_local::PrivateKeySigner;
+use alloy_sol_types::{eip712_domain, SolCall, SolStruct};
+use pod_types::Timestamp;
+
+use super::abi::IOrderbook;
+use crate::{network::PodNetwork, precompiles::ORDERBOOK_PRECOMPILE_ADDRESS};
+
+mod sol {
+    alloy_sol_types::sol! {
+        #[derive(Debug)]
+        struct DelegationAuth {
+            address delegate;
+            uint64 validUntil;
+        }
+
+        function delegated(address master, uint64 validUntil, bytes signature, bytes inner);
+    }
+}
+
+/// The digest a master signs to authorize `delegate` until `valid_until`.
+pub fn delegation_signing_hash(
+    delegate: Address,
+    valid_until: Timestamp,
+    chain_id: u64,
+) -> Result<B256, DelegationError> {
+    let auth = sol::DelegationAuth {
+        delegate,
+        validUntil: envelope_micros(valid_until)?,
+    };
+    Ok(auth.eip712_signing_hash(&eip712_domain! {
+        name: "pod delegation",
+        version: "1",
+        chain_id: chain_id,
+    }))
+}
+
+// `validUntil` is a `uint64` of microseconds.
+fn envelope_micros(valid_until: Timestamp) -> Result<u64, DelegationError> {
+    u64::try_from(valid_until.as_micros())
+        .map_err(|_| DelegationError::ValidUntilOutOfRange { valid_until })
+}
+
+#[derive(Debug)]
+pub enum DelegationError {
+    /// The certificate was not signed by the master it names.
+    WrongSigner {
+        expected: Address,
+        got: Address,
+    },
+    InvalidSignature(alloy_primitives::SignatureError),
+    Expired {
+        valid_until: Timestamp,
+    },
+    /// The expiry is past what the envelope's `uint64` of microseconds holds.
+    ValidUntilOutOfRange {
+        valid_until: Timestamp,
+    },
+    /// An inner intent outlives the certificate, so the node would reject it.
+    DeadlineTooLate {
+        deadline: Timestamp,
+        valid_until: Timestamp,
+    },
+    /// The call is not a deadline-bearing orderbook intent or `submitBatch`;
+    /// this covers `submitSolutions`, `createOrderBook`, nested `delegated`
+    /// and views.
+    NotDelegatable([u8; 4]),
+    /// Delegated transactions may only target the orderbook.
+    WrongTarget(Option<Address>),
+    UnsupportedTransaction,
+}
+
+impl std::fmt::Display for DelegationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::WrongSigner { expected, got } => {
+                write!(f, "delegation signed by {got}, not by master {expected}")
+            }
+            Self::InvalidSignature(e) => write!(f, "invalid delegation signature: {e}"),
+            Self::Expired { valid_until } => write!(f, "delegation expired at {valid_until}"),
+            Self::ValidUntilOutOfRange { valid_until } => {
+                write!(f, "delegation expiry {valid_until} does not fit in uint64")
+            }
+            Self::DeadlineTooLate {
+                deadline,
+                valid_until,
+            } => write!(
+                f,
+                "intent deadline {deadline} is past delegation expiry {valid_until}"
+            ),
+            Self::NotDelegatable(selector) => write!(
+                f,
+                "call with selector 0x{} cannot be delegated",
+                hex::encode(selector)
+            ),
+            Self::WrongTarget(Some(to)) => {
+                write!(
+                    f,
+                    "delegated transactions must call the orderbook, not {to}"
+                )
+            }
+            Self::WrongTarget(None) => write!(f, "delegated transactions cannot create contracts"),
+            Self::UnsupportedTransaction => write!(f, "unsupported transaction type"),
+        }
+    }
+}
+
+impl std::error::Error for DelegationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidSignature(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// An ephemeral delegate key waiting for the master's signature, for masters
+/// that sign outside this process (hardware wallets, browser wallets).
+#[derive(Debug)]
+pub struct DelegationRequest {
+    master: Address,
+    chain_id: u64,
+    valid_until: Timestamp,
+    delegate: PrivateKeySigner,
+}
+
+impl DelegationRequest {
+    /// Generate a fresh delegate key for `master`.
+    pub fn new(master: Address, chain_id: u64, valid_until: Timestamp) -> Self {
+        Self {
+            master,
+            chain_id,
+            valid_until,
+            delegate: PrivateKeySigner::random(),
+        }
+    }
+
+    pub fn delegate(&self) -> Address {
+        self.delegate.address()
+    }
+
+    /// The digest the master has to sign.
+    pub fn signing_hash(&self) -> Result<B256, DelegationError> {
+        delegation_signing_hash(self.delegate(), self.valid_until, self.chain_id)
+    }
+
+    /// Complete the delegation with the master's signature, checking that it
+    /// really comes from the master.
+    pub fn into_wallet(self, signature: Signature) -> Result<DelegatedWallet, DelegationError> {
+        let signer = signature
+            .recover_address_from_prehash(&self.signing_hash()?)
+            .map_err(DelegationError::InvalidSignature)?;
+        if signer != self.master {
+            return Err(DelegationError::WrongSigner {
+                expected: self.master,
+                got: signer,
+            });
+        }
+        Ok(DelegatedWallet {
+            master: self.master,
+            valid_until: self.valid_until,
+            signature: Bytes::copy_from_slice(&signature.as_bytes()),
+            orderbook: ORDERBOOK_PRECOMPILE_ADDRESS,
+            delegate: self.delegate,
+        })
+    }
+}
+
+/// A wallet that signs orderbook transactions with an ephemeral delegate key,
+/// wrapping each in the `delegated` envelope so it acts for the master.
+///
+/// The delegate key is generated in-process and never exposed; dropping the
+/// wallet ends the session. Only calls to the orderbook are signed, and only
+/// those the envelope accepts with deadlines the certificate covers.
+#[derive(Debug)]
+pub struct DelegatedWallet {
+    master: Address,
+    valid_until: Timestamp,
+    signature: Bytes,
+    orderbook: Address,
+    delegate: PrivateKeySigner,
+}
+
+impl DelegatedWallet {
+    /// Generate a delegate key and have `master` authorize it until
+    /// `valid_until`.
+    pub async fn authorize<S>(
+        master: &S,
+        chain_id: u64,
+        valid_until: Timestamp,
+    ) -> Result<Self, alloy_signer::Error>
+    where
+        S: alloy_signer::Signer + ?Sized,
+    {
+        let request = DelegationRequest::new(master.address(), chain_id, valid_until);
+        let digest = request.signing_hash().map_err(alloy_signer::Error::other)?;
+        let signature = master.sign_hash(&digest).await?;
+        request
+            .into_wallet(signature)
+            .map_err(alloy_signer::Error::other)
+    }
+
+    /// Use an orderbook deployed somewhere other than the precompile address.
+    pub fn with_orderbook(mut self, orderbook: Address) -> Self {
+        self.orderbook = orderbook;
+        self
+    }
+
+    pub fn master(&self) -> Address {
+        self.master
+    }
+
+    /// Address of the ephemeral key, which signs and sends the transactions.
+    pub fn delegate(&self) -> Address {
+        self.delegate.address()
+    }
+
+    pub fn valid_until(&self) -> Timestamp {
+        self.valid_until
+    }
+
+    pub fn is_expired(&self, now: Timestamp) -> bool {
+        now >= self.valid_until
+    }
+
+    /// Wrap orderbook calldata in the `delegated` envelope.
+    pub fn wrap(&self, inner: &[u8], now: Timestamp) -> Result<Bytes, DelegationError> {
+        if self.is_expired(now) {
+            return Err(DelegationError::Expired {
+                valid_until: self.valid_until,
+            });
+        }
+        for deadline in intent_deadlines(inner, true)? {
+            if deadline > self.valid_until {
+                return Err(DelegationError::DeadlineTooLate {
+                    deadline,
+                    valid_until: self.valid_until,
+                });
+            }
+        }
+        let call = sol::delegatedCall {
+            master: self.master,
+            validUntil: envelope_micros(self.valid_until)?,
+            signature: self.signature.clone(),
+            inner: Bytes::copy_from_slice(inner),
+        };
+        Ok(call.abi_encode().into())
+    }
+
+    fn wrap_tx(&self, tx: &mut TypedTransaction) -> Result<(), DelegationError> {
+        let to = tx.to();
+        if to != Some(self.orderbook) {
+            return Err(DelegationError::WrongTarget(to));
+        }
+        let TypedTransaction::Eip1559(tx) = tx else {
+            return Err(DelegationError::UnsupportedTransaction);
+        };
+        let input = &mut tx.input;
+        *input = self.wrap(input, Timestamp::now())?;
+        Ok(())
+    }
+}
+
+impl NetworkWallet<PodNetwork> for DelegatedWallet {
+    fn default_signer_address(&self) -> Address {
+        self.delegate()
+    }
+
+    fn has_signer_for(&self, address: &Address) -> bool {
+        *address == self.delegate()
+    }
+
+    fn signer_addresses(&self) -> impl Iterator<Item = Address> {
+        std::iter::once(self.delegate())
+    }
+
+    async fn sign_transaction_from(
+        &self,
+        sender: Address,
+        mut tx: TypedTransaction,
+    ) -> alloy_signer::Result<TxEnvelope> {
+        if sender != self.delegate() {
+            return Err(alloy_signer::Error::other(format!(
+                "Missing signing credential for {sender}"
+            )));
+        }
+        self.wrap_tx(&mut tx).map_err(alloy_signer::Error::other)?;
+        let signature = self.delegate.sign_transaction(&mut tx).await?;
+        Ok(tx.into_signed(signature).into())
+    }
+}
+
+// Deadlines of the intents in `calldata`, refusing anything the envelope
+// rejects. A batch is accepted only at the top level.
+fn intent_deadlines(calldata: &[u8], allow_batch: bool) -> Result<Vec<Timestamp>, DelegationError> {
+    use IOrderbook as I;
+
+    let selector: [u8; 4] = calldata
+        .get(..4)
+        .and_then(|s| s.try_into().ok())
+        .ok_or(DelegationError::NotDelegatable([0; 4]))?;
+    let not_delegatable = |_| DelegationError::NotDelegatable(selector);
+
+    let deadline = match selector {
+        I::submitBatchCall::SELECTOR if allow_batch => {
+            let batch = I::submitBatchCall::abi_decode(calldata).map_err(not_delegatable)?;
+            let mut deadlines = Vec::with_capacity(batch.inner.len());
+            for inner in &batch.inner {
+                deadlines.extend(intent_deadlines(inner, false)?);
+            }
+            return Ok(deadlines);
+        }
+        I::submitOrderCall::SELECTOR => {
+            I::submitOrderCall::abi_decode(calldata).map(|c| c.deadline)
+        }
+        I::cancelCall::SELECTOR => I::cancelCall::abi_decode(calldata).map(|c| c.deadline),
+        I::updateCall::SELECTOR => I::updateCall::abi_decode(calldata).map(|c| c.deadline),
+        I::submitTriggerCall::SELECTOR => {
+            I::submitTriggerCall::abi_decode(calldata).map(|c| c.deadline)
+        }
+        I::cancelTriggerCall::SELECTOR => {
+            I::cancelTriggerCall::abi_decode(calldata).map(|c| c.deadline)
+        }
+        I::updateTriggerCall::SELECTOR => {
+            I::updateTriggerCall::abi_decode(calldata).map(|c| c.deadline)
+        }
+        I::depositCall::SELECTOR => I::depositCall::abi_decode(calldata).map(|c| c.deadline),
+        I::withdrawCall::SELECTOR => I::withdrawCall::abi_decode(calldata).map(|c| c.deadline),
+        _ => return Err(DelegationError::NotDelegatable(selector)),
+    }
+    .map_err(not_delegatable)?;
+    Ok(vec![Timestamp::from_micros(deadline)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_consensus::{transaction::SignerRecoverable, TxEip1559, TxLegacy};
+    use alloy_primitives::TxKind;
+
+    const CHAIN_ID: u64 = 0x50d;
+
+    fn cancel(deadline: Timestamp) -> Vec<u8> {
+        IOrderbook::cancelCall {
+            orderbookId: B256::repeat_byte(7),
+            canceledOrder: B256::repeat_byte(1),
+            deadline: deadline.as_micros(),
+        }
+        .abi_encode()
+    }
+
+    async fn wallet(valid_until: Timestamp) -> (PrivateKeySigner, DelegatedWallet) {
+        let master = PrivateKeySigner::random();
+        let wallet = DelegatedWallet::authorize(&master, CHAIN_ID, valid_until)
+            .await
+            .unwrap();
+        (master, wallet)
+    }
+
+    #[tokio::test]
+    async fn wraps_and_signs_with_delegate() {
+        let valid_until = Timestamp::now() + std::time::Duration::from_secs(3600);
+        let (master, wallet) = wallet(valid_until).await;
+
+        let inner = cancel(Timestamp::now());
+        let tx = TypedTransaction::Eip1559(TxEip1559 {
+            chain_id: CHAIN_ID,
+            to: TxKind::Call(ORDERBOOK_PRECOMPILE_ADDRESS),
+            input: inner.clone().into(),
+            ..Default::default()
+        });
+        let envelope = wallet
+            .sign_transaction_from(wallet.delegate(), tx)
+            .await
+            .unwrap();
+        assert_eq!(envelope.recover_signer().unwrap(), wallet.delegate());
+
+        let call =
+            sol::delegatedCall::abi_decode(envelope.as_eip1559().unwrap().tx().input.as_ref())
+                .unwrap();
+        assert_eq!(call.master, master.address());
+        assert_eq!(call.inner.as_ref(), inner.as_slice());
+
+        let signature = Signature::try_from(call.signature.as_ref()).unwrap();
+        let digest = delegation_signing_hash(wallet.delegate(), valid_until, CHAIN_ID).unwrap();
+        assert_eq!(
+            signature.recover_address_from_prehash(&digest).unwrap(),
+            master.address()
+        );
+    }
+
+    #[tokio::test]
+    async fn refuses_what_cannot_be_delegated() {
+        let now = Timestamp::from_seconds(100);
+        let valid_until = Timestamp::from_seconds(200);
+        let (_, wallet) = wallet(valid_until).await;
+
+        assert!(wallet.wrap(&cancel(now), now).is_ok());
+        assert!(matches!(
+            wallet.wrap(&cancel(Timestamp::from_seconds(300)), now),
+            Err(DelegationError::DeadlineTooLate { .. })
+        ));
+        assert!(matches!(
+            wallet.wrap(&cancel(now), valid_until),
+            Err(DelegationError::Expired { .. })
+        ));
+
+        let nested = wallet.wrap(&cancel(now), now).unwrap();
+        assert!(matches!(
+            wallet.wrap(&nested, now),
+            Err(DelegationError::NotDelegatable(_))
+        ));
+
+        let view = IOrderbook::balanceOfCall {
+            token: Address::ZERO,
+            account: Address::ZERO,
+        }
+        .abi_encode();
+        assert!(matches!(
+            wallet.wrap(&view, now),
+            Err(DelegationError::NotDelegatable(_))
+        ));
+
+        let batch = IOrderbook::submitBatchCall {
+            inner: vec![cancel(now).into()],
+        }
+        .abi_encode();
+        assert!(wallet.wrap(&batch, now).is_ok());
+        let nested_batch = IOrderbook::submitBatchCall {
+            inner: vec![batch.into()],
+        }
+        .abi_encode();
+        assert!(matches!(
+            wallet.wrap(&nested_batch, now),
+            Err(DelegationError::NotDelegatable(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn refuses_legacy_transactions_and_unencodable_expiry() {
+        let (_, wallet) = wallet(Timestamp::now() + std::time::Duration::from_secs(3600)).await;
+        let legacy = TypedTransaction::Legacy(TxLegacy {
+            chain_id: Some(CHAIN_ID),
+            to: TxKind::Call(ORDERBOOK_PRECOMPILE_ADDRESS),
+            input: cancel(Timestamp::now()).into(),
+            ..Default::default()
+        });
+        let err = wallet
+            .sign_transaction_from(wallet.delegate(), legacy)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("unsupported transaction type"));
+
+        let request = DelegationRequest::new(
+            Address::ZERO,
+            CHAIN_ID,
+            Timestamp::from_micros(u128::from(u64::MAX) + 1),
+        );
+        assert!(matches!(
+            request.signing_hash(),
+            Err(DelegationError::ValidUntilOutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_certificate_from_another_key() {
+        let master = PrivateKeySigner::random();
+        let request =
+            DelegationRequest::new(master.address(), CHAIN_ID, Timestamp::from_seconds(1));
+        let signature = alloy_signer::SignerSync::sign_hash_sync(
+            &PrivateKeySigner::random(),
+            &request.signing_hash().unwrap(),
+        )
+        .unwrap();
+        assert!(matches!(
+            request.into_wallet(signature),
+            Err(DelegationError::WrongSigner { .. })
+        ));
+    }
+}
@@ -0,0 +1,547 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use alloy_primitives::{Address, I256, U256};
+use alloy_transport::TransportResult;
+use pod_types::{
+    rpc::{
+        orderbook::{
+            MarketType, Order, OrderDirection, OrderKind, OrderStatus, OrdersQuery, PartialFill,
+            Side,
+        },
+        subscription::{
+            AmendRejectCode, OrderEntity, OrderEvent, OrderEventKind, OrderRef, OrdersFrame,
+            SubscriptionParams,
+        },
+    },
+    wad::{SignedWad, Wad},
+    Hash, Timestamp,
+};
+
+use crate::provider::PodProvider;
+
+/// A position in a `pod_orders_v2` stream: the last frame applied.
+///
+/// A batch arrives as one frame per orderbook, so a position is the pair of
+/// batch and book. A missing book stands for the whole batch, which orders
+/// after every book in it, as on the node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OrdersCursor {
+    /// Batch deadline in microseconds.
+    pub batch: u64,
+    pub book: Option<Hash>,
+}
+
+impl OrdersCursor {
+    pub fn of(frame: &OrdersFrame) -> Self {
+        Self {
+            batch: frame.batch,
+            book: Some(frame.book),
+        }
+    }
+
+    /// `params` resuming right after this position.
+    pub fn resume(&self, params: SubscriptionParams) -> SubscriptionParams {
+        SubscriptionParams {
+            since: Some(self.batch),
+            since_book: self.book,
+            ..params
+        }
+    }
+
+    fn key(&self) -> (u64, Hash) {
+        (self.batch, self.book.unwrap_or(Hash::repeat_byte(0xff)))
+    }
+}
+
+impl Ord for OrdersCursor {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl PartialOrd for OrdersCursor {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// What happened to one order in a frame, as reported by
+/// [`OrderBookKeeper::apply`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderTransition {
+    pub kind: OrderEventKind,
+    /// The order as the whole frame left it.
+    pub order: Order,
+    /// The batch it landed in, in microseconds.
+    pub batch: u64,
+    /// `Fill` only.
+    pub fill: Option<TransitionFill>,
+    /// `Reject` only: why the engine dropped the order.
+    pub reason: Option<String>,
+    /// `ModifyReject` only.
+    pub rejection: Option<AmendRejection>,
+}
+
+/// One fill, not a running total.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransitionFill {
+    pub fill: PartialFill,
+    /// Base filled over the order's life as of this fill. Differs from the
+    /// order's when it filled again later in the frame.
+    pub total_base: U256,
+    /// The status the fill closed the order with, if it did.
+    pub closed_as: Option<OrderStatus>,
+}
+
+/// An amendment the engine refused. The order is as it was.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmendRejection {
+    pub requested_price: U256,
+    pub requested_size: U256,
+    pub code: AmendRejectCode,
+    /// Detail the code does not carry, if any.
+    pub message: Option<String>,
+    /// The account that asked, which need not own the order.
+    pub requested_by: Option<Address>,
+}
+
+#[derive(Default)]
+struct Outcome {
+    fill: Option<TransitionFill>,
+    reason: Option<String>,
+    rejection: Option<AmendRejection>,
+}
+
+/// The orders of one account, kept current by its `pod_orders_v2` frames.
+///
+/// Subscribe first, then [`Self::seed`], then [`Self::apply`] every frame:
+/// frames that arrive during the seed are buffered by the subscription, and
+/// frames at or before [`Self::cursor`] are skipped, so resuming from it
+/// neither loses nor double-applies a fill.
+#[derive(Debug, Clone)]
+pub struct OrderBookKeeper {
+    account: Address,
+    orders: HashMap<Hash, Order>,
+    market_types: HashMap<Hash, MarketType>,
+    cursor: Option<OrdersCursor>,
+}
+
+impl OrderBookKeeper {
+    pub fn new(account: Address) -> Self {
+        Self {
+            account,
+            orders: HashMap::new(),
+            market_types: HashMap::new(),
+            cursor: None,
+        }
+    }
+
+    /// Continue a stream that was applied up to `cursor`.
+    pub fn with_cursor(mut self, cursor: OrdersCursor) -> Self {
+        self.cursor = Some(cursor);
+        self
+    }
+
+    pub fn account(&self) -> Address {
+        self.account
+    }
+
+    /// The last frame applied, to resume the subscription from.
+    pub fn cursor(&self) -> Option<OrdersCursor> {
+        self.cursor
+    }
+
+    /// `params` for a `pod_orders_v2` subscription feeding this keeper.
+    pub fn subscription_params(&self, params: SubscriptionParams) -> SubscriptionParams {
+        let params = params.with_account(self.account);
+        match self.cursor {
+            Some(cursor) => cursor.resume(params),
+            None => params,
+        }
+    }
+
+    pub fn get(&self, order_id: &Hash) -> Option<&Order> {
+        self.orders.get(order_id)
+    }
+
+    pub fn orders(&self) -> impl Iterator<Item = &Order> {
+        self.orders.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    /// Load the account's orders matching `query`, such as only active ones,
+    /// with `ob_getOrders`, and the market types of the orderbooks.
+    ///
+    /// An order a frame has already moved is kept as it is: the indexer
+    /// serving the pages trails the stream.
+    pub async fn seed(
+        &mut self,
+        provider: &PodProvider,
+        query: OrdersQuery,
+    ) -> TransportResult<()> {
+        self.market_types = provider
+            .get_markets()
+            .await?
+            .into_iter()
+            .map(|market| (market.id, market.market_type))
+            .collect();
+        for order in provider.get_all_orders(self.account, query).await? {
+            if self.cursor.is_some() && self.orders.contains_key(&order.order_id) {
+                continue;
+            }
+            self.orders.insert(order.order_id, order);
+        }
+        Ok(())
+    }
+
+    /// Fold `frame` in and report what it did, in the order it happened.
+    /// A frame at or before the cursor was applied already and is skipped.
+    ///
+    /// Events about orders neither held nor created in the frame are
+    /// skipped, as are event kinds this version does not know.
+    pub fn apply(&mut self, frame: &OrdersFrame) -> Vec<OrderTransition> {
+        let at = OrdersCursor::of(frame);
+        if self.cursor.is_some_and(|cursor| at <= cursor) {
+            return Vec::new();
+        }
+
+        let mut created: Vec<Order> = frame
+            .orders
+            .iter()
+            .map(|entity| self.entity_order(entity, frame))
+            .collect();
+        let mut applied = Vec::new();
+        for event in &frame.events {
+            // An event kind added later may name an order of this frame by
+            // id, so look there too.
+            let order = match event.order() {
+                Some(OrderRef::Created(index)) => created.get_mut(index),
+                Some(OrderRef::Resting(id)) => match self.orders.get_mut(&id) {
+                    Some(order) => Some(order),
+                    None => created.iter_mut().find(|order| order.order_id == id),
+                },
+                None => None,
+            };
+            let Some(order) = order else {
+                continue;
+            };
+            if let Some(outcome) = apply_event(event, order, frame, self.account) {
+                applied.push((order.order_id, event.kind, outcome));
+            }
+        }
+        // Only now, so that an order's own `new` or `reject` decides the
+        // status it lands with.
+        for order in created {
+            self.orders.insert(order.order_id, order);
+        }
+        self.cursor = Some(at);
+
+        applied
+            .into_iter()
+            .map(|(order_id, kind, outcome)| OrderTransition {
+                kind,
+                order: self.orders[&order_id].clone(),
+                batch: frame.batch,
+                fill: outcome.fill,
+                reason: outcome.reason,
+                rejection: outcome.rejection,
+            })
+            .collect()
+    }
+
+    fn entity_order(&self, entity: &OrderEntity, frame: &OrdersFrame) -> Order {
+        // A book listed after the seed reads as spot until the next one.
+        let market_type = self
+            .market_types
+            .get(&frame.book)
+            .copied()
+            .unwrap_or(MarketType::Spot);
+        Order {
+            orderbook_id: frame.book,
+            market_type,
+            kind: entity.kind,
+            order_id: entity.id,
+            tx_hash: entity.tx_hash,
+            bidder: frame
+                .owner(entity.account, self.account)
+                .unwrap_or(self.account),
+            nonce: entity.nonce,
+            order_type: entity.order_type,
+            status: OrderStatus::Active,
+            side: if entity.size.is_negative() {
+                Side::Sell
+            } else {
+                Side::Buy
+            },
+            price: entity.price,
+            initial_size: entity.size,
+            filled_base_amount: U256::ZERO,
+            filled_quote_amount: U256::ZERO,
+            fee: U256::ZERO,
+            // The signed deadline is not on this wire, and the batch is not
+            // it.
+            deadline: Timestamp::zero(),
+            end: entity.end.unwrap_or(Timestamp::MAX),
+            included_batch: Some(Timestamp::from_micros_u64(frame.batch)),
+            effective_price: U256::ZERO,
+            fills: Vec::new(),
+            reduce_only: Some(entity.reduce_only),
+            ioc: Some(entity.ioc),
+            post_only: entity.post_only,
+            direction: None,
+            grouping: entity.grouping,
+            trigger_type: entity.trigger,
+        }
+    }
+}
+
+fn apply_event(
+    event: &OrderEvent,
+    order: &mut Order,
+    frame: &OrdersFrame,
+    account: Address,
+) -> Option<Outcome> {
+    let mut outcome = Outcome::default();
+    match event.kind {
+        OrderEventKind::New => order.status = OrderStatus::Active,
+        OrderEventKind::Reject => {
+            order.status = OrderStatus::Invalid;
+            outcome.reason = event.reason.clone();
+        }
+        OrderEventKind::Cancel => {
+            order.status = event.status.unwrap_or(OrderStatus::Canceled);
+            apply_totals(event, order);
+        }
+        OrderEventKind::Expire => {
+            order.status = OrderStatus::Expired;
+            apply_totals(event, order);
+        }
+        OrderEventKind::Modify => {
+            if let Some(price) = event.price {
+                order.price = price;
+            }
+            // The size is unsigned; the side stays the order's.
+            if let Some(size) = event.size {
+                let size = I256::from_raw(size);
+                order.initial_size = if order.initial_size.is_negative() {
+                    -size
+                } else {
+                    size
+                };
+            }
+        }
+        OrderEventKind::Fill => {
+            apply_totals(event, order);
+            let base = event.base.unwrap_or_default();
+            let quote = event.quote.unwrap_or_default();
+            let fill = PartialFill {
+                base_amount: base,
+                quote_amount: quote,
+                timestamp: Timestamp::from_micros_u64(frame.batch),
+                price: (Wad::from_raw(quote) / Wad::from_raw(base)).raw(),
+            };
+            // A zero-size fill only carries a status change, not a trade.
+            if !base.is_zero() {
+                order.fills.push(fill.clone());
+            }
+            if let Some(after) = event.position_after {
+                let moved = I256::from_raw(base);
+                let moved = if order.initial_size.is_negative() {
+                    -moved
+                } else {
+                    moved
+                };
+                order.direction = Some(if order.kind == OrderKind::Liquidation {
+                    OrderDirection::Liquidation
+                } else {
+                    perp_direction(after - moved, after)
+                });
+            }
+            if let Some(status) = event.status {
+                order.status = status;
+            }
+            outcome.fill = Some(TransitionFill {
+                fill,
+                total_base: event.total_base.unwrap_or_default(),
+                closed_as: event.status,
+            });
+        }
+        OrderEventKind::ModifyReject => {
+            outcome.rejection = Some(AmendRejection {
+                requested_price: event.requested_price.unwrap_or_default(),
+                requested_size: event.requested_size.unwrap_or_default(),
+                code: event.code.unwrap_or(AmendRejectCode::Unspecified),
+                message: event.reason.clone(),
+                requested_by: event
+                    .requester
+                    .and_then(|index| frame.owner(Some(index), account)),
+            })
+        }
+        OrderEventKind::Unknown => return None,
+    }
+    Some(outcome)
+}
+
+/// Take the totals an event reports over the order's life, rather than
+/// accumulating fills, so a missed fill cannot make them drift.
+fn apply_totals(event: &OrderEvent, order: &mut Order) {
+    order.filled_base_amount = event.total_base.unwrap_or_default();
+    order.filled_quote_amount = event.total_quote.unwrap_or_default();
+    order.fee = event.total_fee.unwrap_or_default();
+    order.effective_price =
+        (Wad::from_raw(order.filled_quote_amount) / Wad::from_raw(order.filled_base_amount)).raw();
+}
+
+/// What a fill did to a perp position, labelled as the node labels orders
+/// over REST.
+fn perp_direction(before: I256, after: I256) -> OrderDirection {
+    let (before, after) = (SignedWad::from_raw(before), SignedWad::from_raw(after));
+    let zero = SignedWad::ZERO;
+    if before == zero && after == zero {
+        OrderDirection::ReduceLong
+    } else if before == after {
+        if before > zero {
+            OrderDirection::ReduceLong
+        } else {
+            OrderDirection::ReduceShort
+        }
+    } else if before == zero {
+        if after > zero {
+            OrderDirection::OpenLong
+        } else {
+            OrderDirection::OpenShort
+        }
+    } else if after == zero {
+        if before > zero {
+            OrderDirection::CloseLong
+        } else {
+            OrderDirection::CloseShort
+        }
+    } else if before > zero && after < zero {
+        OrderDirection::LongToShort
+    } else if before < zero && after > zero {
+        OrderDirection::ShortToLong
+    } else if before > zero {
+        if after > before {
+            OrderDirection::AddLong
+        } else {
+            OrderDirection::ReduceLong
+        }
+    } else if after < before {
+        OrderDirection::AddShort
+    } else {
+        OrderDirection::ReduceShort
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOOK: Hash = Hash::repeat_byte(0x07);
+    const ACCOUNT: Address = Address::repeat_byte(0xaa);
+
+    fn frame(batch: u64, json: serde_json::Value) -> OrdersFrame {
+        let mut frame: OrdersFrame = serde_json::from_value(json).unwrap();
+        frame.book = BOOK;
+        frame.batch = batch;
+        frame
+    }
+
+    #[test]
+    fn folds_frames_into_orders() {
+        let id = Hash::repeat_byte(0x01);
+        let mut keeper = OrderBookKeeper::new(ACCOUNT);
+        let placed = frame(
+            100,
+            serde_json::json!({
+                "book": BOOK, "batch": 0,
+                "orders": [{ "id": id, "tx": Hash::ZERO, "n": 3, "px": "5000000000000000000", "sz": "-2000000000000000000" }],
+                "events": [{ "k": "new", "o": 0 }]
+            }),
+        );
+        let transitions = keeper.apply(&placed);
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(transitions[0].order.bidder, ACCOUNT);
+        assert_eq!(transitions[0].order.side, Side::Sell);
+        // Read off the frame even before the book's market type is known.
+        assert_eq!(transitions[0].order.reduce_only, Some(false));
+        assert_eq!(transitions[0].order.ioc, Some(false));
+        assert_eq!(transitions[0].order.deadline, Timestamp::zero());
+        assert_eq!(
+            transitions[0].order.included_batch,
+            Some(Timestamp::from_micros(100))
+        );
+
+        let filled = frame(
+            200,
+            serde_json::json!({
+                "book": BOOK, "batch": 0, "orders": [],
+                "events": [
+                    { "k": "fill", "id": id, "b": "1000000000000000000", "q": "5000000000000000000",
+                      "tb": "1000000000000000000", "tq": "5000000000000000000", "tf": "0", "pa": "-1000000000000000000" },
+                    { "k": "modify_reject", "id": id, "req_px": "1", "req_sz": "1", "code": "price_off_tick" },
+                    { "k": "fill", "id": id, "b": "1000000000000000000", "q": "5000000000000000000",
+                      "tb": "2000000000000000000", "tq": "10000000000000000000", "tf": "0",
+                      "pa": "-2000000000000000000", "st": "filled" },
+                    { "k": "split", "id": id }
+                ]
+            }),
+        );
+        let transitions = keeper.apply(&filled);
+        assert_eq!(transitions.len(), 3);
+        let first = transitions[0].fill.as_ref().unwrap();
+        assert_eq!(first.total_base, U256::from(10u64.pow(18)));
+        assert_eq!(first.fill.price, U256::from(5 * 10u64.pow(18)));
+        assert_eq!(first.closed_as, None);
+        assert_eq!(
+            transitions[1].rejection.as_ref().unwrap().code,
+            AmendRejectCode::PriceOffTick
+        );
+
+        let order = keeper.get(&id).unwrap();
+        assert_eq!(transitions[0].order, *order);
+        assert_eq!(order.status, OrderStatus::Filled);
+        assert_eq!(order.fills.len(), 2);
+        assert_eq!(order.filled_quote_amount, U256::from(10u64.pow(19)));
+        assert_eq!(order.direction, Some(OrderDirection::AddShort));
+
+        // Redelivered on resume.
+        assert!(keeper.apply(&filled).is_empty());
+        assert_eq!(keeper.get(&id).unwrap().fills.len(), 2);
+    }
+
+    #[test]
+    fn whole_batch_cursor_follows_its_books() {
+        let book = |byte| OrdersCursor {
+            batch: 5,
+            book: Some(Hash::repeat_byte(byte)),
+        };
+        let whole = OrdersCursor {
+            batch: 5,
+            book: None,
+        };
+        assert!(book(0x01) < book(0x02));
+        assert!(book(0xfe) < whole);
+        assert!(
+            whole
+                < OrdersCursor {
+                    batch: 6,
+                    book: Some(Hash::ZERO)
+                }
+        );
+
+        let params = book(0x01).resume(SubscriptionParams::default());
+        assert_eq!(
+            (params.since, params.since_book),
+            (Some(5), Some(Hash::repeat_byte(0x01)))
+        );
+    }
+}
@@ -0,0 +1,436 @@
+use std::collections::{BTreeMap, HashMap};
+
+use alloy_primitives::U256;
+use anyhow::Context;
+use futures::{Stream, StreamExt};
+use pod_types::{
+    rpc::orderbook::{OrderbookSnapshot, Side, TickSnapshot},
+    wad::{SignedWad, Wad},
+    Hash,
+};
+
+use crate::{provider::PodProvider, subscription::SubscriptionError};
+
+/// Why a snapshot was not applied to a [`LocalOrderbook`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BookIntegrityError {
+    /// The best bid is at or above the best ask, which a cleared book never
+    /// is.
+    Crossed { bid: U256, ask: U256 },
+    /// A price level holds no volume.
+    EmptyLevel { price: U256 },
+    /// More price levels on a side than orders, or than the depth asked for.
+    TooManyLevels { side: Side, levels: usize },
+}
+
+impl std::fmt::Display for BookIntegrityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Crossed { bid, ask } => write!(f, "book crossed: bid {bid} >= ask {ask}"),
+            Self::EmptyLevel { price } => write!(f, "empty price level at {price}"),
+            Self::TooManyLevels { side, levels } => {
+                write!(f, "{levels} {side:?} levels exceed the orders or depth")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BookIntegrityError {}
+
+/// The price levels of one orderbook, as last snapshotted.
+#[derive(Debug, Clone)]
+pub struct LocalBook {
+    snapshot: OrderbookSnapshot,
+    stale: bool,
+}
+
+impl LocalBook {
+    pub fn snapshot(&self) -> &OrderbookSnapshot {
+        &self.snapshot
+    }
+
+    /// Whether the stream that kept this book current has stopped, so it may
+    /// no longer match the venue.
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    /// Highest bid and the volume resting at it.
+    pub fn best_bid(&self) -> Option<(Wad, Wad)> {
+        self.snapshot.best_bid().map(wads)
+    }
+
+    /// Lowest ask and the volume resting at it.
+    pub fn best_ask(&self) -> Option<(Wad, Wad)> {
+        self.snapshot.best_ask().map(wads)
+    }
+
+    pub fn mid(&self) -> Option<Wad> {
+        let (bid, _) = self.best_bid()?;
+        let (ask, _) = self.best_ask()?;
+        Some((bid + ask) / Wad::from_integer(2))
+    }
+
+    pub fn spread(&self) -> Option<SignedWad> {
+        let (bid, _) = self.best_bid()?;
+        let (ask, _) = self.best_ask()?;
+        Some(SignedWad::from(ask) - SignedWad::from(bid))
+    }
+
+    /// Volume resting at exactly `price` on `side`.
+    pub fn depth_at(&self, side: Side, price: Wad) -> Wad {
+        self.levels(side)
+            .get(&price.raw())
+            .map_or(Wad::ZERO, |tick| Wad::from_raw(tick.volume))
+    }
+
+    /// Average price of taking `size` of base on `side`, walking the book from
+    /// the best opposing price. `None` when the levels held cannot fill it,
+    /// which with a depth cap includes levels beyond the cap.
+    pub fn vwap(&self, side: Side, size: Wad) -> Option<Wad> {
+        if size.is_zero() {
+            return None;
+        }
+        let levels: Box<dyn Iterator<Item = (&U256, &TickSnapshot)>> = match side {
+            Side::Buy => Box::new(self.snapshot.sells.iter()),
+            Side::Sell => Box::new(self.snapshot.buys.iter().rev()),
+        };
+        let mut remaining = size;
+        let mut quote = Wad::ZERO;
+        for (price, tick) in levels {
+            let take = remaining.min(Wad::from_raw(tick.volume));
+            quote += Wad::from_raw(*price) * take;
+            remaining -= take;
+            if remaining.is_zero() {
+                return Some(quote / size);
+            }
+        }
+        None
+    }
+
+    fn levels(&self, side: Side) -> &BTreeMap<U256, TickSnapshot> {
+        match side {
+            Side::Buy => &self.snapshot.buys,
+            Side::Sell => &self.snapshot.sells,
+        }
+    }
+}
+
+fn wads((price, volume): (U256, U256)) -> (Wad, Wad) {
+    (Wad::from_raw(price), Wad::from_raw(volume))
+}
+
+/// Replicas of orderbooks, kept current by `pod_orderbook` snapshots.
+///
+/// Seed with [`Self::seed`], then [`Self::follow`] the subscription, which
+/// marks the books stale once it ends or fails. A book is fresh again with its
+/// next snapshot. Snapshots from elsewhere can be [`Self::apply`]d directly.
+#[derive(Debug, Clone, Default)]
+pub struct LocalOrderbook {
+    books: HashMap<Hash, LocalBook>,
+    depth: Option<u32>,
+}
+
+impl LocalOrderbook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hold at most `depth` price levels per side, as the subscription and
+    /// seed are asked for.
+    pub fn with_depth(mut self, depth: u32) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    pub fn depth(&self) -> Option<u32> {
+        self.depth
+    }
+
+    /// Load the current levels of `orderbook_ids` with `ob_getOrderbook`.
+    /// Returns the books that failed to load, with why; the others are
+    /// loaded regardless.
+    pub async fn seed(
+        &mut self,
+        provider: &PodProvider,
+        orderbook_ids: impl IntoIterator<Item = Hash>,
+    ) -> Vec<(Hash, anyhow::Error)> {
+        let mut failed = Vec::new();
+        for orderbook_id in orderbook_ids {
+            let seeded = match provider
+                .get_orderbook(orderbook_id, self.depth.map(u64::from))
+                .await
+            {
+                Ok(snapshot) => self
+                    .apply(snapshot)
+                    .map(|_| ())
+                    .context("checking orderbook"),
+                Err(err) => Err(err).context("getting orderbook"),
+            };
+            if let Err(err) = seeded {
+                failed.push((orderbook_id, err));
+            }
+        }
+        failed
+    }
+
+    /// Apply snapshots of `stream`, a [`subscribe_orderbook`] subscription,
+    /// until one changes a book. Returns its id, or once the stream ends or
+    /// fails, `None` or the failure, with every book marked stale.
+    ///
+    /// A snapshot failing the integrity checks marks its book stale, and a
+    /// frame that does not decode is skipped; neither ends the stream.
+    ///
+    /// [`subscribe_orderbook`]: crate::subscription::PodSubscriber::subscribe_orderbook
+    pub async fn follow<S>(&mut self, stream: &mut S) -> Option<Result<Hash, SubscriptionError>>
+    where
+        S: Stream<Item = Result<OrderbookSnapshot, SubscriptionError>> + Unpin,
+    {
+        loop {
+            match stream.next().await {
+                Some(Ok(snapshot)) => {
+                    let orderbook_id = snapshot.orderbook_id;
+                    match self.apply(snapshot) {
+                        Ok(true) => return Some(Ok(orderbook_id)),
+                        Ok(false) => {}
+                        Err(err) => {
+                            tracing::warn!(%orderbook_id, %err, "orderbook snapshot not applied");
+                            if let Some(book) = self.books.get_mut(&orderbook_id) {
+                                book.stale = true;
+                            }
+                        }
+                    }
+                }
+                Some(Err(SubscriptionError::Decode(err))) => {
+                    tracing::warn!(%err, "skipping orderbook frame");
+                }
+                Some(Err(err)) => {
+                    self.mark_stale();
+                    return Some(Err(err));
+                }
+                None => {
+                    self.mark_stale();
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// Replace the book of `snapshot` with it. Returns `false` when the book
+    /// held is newer, as after a seed that raced the stream.
+    ///
+    /// A snapshot failing the integrity checks is not applied, and the book
+    /// is left as it was.
+    pub fn apply(&mut self, snapshot: OrderbookSnapshot) -> Result<bool, BookIntegrityError> {
+        if self
+            .books
+            .get(&snapshot.orderbook_id)
+            .is_some_and(|book| book.snapshot.timestamp > snapshot.timestamp)
+        {
+            return Ok(false);
+        }
+        self.check(&snapshot)?;
+        self.books.insert(
+            snapshot.orderbook_id,
+            LocalBook {
+                snapshot,
+                stale: false,
+            },
+        );
+        Ok(true)
+    }
+
+    /// Flag every book as possibly out of date.
+    pub fn mark_stale(&mut self) {
+        for book in self.books.values_mut() {
+            book.stale = true;
+        }
+    }
+
+    pub fn book(&self, orderbook_id: &Hash) -> Option<&LocalBook> {
+        self.books.get(orderbook_id)
+    }
+
+    /// The book of `orderbook_id`, unless it is stale.
+    pub fn fresh_book(&self, orderbook_id: &Hash) -> Option<&LocalBook> {
+        self.book(orderbook_id).filter(|book| !book.stale)
+    }
+
+    pub fn books(&self) -> impl Iterator<Item = &LocalBook> {
+        self.books.values()
+    }
+
+    fn check(&self, snapshot: &OrderbookSnapshot) -> Result<(), BookIntegrityError> {
+        for (side, levels, orders) in [
+            (Side::Buy, &snapshot.buys, snapshot.buys_count),
+            (Side::Sell, &snapshot.sells, snapshot.sells_count),
+        ] {
+            let cap = self.depth.map_or(usize::MAX, |depth| depth as usize);
+            if levels.len() as u64 > orders || levels.len() > cap {
+                return Err(BookIntegrityError::TooManyLevels {
+                    side,
+                    levels: levels.len(),
+                });
+            }
+            if let Some((price, _)) = levels.iter().find(|(_, tick)| tick.volume.is_zero()) {
+                return Err(BookIntegrityError::EmptyLevel { price: *price });
+            }
+        }
+        if let (Some((bid, _)), Some((ask, _))) = (snapshot.best_bid(), snapshot.best_ask()) {
+            if bid >= ask {
+                return Err(BookIntegrityError::Crossed { bid, ask });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pod_types::Timestamp;
+
+    use super::*;
+
+    const BOOK: Hash = Hash::repeat_byte(0x01);
+
+    fn snapshot(micros: u128, buys: &[(u64, u64)], sells: &[(u64, u64)]) -> OrderbookSnapshot {
+        let levels = |levels: &[(u64, u64)]| {
+            levels
+                .iter()
+                .map(|&(price, volume)| {
+                    (
+                        Wad::from_integer(price).raw(),
+                        TickSnapshot {
+                            volume: Wad::from_integer(volume).raw(),
+                        },
+                    )
+                })
+                .collect::<BTreeMap<_, _>>()
+        };
+        OrderbookSnapshot {
+            orderbook_id: BOOK,
+            buys: levels(buys),
+            sells: levels(sells),
+            clearing_price: U256::ZERO,
+            grouping_precision: U256::from(1),
+            timestamp: Timestamp::from_micros(micros),
+            new_orders_count: 0,
+            buys_count: buys.len() as u64,
+            sells_count: sells.len() as u64,
+            oracle_price: None,
+            funding_rate: None,
+            funding_index: None,
+            funding_last_updated: None,
+        }
+    }
+
+    #[test]
+    fn prices_from_the_replica() {
+        let mut local = LocalOrderbook::new().with_depth(2);
+        assert!(local
+            .apply(snapshot(2, &[(99, 1), (98, 2)], &[(101, 1), (103, 3)]))
+            .unwrap());
+        assert!(!local.apply(snapshot(1, &[], &[])).unwrap());
+
+        let book = local.fresh_book(&BOOK).unwrap();
+        assert_eq!(book.mid(), Some(Wad::from_integer(100)));
+        assert_eq!(book.spread(), Some(SignedWad::from_integer(2)));
+        assert_eq!(
+            book.depth_at(Side::Buy, Wad::from_integer(98)),
+            Wad::from_integer(2)
+        );
+        // 1 at 101 and 1 at 103.
+        assert_eq!(
+            book.vwap(Side::Buy, Wad::from_integer(2)),
+            Some(Wad::from_integer(102))
+        );
+        assert_eq!(
+            book.vwap(Side::Sell, Wad::from_integer(3)),
+            Some(Wad::from_raw(U256::from(98_333_333_333_333_333_333u128)))
+        );
+        assert_eq!(book.vwap(Side::Buy, Wad::from_integer(5)), None);
+
+        assert_eq!(
+            local.apply(snapshot(3, &[(101, 1)], &[(101, 1)])),
+            Err(BookIntegrityError::Crossed {
+                bid: Wad::from_integer(101).raw(),
+                ask: Wad::from_integer(101).raw(),
+            })
+        );
+        assert!(matches!(
+            local.apply(snapshot(3, &[(97, 1), (98, 1), (99, 1)], &[])),
+            Err(BookIntegrityError::TooManyLevels {
+                side: Side::Buy,
+                ..
+            })
+        ));
+
+        local.mark_stale();
+        assert!(local.fresh_book(&BOOK).is_none());
+        local.apply(snapshot(4, &[(99, 1)], &[])).unwrap();
+        assert!(local.fresh_book(&BOOK).is_some());
+    }
+
+    #[tokio::test]
+    async fn follows_the_stream_until_it_fails() {
+        let undecodable = serde_json::from_str::<u64>("x").unwrap_err();
+        let mut stream = futures::stream::iter(vec![
+            Ok(snapshot(2, &[(99, 1)], &[(101, 1)])),
+            Err(SubscriptionError::Decode(undecodable)),
+            // Older than the book held.
+            Ok(snapshot(1, &[(98, 1)], &[])),
+            Ok(snapshot(3, &[(100, 1)], &[])),
+            Ok(snapshot(4, &[(101, 1)], &[(101, 1)])),
+            Ok(snapshot(5, &[(99, 2)], &[])),
+            Err(SubscriptionError::Disconnected),
+            Ok(snapshot(6, &[(97, 1)], &[])),
+        ]);
+        let mut local = LocalOrderbook::new();
+
+        assert_eq!(local.follow(&mut stream).await.unwrap().unwrap(), BOOK);
+        assert_eq!(local.follow(&mut stream).await.unwrap().unwrap(), BOOK);
+        assert_eq!(
+            local.fresh_book(&BOOK).unwrap().best_bid(),
+            Some((Wad::from_integer(100), Wad::from_integer(1)))
+        );
+        // The crossed snapshot leaves the book stale until the next one.
+        assert_eq!(local.follow(&mut stream).await.unwrap().unwrap(), BOOK);
+        assert!(local.fresh_book(&BOOK).is_some());
+
+        assert!(matches!(
+            local.follow(&mut stream).await,
+            Some(Err(SubscriptionError::Disconnected))
+        ));
+        let book = local.book(&BOOK).unwrap();
+        assert!(book.is_stale());
+        assert_eq!(
+            book.best_bid(),
+            Some((Wad::from_integer(99), Wad::from_integer(2)))
+        );
+    }
+
+    #[tokio::test]
+    async fn seed_reports_each_failed_book() {
+        use alloy_transport::mock::Asserter;
+
+        use crate::test_utils::{provider, rpc_error};
+
+        let asserter = Asserter::new();
+        let provider = provider(&asserter);
+        let missing = Hash::repeat_byte(0x02);
+        let crossed = Hash::repeat_byte(0x03);
+        asserter.push_failure(rpc_error(-32602, "orderbook not found", None));
+        asserter.push_success(&OrderbookSnapshot {
+            orderbook_id: crossed,
+            ..snapshot(1, &[(101, 1)], &[(100, 1)])
+        });
+        asserter.push_success(&snapshot(1, &[(99, 1)], &[]));
+
+        let mut local = LocalOrderbook::new();
+        let failed = local.seed(&provider, [missing, crossed, BOOK]).await;
+        let failed: Vec<Hash> = failed.into_iter().map(|(id, _)| id).collect();
+        assert_eq!(failed, vec![missing, crossed]);
+        assert!(local.fresh_book(&BOOK).is_some());
+        assert!(local.book(&crossed).is_none());
+    }
+}
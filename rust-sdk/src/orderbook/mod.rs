@@ -0,0 +1,16 @@
+pub mod abi;
+pub mod batch;
+pub mod candles;
+pub mod client;
+pub mod delegation;
+pub mod keeper;
+pub mod local;
+pub mod trigger;
+
+pub use batch::{Batch, BatchBuilder, BatchError};
+pub use candles::CandleSeries;
+pub use client::{Order, OrderFlags, OrderFlagsError, OrderbookClient};
+pub use delegation::{DelegatedWallet, DelegationError, DelegationRequest};
+pub use keeper::{OrderBookKeeper, OrderTransition, OrdersCursor};
+pub use local::{BookIntegrityError, LocalBook, LocalOrderbook};
+pub use trigger::Trigger;
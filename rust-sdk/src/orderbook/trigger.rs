@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use alloy_primitives::{I256, U256};
+use pod_types::{Hash, Timestamp};
+
+use super::abi::IOrderbook::{self, TriggerGrouping, TriggerType};
+
+/// A take-profit / stop-loss trigger, as passed to `submitTrigger`. Perp
+/// markets only.
+#[derive(Debug, Clone)]
+pub struct Trigger {
+    pub orderbook_id: Hash,
+    /// Size of the order produced when the trigger fires; positive to buy,
+    /// negative to sell.
+    pub size: I256,
+    pub limit_price: U256,
+    /// Mark price that fires the trigger.
+    pub trigger_price: U256,
+    pub trigger_type: TriggerType,
+    pub grouping: TriggerGrouping,
+    pub deadline: Timestamp,
+    /// How long the armed trigger rests on the venue.
+    pub ttl: Duration,
+    pub reduce_only: bool,
+    pub ioc: bool,
+}
+
+impl Trigger {
+    pub fn to_call(&self) -> IOrderbook::submitTriggerCall {
+        IOrderbook::submitTriggerCall {
+            orderbookId: self.orderbook_id,
+            size: self.size,
+            limitPrice: self.limit_price,
+            triggerPrice: self.trigger_price,
+            triggerType: self.trigger_type,
+            grouping: self.grouping,
+            deadline: self.deadline.as_micros(),
+            ttl: self.ttl.as_micros(),
+            reduceOnly: self.reduce_only,
+            ioc: self.ioc,
+        }
+    }
+
+    /// Whether the trigger fires at `mark_price`; see [`fires`].
+    pub fn fires_at(&self, mark_price: U256) -> bool {
+        fires(
+            !self.size.is_negative(),
+            self.trigger_type,
+            self.trigger_price,
+            mark_price,
+        )
+    }
+}
+
+/// Whether a trigger fires at `mark_price`, by the venue's rule:
+///
+/// | Side | Type       | Fires when             |
+/// |------|------------|------------------------|
+/// | Buy  | TakeProfit | mark price <= trigger  |
+/// | Buy  | StopLoss   | mark price >= trigger  |
+/// | Sell | TakeProfit | mark price >= trigger  |
+/// | Sell | StopLoss   | mark price <= trigger  |
+///
+/// `is_buy` is the side of the order the trigger produces, so a take-profit
+/// closing a long is a sell.
+pub fn fires(
+    is_buy: bool,
+    trigger_type: TriggerType,
+    trigger_price: U256,
+    mark_price: U256,
+) -> bool {
+    match (is_buy, trigger_type) {
+        (true, TriggerType::TakeProfit) | (false, TriggerType::StopLoss) => {
+            mark_price <= trigger_price
+        }
+        (true, TriggerType::StopLoss) | (false, TriggerType::TakeProfit) => {
+            mark_price >= trigger_price
+        }
+        (_, TriggerType::__Invalid) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trigger(size: i64, trigger_type: TriggerType) -> Trigger {
+        Trigger {
+            orderbook_id: Hash::ZERO,
+            size: I256::try_from(size).unwrap(),
+            limit_price: U256::from(100),
+            trigger_price: U256::from(100),
+            trigger_type,
+            grouping: TriggerGrouping::Asset,
+            deadline: Timestamp::zero(),
+            ttl: Duration::ZERO,
+            reduce_only: true,
+            ioc: false,
+        }
+    }
+
+    #[test]
+    fn fires_by_side_and_type() {
+        let (below, at, above) = (U256::from(99), U256::from(100), U256::from(101));
+
+        let buy_tp = trigger(1, TriggerType::TakeProfit);
+        assert!(buy_tp.fires_at(below) && buy_tp.fires_at(at) && !buy_tp.fires_at(above));
+
+        let buy_sl = trigger(1, TriggerType::StopLoss);
+        assert!(!buy_sl.fires_at(below) && buy_sl.fires_at(at) && buy_sl.fires_at(above));
+
+        let sell_tp = trigger(-1, TriggerType::TakeProfit);
+        assert!(!sell_tp.fires_at(below) && sell_tp.fires_at(at) && sell_tp.fires_at(above));
+
+        let sell_sl = trigger(-1, TriggerType::StopLoss);
+        assert!(sell_sl.fires_at(below) && sell_sl.fires_at(at) && !sell_sl.fires_at(above));
+    }
+}
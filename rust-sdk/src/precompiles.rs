@@ -2,3 +2,18 @@ use alloy_primitives::{address, Address};
 
 pub const REGISTER_TIMER_CONTRACT_ADDRESS: Address =
     address!("0x72a693a8644edf08e733d47bf26ab75ec399f640");
+
+/// Central limit order book for spot and perpetual markets.
+pub const ORDERBOOK_PRECOMPILE_ADDRESS: Address =
+    address!("0x50d0000000000000000000000000000000000002");
+
+/// Withdrawals of pod account balances to other chains.
+pub const BRIDGE_PRECOMPILE_ADDRESS: Address =
+    address!("0x50d0000000000000000000000000000000000001");
+
+/// Recovery of accounts locked by conflicting transactions at one nonce.
+pub const RECOVERY_PRECOMPILE_ADDRESS: Address =
+    address!("0x50d0000000000000000000000000000000000003");
+
+/// Stand-in token address for the native coin in bridge calls.
+pub const NATIVE_TOKEN_ADDRESS: Address = address!("0xEeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE");
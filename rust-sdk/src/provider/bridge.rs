@@ -0,0 +1,26 @@
+use alloy_provider::Provider;
+use alloy_transport::TransportResult;
+use pod_types::{
+    rpc::bridge::{BridgeClaimProof, ProcessedDeposits},
+    Hash,
+};
+
+use super::PodProvider;
+
+impl PodProvider {
+    /// Validator signatures for claiming the bridge precompile withdrawal made
+    /// by `tx_hash`. See [`crate::bridge::ClaimProof::from_response`] to turn
+    /// them into the `proof` the claim-chain bridge accepts.
+    pub async fn get_bridge_claim_proof(&self, tx_hash: Hash) -> TransportResult<BridgeClaimProof> {
+        self.client()
+            .request("pod_getBridgeClaimProof", (tx_hash,))
+            .await
+    }
+
+    /// Source-chain deposits pod has credited so far.
+    pub async fn get_processed_deposits(&self) -> TransportResult<ProcessedDeposits> {
+        self.client()
+            .request_noparams("pod_getProcessedDeposits")
+            .await
+    }
+}
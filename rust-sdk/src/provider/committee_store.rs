@@ -0,0 +1,144 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{Mutex, RwLock},
+};
+
+use pod_types::Committee;
+
+/// Committees seen so far, keyed by the epoch they were active in.
+///
+/// `pod_getCommittee` only reports the current committee, so a store learns
+/// older epochs by having seen them earlier. Persisting it to disk with
+/// [`Self::open`] keeps old receipts and logs verifiable across restarts.
+#[derive(Debug, Default)]
+pub struct CommitteeStore {
+    committees: RwLock<BTreeMap<u64, Committee>>,
+    path: Option<PathBuf>,
+    /// Held while writing the file, so writes land in the order of the
+    /// inserts they follow.
+    persisting: Mutex<()>,
+}
+
+impl CommitteeStore {
+    /// An in-memory store, lost on drop.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A store persisted as JSON at `path`, loading what it holds if it exists.
+    /// Every insert rewrites the file.
+    pub fn open(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+        let committees = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self {
+            committees: RwLock::new(committees),
+            path: Some(path),
+            persisting: Mutex::new(()),
+        })
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// The committee active in `epoch`, if it has been seen.
+    pub fn get(&self, epoch: u64) -> Option<Committee> {
+        self.committees
+            .read()
+            .expect("poisoned")
+            .get(&epoch)
+            .cloned()
+    }
+
+    /// The committee of the newest epoch seen.
+    pub fn latest(&self) -> Option<Committee> {
+        self.committees
+            .read()
+            .expect("poisoned")
+            .last_key_value()
+            .map(|(_, committee)| committee.clone())
+    }
+
+    pub fn epochs(&self) -> Vec<u64> {
+        self.committees
+            .read()
+            .expect("poisoned")
+            .keys()
+            .copied()
+            .collect()
+    }
+
+    /// Record `committee` under its epoch, replacing any committee already
+    /// stored for it, and persist the store if it has a path.
+    ///
+    /// The in-memory store is updated even if persisting fails. Readers are
+    /// not held up by the write.
+    pub fn insert(&self, committee: Committee) -> std::io::Result<()> {
+        self.committees
+            .write()
+            .expect("poisoned")
+            .insert(committee.epoch, committee);
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let _persisting = self.persisting.lock().expect("poisoned");
+        // Taken after the lock, so the last write has every insert.
+        let committees = self.committees.read().expect("poisoned").clone();
+        Self::persist(path, &committees)
+    }
+
+    // Write to a sibling file first so a crash never leaves a truncated store.
+    fn persist(path: &Path, committees: &BTreeMap<u64, Committee>) -> std::io::Result<()> {
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(committees)?)?;
+        std::fs::rename(&tmp, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::Address;
+
+    fn committee(epoch: u64) -> Committee {
+        Committee::new([Address::repeat_byte(epoch as u8)], 1).with_epoch(epoch)
+    }
+
+    #[test]
+    fn keeps_committees_per_epoch() {
+        let store = CommitteeStore::new();
+        assert!(store.latest().is_none());
+
+        store.insert(committee(2)).unwrap();
+        store.insert(committee(1)).unwrap();
+
+        assert_eq!(store.epochs(), vec![1, 2]);
+        assert_eq!(store.latest().unwrap().epoch, 2);
+        assert!(store
+            .get(1)
+            .unwrap()
+            .is_in_committee(&Address::repeat_byte(1)));
+        assert!(store.get(3).is_none());
+    }
+
+    #[test]
+    fn persists_to_disk() {
+        let path =
+            std::env::temp_dir().join(format!("pod-committee-store-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let store = CommitteeStore::open(&path).unwrap();
+        store.insert(committee(5)).unwrap();
+        drop(store);
+
+        let reopened = CommitteeStore::open(&path).unwrap();
+        assert_eq!(reopened.epochs(), vec![5]);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,331 @@
+use std::fmt;
+
+use alloy_primitives::Address;
+use alloy_provider::Provider;
+use alloy_transport::TransportResult;
+use pod_types::rpc::account::{
+    AccountDiagnostics, NonceVotes, RecoveryTarget, ValidatorAccountStatus, Voter,
+};
+
+use super::PodProvider;
+
+/// Pending nonces [`PodProvider::diagnose_account`] fetches the votes of.
+const MAX_REPORTED_NONCES: u64 = 16;
+
+impl PodProvider {
+    /// Every validator's vote state for `account`, with its recovery status.
+    pub async fn get_account_diagnostics(
+        &self,
+        account: Address,
+    ) -> TransportResult<AccountDiagnostics> {
+        self.client()
+            .request("pod_getAccountDiagnostics", (account,))
+            .await
+    }
+
+    /// The votes at `nonce` of `account`, or `None` when none are pending
+    /// there.
+    pub async fn get_votes(
+        &self,
+        account: Address,
+        nonce: u64,
+    ) -> TransportResult<Option<NonceVotes>> {
+        self.client()
+            .request("pod_getVotes", (account, nonce))
+            .await
+    }
+
+    /// How `validator` voted at each pending nonce of `account`, or `None` when
+    /// it is not in the committee.
+    pub async fn get_validator_account_status(
+        &self,
+        validator: Address,
+        account: Address,
+    ) -> TransportResult<Option<ValidatorAccountStatus>> {
+        self.client()
+            .request("pod_getValidatorAccountStatus", (validator, account))
+            .await
+    }
+
+    /// Everything the node knows about why `account` is or isn't progressing,
+    /// with what to do about it. Prints as a report.
+    pub async fn diagnose_account(&self, account: Address) -> TransportResult<AccountReport> {
+        let diagnostics = self.get_account_diagnostics(account).await?;
+        let last_finalized_nonce = self.get_last_finalized_nonce(account).await?;
+
+        let first = diagnostics.next_finalized_nonce;
+        let end = diagnostics
+            .expected_nonce
+            .min(first.saturating_add(MAX_REPORTED_NONCES));
+        let mut pending = Vec::new();
+        for nonce in first..end {
+            if let Some(votes) = self.get_votes(account, nonce).await? {
+                pending.push(votes);
+            }
+        }
+
+        let next_step = NextStep::for_account(&diagnostics, &pending);
+        Ok(AccountReport {
+            diagnostics,
+            last_finalized_nonce,
+            pending,
+            next_step,
+        })
+    }
+}
+
+/// What gets a stuck account moving again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NextStep {
+    /// Nothing is pending.
+    Nothing,
+    /// A transaction has enough votes and is finalizing.
+    Wait,
+    /// Some validators have not seen the pending transaction. Send the same
+    /// signed bytes again; signing a different one would split the votes.
+    Resend { nonce: u64 },
+    /// The nonce has votes that cannot make a certificate. An empty
+    /// self-transfer forces one, see [`PodProvider::send_empty_tx`].
+    SendEmptyTx { nonce: u64 },
+    /// The account is locked, see [`PodProvider::recover_account`].
+    Recover { target: RecoveryTarget },
+}
+
+impl NextStep {
+    fn for_account(diagnostics: &AccountDiagnostics, pending: &[NonceVotes]) -> Self {
+        if let Some(target) = diagnostics.recovery_target {
+            return Self::Recover { target };
+        }
+        let nonce = diagnostics.next_finalized_nonce;
+        if diagnostics.expected_nonce <= nonce {
+            return Self::Nothing;
+        }
+        let Some(votes) = pending.iter().find(|votes| votes.nonce == nonce) else {
+            return Self::Resend { nonce };
+        };
+
+        let leading = votes
+            .txs
+            .iter()
+            .map(|tx| tx.voters.len())
+            .max()
+            .unwrap_or(0);
+        if leading >= votes.quorum {
+            return Self::Wait;
+        }
+        let voted = votes.total_tx_votes + votes.bot_voters.len();
+        let undecided = diagnostics.validators.len().saturating_sub(voted);
+        if votes.bot_voters.is_empty() && leading + undecided >= votes.quorum {
+            Self::Resend { nonce }
+        } else {
+            Self::SendEmptyTx { nonce }
+        }
+    }
+}
+
+impl fmt::Display for NextStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Nothing => write!(f, "nothing, no nonce is pending"),
+            Self::Wait => write!(f, "wait, a transaction has a quorum of votes"),
+            Self::Resend { nonce } => {
+                write!(f, "resend the signed transaction at nonce {nonce}")
+            }
+            Self::SendEmptyTx { nonce } => {
+                write!(f, "send an empty self-transfer at nonce {nonce}")
+            }
+            Self::Recover { target } => {
+                write!(f, "recover to {} at nonce {}", target.hash, target.nonce)
+            }
+        }
+    }
+}
+
+/// Result of [`PodProvider::diagnose_account`].
+#[derive(Debug, Clone)]
+pub struct AccountReport {
+    pub diagnostics: AccountDiagnostics,
+    pub last_finalized_nonce: Option<u64>,
+    /// Votes at the oldest pending nonces that have any.
+    pub pending: Vec<NonceVotes>,
+    pub next_step: NextStep,
+}
+
+impl fmt::Display for AccountReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let d = &self.diagnostics;
+        write!(f, "account {}", d.account)?;
+        for (flag, label) in [
+            (d.is_locked, "locked"),
+            (d.needs_recovery, "needs recovery"),
+            (d.equivocating, "equivocating"),
+            (d.has_clob_order_pending_solution, "order pending solution"),
+        ] {
+            if flag {
+                write!(f, ", {label}")?;
+            }
+        }
+        writeln!(f)?;
+        match self.last_finalized_nonce {
+            Some(nonce) => writeln!(
+                f,
+                "  last finalized nonce {nonce} ({}), expected nonce {}",
+                d.last_finalized_tx, d.expected_nonce
+            )?,
+            None => writeln!(
+                f,
+                "  nothing finalized, expected nonce {}",
+                d.expected_nonce
+            )?,
+        }
+        writeln!(
+            f,
+            "  quorum {} of {} validators",
+            d.quorum,
+            d.validators.len()
+        )?;
+
+        for votes in &self.pending {
+            writeln!(f, "  nonce {}:", votes.nonce)?;
+            for tx in &votes.txs {
+                writeln!(
+                    f,
+                    "    {}: {} votes from {}",
+                    tx.tx_hash,
+                    tx.voters.len(),
+                    voter_list(&tx.voters)
+                )?;
+            }
+            if !votes.bot_voters.is_empty() {
+                writeln!(f, "    no transaction: {}", voter_list(&votes.bot_voters))?;
+            }
+        }
+        for validator in d.validators.iter().filter(|v| v.equivocating) {
+            writeln!(
+                f,
+                "  validator #{} ({}) equivocated",
+                validator.validator_index, validator.validator_address
+            )?;
+        }
+        write!(f, "  next step: {}", self.next_step)
+    }
+}
+
+fn voter_list(voters: &[Voter]) -> String {
+    voters
+        .iter()
+        .map(|voter| format!("#{}", voter.validator_index))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use pod_types::{
+        rpc::account::{TxVotes, ValidatorDiagnostics, ValidatorVote},
+        Hash,
+    };
+
+    use super::*;
+
+    fn voters(indices: &[usize]) -> Vec<Voter> {
+        indices
+            .iter()
+            .map(|&validator_index| Voter {
+                validator_index,
+                validator_address: Address::repeat_byte(validator_index as u8),
+            })
+            .collect()
+    }
+
+    fn diagnostics() -> AccountDiagnostics {
+        AccountDiagnostics {
+            account: Address::repeat_byte(0xaa),
+            next_finalized_nonce: 5,
+            expected_nonce: 6,
+            last_finalized_tx: Hash::repeat_byte(0x01),
+            is_locked: true,
+            quorum: 3,
+            validators: (0..4)
+                .map(|i| ValidatorDiagnostics {
+                    validator_index: i,
+                    validator_address: Address::repeat_byte(i as u8),
+                    highest_attested_nonce: None,
+                    current_vote: ValidatorVote::NotSeen,
+                    equivocating: false,
+                })
+                .collect(),
+            needs_recovery: false,
+            recovery_target: None,
+            has_clob_order_pending_solution: false,
+            equivocating: false,
+        }
+    }
+
+    fn votes(txs: &[&[usize]], bot: &[usize]) -> NonceVotes {
+        NonceVotes {
+            account: Address::repeat_byte(0xaa),
+            nonce: 5,
+            txs: txs
+                .iter()
+                .enumerate()
+                .map(|(i, indices)| TxVotes {
+                    tx_hash: Hash::repeat_byte(0x10 + i as u8),
+                    voters: voters(indices),
+                })
+                .collect(),
+            bot_voters: voters(bot),
+            total_tx_votes: txs.iter().map(|indices| indices.len()).sum(),
+            quorum: 3,
+        }
+    }
+
+    #[test]
+    fn next_step_follows_head_nonce_votes() {
+        let d = diagnostics();
+        assert_eq!(
+            NextStep::for_account(&d, &[]),
+            NextStep::Resend { nonce: 5 }
+        );
+        assert_eq!(
+            NextStep::for_account(&d, &[votes(&[&[0, 1]], &[])]),
+            NextStep::Resend { nonce: 5 }
+        );
+        assert_eq!(
+            NextStep::for_account(&d, &[votes(&[&[0, 1, 2]], &[])]),
+            NextStep::Wait
+        );
+        assert_eq!(
+            NextStep::for_account(&d, &[votes(&[&[0, 1], &[2]], &[])]),
+            NextStep::Resend { nonce: 5 }
+        );
+        assert_eq!(
+            NextStep::for_account(&d, &[votes(&[&[0, 1], &[2, 3]], &[])]),
+            NextStep::SendEmptyTx { nonce: 5 }
+        );
+
+        let target = RecoveryTarget {
+            hash: Hash::repeat_byte(0x20),
+            nonce: 4,
+        };
+        let locked = AccountDiagnostics {
+            recovery_target: Some(target),
+            ..d.clone()
+        };
+        assert_eq!(
+            NextStep::for_account(&locked, &[]),
+            NextStep::Recover { target }
+        );
+
+        let report = AccountReport {
+            diagnostics: d,
+            last_finalized_nonce: Some(4),
+            pending: vec![votes(&[&[0, 1], &[2]], &[3])],
+            next_step: NextStep::SendEmptyTx { nonce: 5 },
+        }
+        .to_string();
+        assert!(report.contains("2 votes from #0, #1"));
+        assert!(report.contains("no transaction: #3"));
+        assert!(report.ends_with("next step: send an empty self-transfer at nonce 5"));
+    }
+}
@@ -0,0 +1,285 @@
+use alloy_rpc_types::TransactionReceipt;
+use alloy_transport::TransportError;
+use pod_types::{
+    consensus::committee::CommitteeError,
+    ledger::log::VerifiableLog,
+    metadata::{MetadataWrappedItem, RegularReceiptMetadata},
+    rpc::receipt::PodReceiptResponse,
+    AttestedTx, Committee, Hashable,
+};
+
+use alloy_primitives::B256 as Hash;
+
+/// A response carrying committee attestations that a light client can check
+/// without trusting the node that served it.
+pub trait Verifiable {
+    /// Hash of the transaction the attestations are about, for error reports.
+    fn tx_hash(&self) -> Option<Hash>;
+
+    /// Epoch of the committee that attested the item, when the node reports it.
+    fn committee_epoch(&self) -> Option<u64>;
+
+    /// Check the attestations against `committee`.
+    fn verify_against(&self, committee: &Committee) -> Result<(), CommitteeError>;
+}
+
+impl Verifiable for PodReceiptResponse {
+    fn tx_hash(&self) -> Option<Hash> {
+        Some(self.receipt.transaction_hash)
+    }
+
+    fn committee_epoch(&self) -> Option<u64> {
+        Some(self.pod_metadata.committee_epoch)
+    }
+
+    fn verify_against(&self, committee: &Committee) -> Result<(), CommitteeError> {
+        self.verify(committee).map(|_| ())
+    }
+}
+
+impl Verifiable for VerifiableLog {
+    fn tx_hash(&self) -> Option<Hash> {
+        self.inner.transaction_hash
+    }
+
+    fn committee_epoch(&self) -> Option<u64> {
+        Some(self.pod_metadata.receipt.attested_tx.committee_epoch)
+    }
+
+    fn verify_against(&self, committee: &Committee) -> Result<(), CommitteeError> {
+        self.verify(committee)
+    }
+}
+
+/// Items of [`super::PodProvider::subscribe_receipts`]. Signed over the same
+/// [`AttestedTx`] as [`PodReceiptResponse`]; the epoch is not reported, so the
+/// attestations are checked as made under the committee they are verified
+/// against.
+impl Verifiable for MetadataWrappedItem<TransactionReceipt, RegularReceiptMetadata> {
+    fn tx_hash(&self) -> Option<Hash> {
+        Some(self.inner.transaction_hash)
+    }
+
+    fn committee_epoch(&self) -> Option<u64> {
+        None
+    }
+
+    fn verify_against(&self, committee: &Committee) -> Result<(), CommitteeError> {
+        let signatures = self
+            .pod_metadata
+            .attestations
+            .iter()
+            .map(|att| att.signature)
+            .collect();
+        let digest = AttestedTx::new(self.inner.transaction_hash, committee.epoch).hash_custom();
+        committee.verify_aggregate_attestation(digest, &signatures)
+    }
+}
+
+/// Why a light-client [`super::PodProvider`] refused to return a response.
+///
+/// Methods that return a [`TransportError`] carry this as a local usage
+/// error; recover it with [`VerificationError::from_transport`].
+#[derive(Debug)]
+pub enum VerificationError {
+    /// The committee to verify against could not be fetched.
+    CommitteeUnavailable(TransportError),
+    /// The item was attested in an epoch whose committee was never seen, and
+    /// the node no longer reports it.
+    UnknownEpoch(u64),
+    /// The response is not attested by a quorum of the committee.
+    Invalid {
+        tx_hash: Option<Hash>,
+        source: CommitteeError,
+    },
+    /// `method` returns items it cannot verify; `use_instead` verifies them.
+    Unverifiable {
+        method: &'static str,
+        use_instead: &'static str,
+    },
+}
+
+impl VerificationError {
+    /// The verification failure inside `err`, if it is one.
+    pub fn from_transport(err: &TransportError) -> Option<&Self> {
+        match err {
+            TransportError::LocalUsageError(inner) => inner.downcast_ref(),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CommitteeUnavailable(e) => write!(f, "failed to fetch committee: {e}"),
+            Self::UnknownEpoch(epoch) => write!(f, "no committee known for epoch {epoch}"),
+            Self::Invalid {
+                tx_hash: Some(tx_hash),
+                source,
+            } => write!(f, "verification of {tx_hash} failed: {source}"),
+            Self::Invalid {
+                tx_hash: None,
+                source,
+            } => write!(f, "verification failed: {source}"),
+            Self::Unverifiable {
+                method,
+                use_instead,
+            } => write!(
+                f,
+                "{method} does not verify its items; use {use_instead} on a light client"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for VerificationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::CommitteeUnavailable(e) => Some(e),
+            Self::Invalid { source, .. } => Some(source),
+            Self::UnknownEpoch(_) | Self::Unverifiable { .. } => None,
+        }
+    }
+}
+
+impl From<VerificationError> for TransportError {
+    fn from(err: VerificationError) -> Self {
+        TransportError::local_usage(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recovers_verification_error_from_transport() {
+        let err: TransportError = VerificationError::Invalid {
+            tx_hash: None,
+            source: CommitteeError::InsufficientQuorum {
+                got: 1,
+                required: 3,
+            },
+        }
+        .into();
+        assert!(matches!(
+            VerificationError::from_transport(&err),
+            Some(VerificationError::Invalid { .. })
+        ));
+
+        let other = TransportError::local_usage_str("unrelated");
+        assert!(VerificationError::from_transport(&other).is_none());
+    }
+
+    /// A `pod_receipts` item signed by a fixed key over the same digest as
+    /// the `recovers_attestation_signer` receipt in `pod-types`.
+    #[test]
+    fn verifies_subscribed_receipt() {
+        let item: MetadataWrappedItem<TransactionReceipt, RegularReceiptMetadata> =
+            serde_json::from_str(
+                r#"{
+                    "type": "0x2",
+                    "status": "0x1",
+                    "cumulativeGasUsed": "0xf4240",
+                    "logs": [],
+                    "logsBloom": "0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000",
+                    "transactionHash": "0x63dfbb7b3294d3d9aedb9bd1a306ec40156d0da1c374b2348f60c57186704fcd",
+                    "transactionIndex": "0x0",
+                    "blockHash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+                    "blockNumber": "0x1",
+                    "gasUsed": "0xf4240",
+                    "effectiveGasPrice": "0x1",
+                    "from": "0xe51e549231219c35040efcf784f58776d5065d26",
+                    "to": "0x000000000000000000000000000000000000c10b",
+                    "contractAddress": null,
+                    "pod_metadata": {
+                        "attestations": [{
+                            "timestamp": 1770737135051543,
+                            "public_key": "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266",
+                            "signature": {
+                                "r": "0xc68600f4f02efbf8d7945ff781e15efd22cb7d68cdbf8937f002413dabfbc18b",
+                                "s": "0x1e61753bfdd2d212f10d356581203efaa6f7a32fa3690249962816e67307fa8d",
+                                "yParity": "0x0"
+                            }
+                        }]
+                    }
+                }"#,
+            )
+            .unwrap();
+
+        let validator = item.pod_metadata.attestations[0].public_key;
+        let digest = AttestedTx::new(item.inner.transaction_hash, 0).hash_custom();
+        assert_eq!(
+            item.pod_metadata.attestations[0]
+                .signature
+                .recover_address_from_prehash(&digest)
+                .unwrap(),
+            validator
+        );
+
+        let committee = |epoch| Committee::new([validator], 1).with_epoch(epoch);
+        assert!(item.verify_against(&committee(0)).is_ok());
+        assert!(item.verify_against(&committee(1)).is_err());
+    }
+
+    #[tokio::test]
+    async fn light_client_refuses_unverified_subscriptions() {
+        use alloy_transport::mock::Asserter;
+        use pod_types::{rpc::filter::LogFilterBuilder, Timestamp};
+
+        let provider = crate::test_utils::provider(&Asserter::new()).into_light_client();
+
+        let err = provider
+            .subscribe_receipts(None, Timestamp::zero())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            VerificationError::from_transport(&err),
+            Some(VerificationError::Unverifiable {
+                method: "subscribe_receipts",
+                ..
+            })
+        ));
+        let err = provider
+            .subscribe_verifiable_logs(&LogFilterBuilder::new().build())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            VerificationError::from_transport(&err),
+            Some(VerificationError::Unverifiable {
+                use_instead: "subscribe_verified_logs",
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn cache_refreshes_on_newer_epoch() {
+        use alloy_primitives::Address;
+        use alloy_transport::mock::Asserter;
+
+        let committee = |epoch| Committee::new([Address::repeat_byte(1)], 1).with_epoch(epoch);
+        let asserter = Asserter::new();
+        let provider = crate::test_utils::provider(&asserter);
+
+        asserter.push_success(&committee(3));
+        assert_eq!(provider.committee_for(None).await.unwrap().epoch, 3);
+        // Served from the store.
+        assert_eq!(provider.committee_for(None).await.unwrap().epoch, 3);
+        assert_eq!(provider.committee_for(Some(3)).await.unwrap().epoch, 3);
+
+        // An older epoch never seen cannot be fetched anymore.
+        asserter.push_success(&committee(3));
+        assert!(matches!(
+            provider.committee_for(Some(2)).await,
+            Err(VerificationError::UnknownEpoch(2))
+        ));
+
+        asserter.push_success(&committee(4));
+        assert_eq!(provider.committee_for(Some(4)).await.unwrap().epoch, 4);
+        assert_eq!(provider.committee_for(Some(3)).await.unwrap().epoch, 3);
+        assert_eq!(provider.committee_store().epochs(), vec![3, 4]);
+        assert!(asserter.read_q().is_empty());
+    }
+}
@@ -3,6 +3,7 @@ use std::{sync::Arc, time::Duration};
 pub use alloy_provider;
 use alloy_rpc_types::TransactionReceipt;
 use anyhow::Context;
+use futures::{Stream, StreamExt};
 
 use crate::network::{PodNetwork, PodTransactionRequest};
 use alloy_eips::eip2718::Encodable2718;
@@ -10,8 +11,8 @@ use alloy_json_rpc::{RpcError, RpcRecv, RpcSend};
 use alloy_network::{EthereumWallet, Network, NetworkWallet, TransactionBuilder};
 use alloy_provider::{
     fillers::{JoinFill, RecommendedFillers, TxFiller, WalletFiller},
-    Identity, PendingTransactionBuilder, Provider, ProviderBuilder, ProviderLayer, RootProvider,
-    SendableTx,
+    Identity, PendingTransactionBuilder, PendingTransactionError, Provider, ProviderBuilder,
+    ProviderCall, ProviderLayer, RootProvider, SendableTx,
 };
 use alloy_pubsub::Subscription;
 use async_trait::async_trait;
@@ -22,14 +23,32 @@ use pod_types::{
     ledger::log::VerifiableLog,
     metadata::{MetadataWrappedItem, RegularReceiptMetadata},
     pagination::{ApiPaginatedResult, CursorPaginationRequest},
-    rpc::filter::LogFilter,
+    rpc::{account::TxStatus, filter::LogFilter},
 };
 
-use alloy_primitives::{Address, B256 as Hash, U256};
+use alloy_primitives::{Address, TxHash, B256 as Hash, U256};
 use pod_types::Timestamp;
 use serde::Deserialize;
 
-pub struct PodProviderBuilder<L, F>(ProviderBuilder<L, F, PodNetwork>);
+mod bridge;
+mod committee_store;
+mod diagnostics;
+mod light_client;
+mod orderbook;
+mod recovery;
+mod watch;
+
+pub use committee_store::CommitteeStore;
+pub use diagnostics::{AccountReport, NextStep};
+pub use light_client::{Verifiable, VerificationError};
+pub use recovery::{empty_tx_request, recover_request, IRecovery, RecoveryPolicy};
+pub use watch::{WatchError, WatchPolicy};
+
+pub struct PodProviderBuilder<L, F> {
+    inner: ProviderBuilder<L, F, PodNetwork>,
+    light_client: bool,
+    committee_store: Option<Arc<CommitteeStore>>,
+}
 
 impl
     PodProviderBuilder<
@@ -44,13 +63,22 @@ impl
     /// The returned builder has fillers preconfigured to automatically fill
     /// chain ID, nonce and gas price. Check [PodNetwork::RecommendedFillers] for details.
     pub fn with_recommended_settings() -> Self {
-        Self(PodProviderBuilder::default().0.with_recommended_fillers())
+        let builder = PodProviderBuilder::default();
+        PodProviderBuilder {
+            inner: builder.inner.with_recommended_fillers(),
+            light_client: builder.light_client,
+            committee_store: builder.committee_store,
+        }
     }
 }
 
 impl Default for PodProviderBuilder<Identity, Identity> {
     fn default() -> Self {
-        Self(ProviderBuilder::<_, _, PodNetwork>::default())
+        Self {
+            inner: ProviderBuilder::<_, _, PodNetwork>::default(),
+            light_client: false,
+            committee_store: None,
+        }
     }
 }
 
@@ -70,8 +98,30 @@ impl<L, F> PodProviderBuilder<L, F> {
         F: TxFiller<PodNetwork> + ProviderLayer<L::Provider, PodNetwork>,
         F::Provider: 'static,
     {
-        let alloy_provider = self.0.connect(url.as_ref()).await?;
-        Ok(PodProvider::new(alloy_provider))
+        let alloy_provider = self.inner.connect(url.as_ref()).await?;
+        let mut provider = PodProvider::new(alloy_provider);
+        if let Some(store) = self.committee_store {
+            provider = provider.with_committee_store(store);
+        }
+        Ok(if self.light_client {
+            provider.into_light_client()
+        } else {
+            provider
+        })
+    }
+
+    /// Verify every attested response against the committee instead of
+    /// trusting the RPC node. See [`PodProvider::into_light_client`].
+    pub fn light_client(mut self) -> Self {
+        self.light_client = true;
+        self
+    }
+
+    /// Use `store` for the committees responses are verified against, e.g. one
+    /// persisted with [`CommitteeStore::open`] or shared between providers.
+    pub fn committee_store(mut self, store: Arc<CommitteeStore>) -> Self {
+        self.committee_store = Some(store);
+        self
     }
 
     /// Configure a wallet to be used for signing transactions and spending funds.
@@ -79,7 +129,11 @@ impl<L, F> PodProviderBuilder<L, F> {
     where
         W: NetworkWallet<PodNetwork>,
     {
-        PodProviderBuilder::<_, _>(self.0.wallet(wallet))
+        PodProviderBuilder {
+            inner: self.inner.wallet(wallet),
+            light_client: self.light_client,
+            committee_store: self.committee_store,
+        }
     }
 
     pub fn with_private_key(
@@ -129,12 +183,16 @@ impl<L, F> PodProviderBuilder<L, F> {
 /// with pod-specific features.
 pub struct PodProvider {
     inner: Arc<dyn Provider<PodNetwork>>,
+    committees: Arc<CommitteeStore>,
+    light_client: bool,
 }
 
 impl Clone for PodProvider {
     fn clone(&self) -> Self {
         Self {
             inner: self.inner.clone(),
+            committees: self.committees.clone(),
+            light_client: self.light_client,
         }
     }
 }
@@ -156,6 +214,24 @@ impl Provider<PodNetwork> for PodProvider {
     ) -> TransportResult<PendingTransactionBuilder<PodNetwork>> {
         self.inner.send_transaction_internal(tx).await
     }
+
+    fn get_transaction_receipt(
+        &self,
+        hash: TxHash,
+    ) -> ProviderCall<(TxHash,), Option<<PodNetwork as Network>::ReceiptResponse>> {
+        let call = self.inner.get_transaction_receipt(hash);
+        if !self.light_client {
+            return call;
+        }
+        let this = self.clone();
+        ProviderCall::BoxedFuture(Box::pin(async move {
+            let receipt = call.await?;
+            if let Some(receipt) = &receipt {
+                this.verify(receipt).await?;
+            }
+            Ok(receipt)
+        }))
+    }
 }
 
 impl PodProvider {
@@ -163,19 +239,142 @@ impl PodProvider {
     pub fn new(provider: impl Provider<PodNetwork> + 'static) -> Self {
         Self {
             inner: Arc::new(provider),
+            committees: Default::default(),
+            light_client: false,
         }
     }
 
+    /// Use `store` for the committees responses are verified against.
+    pub fn with_committee_store(mut self, store: Arc<CommitteeStore>) -> Self {
+        self.committees = store;
+        self
+    }
+
+    /// The committees seen by this provider, by epoch.
+    pub fn committee_store(&self) -> &Arc<CommitteeStore> {
+        &self.committees
+    }
+
+    /// Turn this provider into a light client, which stops trusting the RPC
+    /// node for attested data.
+    ///
+    /// Receipts from [`Provider::get_transaction_receipt`] and
+    /// [`Self::get_receipts`], and logs from [`Self::get_verifiable_logs`],
+    /// are checked against the committee before they are returned; one that
+    /// fails comes back as a [`VerificationError`] inside the
+    /// [`TransportError`]. Each response is checked against the committee of
+    /// the epoch it was attested in, taken from the [`CommitteeStore`] and
+    /// refreshed from the node when it names an unseen epoch. Subscriptions
+    /// cannot be checked in place, so [`Self::subscribe_receipts`] and
+    /// [`Self::subscribe_verifiable_logs`] refuse with
+    /// [`VerificationError::Unverifiable`]; use
+    /// [`Self::subscribe_verified_receipts`] and
+    /// [`Self::subscribe_verified_logs`] for streams.
+    ///
+    /// [`PendingTransactionBuilder::get_receipt`] fetches through the root
+    /// provider and is not checked either; wait with
+    /// [`Self::get_verified_receipt`] instead.
+    pub fn into_light_client(mut self) -> Self {
+        self.light_client = true;
+        self
+    }
+
+    pub fn is_light_client(&self) -> bool {
+        self.light_client
+    }
+
     /// Gets the current committee members
     pub async fn get_committee(&self) -> TransportResult<Committee> {
         self.client().request_noparams("pod_getCommittee").await
     }
 
+    /// The committee that was active in `epoch`, or the newest one when the
+    /// epoch is not known. Fetches the current committee when the store has
+    /// not seen `epoch` yet.
+    pub async fn committee_for(&self, epoch: Option<u64>) -> Result<Committee, VerificationError> {
+        let stored = match epoch {
+            Some(epoch) => self.committees.get(epoch),
+            None => self.committees.latest(),
+        };
+        if let Some(committee) = stored {
+            return Ok(committee);
+        }
+
+        let committee = self
+            .get_committee()
+            .await
+            .map_err(VerificationError::CommitteeUnavailable)?;
+        if let Err(e) = self.committees.insert(committee.clone()) {
+            tracing::warn!(
+                "failed to persist committee of epoch {}: {e}",
+                committee.epoch
+            );
+        }
+        match epoch {
+            Some(epoch) if epoch != committee.epoch => Err(VerificationError::UnknownEpoch(epoch)),
+            _ => Ok(committee),
+        }
+    }
+
+    /// Check `item`'s attestations against the committee, regardless of
+    /// whether this provider is a light client.
+    pub async fn verify<T: Verifiable>(&self, item: &T) -> Result<(), VerificationError> {
+        let committee = self.committee_for(item.committee_epoch()).await?;
+        item.verify_against(&committee)
+            .map_err(|source| VerificationError::Invalid {
+                tx_hash: item.tx_hash(),
+                source,
+            })
+    }
+
+    /// Wait for `pending` like [`PendingTransactionBuilder::get_receipt`],
+    /// verifying the receipt when this provider is a light client.
+    pub async fn get_verified_receipt(
+        &self,
+        pending: PendingTransactionBuilder<PodNetwork>,
+    ) -> Result<<PodNetwork as Network>::ReceiptResponse, PendingTransactionError> {
+        let receipt = pending.get_receipt().await?;
+        self.verify_all([&receipt]).await?;
+        Ok(receipt)
+    }
+
+    async fn verify_all<'a, T: Verifiable + 'a>(
+        &self,
+        items: impl IntoIterator<Item = &'a T>,
+    ) -> TransportResult<()> {
+        if self.light_client {
+            for item in items {
+                self.verify(item).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Verify each item of `subscription` before yielding it.
+    pub fn verify_subscription<T>(
+        &self,
+        subscription: Subscription<T>,
+    ) -> impl Stream<Item = Result<T, VerificationError>>
+    where
+        T: Verifiable + RpcRecv,
+    {
+        let this = self.clone();
+        subscription.into_stream().then(move |item| {
+            let this = this.clone();
+            async move {
+                this.verify(&item).await?;
+                Ok(item)
+            }
+        })
+    }
+
     pub async fn get_verifiable_logs(
         &self,
         filter: &LogFilter,
     ) -> TransportResult<Vec<VerifiableLog>> {
-        self.client().request("eth_getLogs", (filter,)).await
+        let logs: Vec<VerifiableLog> = self.client().request("eth_getLogs", (filter,)).await?;
+        self.verify_all(&logs).await?;
+        Ok(logs)
     }
 
     pub async fn websocket_subscribe<Params, Resp>(
@@ -194,13 +393,32 @@ impl PodProvider {
         self.root().get_subscription(id).await
     }
 
+    /// Subscribe to logs matching `filter`, unverified. A light client
+    /// refuses; see [`Self::subscribe_verified_logs`].
     pub async fn subscribe_verifiable_logs(
         &self,
         filter: &LogFilter,
     ) -> TransportResult<Subscription<VerifiableLog>> {
+        if self.light_client {
+            return Err(VerificationError::Unverifiable {
+                method: "subscribe_verifiable_logs",
+                use_instead: "subscribe_verified_logs",
+            }
+            .into());
+        }
         self.websocket_subscribe("logs", filter).await
     }
 
+    /// Like [`Self::subscribe_verifiable_logs`], but every log is verified
+    /// against the committee before it is yielded.
+    pub async fn subscribe_verified_logs(
+        &self,
+        filter: &LogFilter,
+    ) -> TransportResult<impl Stream<Item = Result<VerifiableLog, VerificationError>>> {
+        let subscription = self.websocket_subscribe("logs", filter).await?;
+        Ok(self.verify_subscription(subscription))
+    }
+
     pub async fn wait_past_perfect_time(&self, timestamp: Timestamp) -> TransportResult<()> {
         const INVALID_PARAMS_CODE: i64 = -32602;
         const PPT_TOO_FAR_MSG: &str = "Requested PPT is too far in the future";
@@ -236,6 +454,8 @@ impl PodProvider {
     ///
     /// The parameters `address` and `since` allow to optionally filter receipts.
     /// Pass `None` and `Timestamp::zero()` respectively for wildcards.
+    /// The receipts are unverified, so a light client refuses; see
+    /// [`Self::subscribe_verified_receipts`].
     pub async fn subscribe_receipts(
         &self,
         address: Option<Address>,
@@ -243,22 +463,55 @@ impl PodProvider {
     ) -> TransportResult<
         Subscription<MetadataWrappedItem<TransactionReceipt, RegularReceiptMetadata>>,
     > {
+        if self.light_client {
+            return Err(VerificationError::Unverifiable {
+                method: "subscribe_receipts",
+                use_instead: "subscribe_verified_receipts",
+            }
+            .into());
+        }
         self.websocket_subscribe("pod_receipts", (address, since))
             .await
     }
 
+    /// Like [`Self::subscribe_receipts`], but every receipt is verified
+    /// against the committee before it is yielded.
+    pub async fn subscribe_verified_receipts(
+        &self,
+        address: Option<Address>,
+        since: Timestamp,
+    ) -> TransportResult<
+        impl Stream<
+            Item = Result<
+                MetadataWrappedItem<TransactionReceipt, RegularReceiptMetadata>,
+                VerificationError,
+            >,
+        >,
+    > {
+        let subscription = self
+            .websocket_subscribe("pod_receipts", (address, since))
+            .await?;
+        Ok(self.verify_subscription(subscription))
+    }
+
     pub async fn get_receipts(
         &self,
         address: Option<Address>,
         since_micros: u64,
         paginator: Option<CursorPaginationRequest>,
     ) -> TransportResult<ApiPaginatedResult<<PodNetwork as Network>::ReceiptResponse>> {
-        self.client()
+        let receipts: ApiPaginatedResult<_> = self
+            .client()
             .request("pod_listReceipts", &(address, since_micros, paginator))
-            .await
+            .await?;
+        self.verify_all(&receipts.items).await?;
+        Ok(receipts)
     }
 
     /// Transfer specified `amount` funds to the `to` account.
+    ///
+    /// Fails with a [`WatchError`] if the transfer reverts or does not
+    /// finalize under the default [`WatchPolicy`].
     pub async fn transfer(
         &self,
         to: Address,
@@ -270,11 +523,35 @@ impl PodProvider {
 
         let pending_tx = self.send_transaction(tx).await?;
 
-        let receipt = pending_tx.get_receipt().await?;
+        let receipt = self
+            .wait_for_tx(*pending_tx.tx_hash(), &WatchPolicy::default())
+            .await?;
 
         Ok(receipt)
     }
 
+    /// Fill in the fields of `tx` feeding its hash that are still missing, so
+    /// it can go to [`sign_transaction_bytes`]: the chain id, `from`'s next
+    /// nonce and an estimated gas limit.
+    pub async fn prepare_transaction(
+        &self,
+        mut tx: PodTransactionRequest,
+        from: Address,
+    ) -> TransportResult<PodTransactionRequest> {
+        tx.set_from(from);
+        if tx.chain_id().is_none() {
+            tx.set_chain_id(self.get_chain_id().await?);
+        }
+        if tx.nonce().is_none() {
+            tx.set_nonce(self.get_transaction_count(from).await?);
+        }
+        if tx.gas_limit().is_none() {
+            let gas_limit = self.estimate_gas(tx.clone()).await?;
+            tx.set_gas_limit(gas_limit);
+        }
+        Ok(tx)
+    }
+
     /// Submit an already-signed transaction through `pod_sendRawTransaction`,
     /// which waits for attestations (up to `timeout`, default 10s server-side)
     /// and classifies the outcome.
@@ -284,7 +561,7 @@ impl PodProvider {
     ///
     /// `Ok` means only that no terminal verdict was reached: it covers a
     /// transaction that executed and one still gathering votes, and the response
-    /// cannot distinguish them. Wait for the receipt to settle that.
+    /// cannot distinguish them. [`Self::wait_for_tx`] settles that.
     pub async fn pod_send_raw_transaction(
         &self,
         encoded_tx: &[u8],
@@ -298,6 +575,12 @@ impl PodProvider {
             .map_err(PodSendError::from)
     }
 
+    /// Where `tx_hash` is in the pipeline, with the votes it has so far while
+    /// pending.
+    pub async fn get_tx_status(&self, tx_hash: Hash) -> TransportResult<TxStatus> {
+        self.client().request("pod_getTxStatus", (tx_hash,)).await
+    }
+
     pub async fn past_perfect_time(&self, contract: Address) -> TransportResult<Timestamp> {
         let micros_str: String = self
             .client()
@@ -319,7 +602,7 @@ impl PodProvider {
 /// different transactions at one nonce split validator votes so neither reaches
 /// quorum, which locks the account until a recovery transaction clears it. `tx`
 /// must already carry every field feeding the hash, since nothing here fills any
-/// in.
+/// in. [`crate::sender::SafeSender`] keeps to this for a whole account.
 pub async fn sign_transaction_bytes(
     tx: PodTransactionRequest,
     key: crate::SigningKey,
@@ -384,6 +667,7 @@ const REJECTED_CODE: i64 = -32003;
 pub enum PodSendError {
     /// No transaction at this nonce can reach quorum; only a recovery transaction
     /// at a fresh nonce, pointing at `recovery_target`, advances the account.
+    /// [`PodProvider::recover_account`] sends one.
     AccountLocked {
         recovery_target: Hash,
         recovery_target_nonce: u64,
@@ -392,7 +676,8 @@ pub enum PodSendError {
     /// a replacement at the same nonce is safe.
     Rejected { errors: Vec<String> },
     /// The head nonce has votes but no certificate; a deadline-free empty
-    /// self-transfer at `nonce` forces one.
+    /// self-transfer at `nonce` forces one. [`PodProvider::send_empty_tx`]
+    /// sends it.
     EmptyTxRequired { nonce: u64, errors: Vec<String> },
     /// Transport failure, or an unclassified server error. May still have been
     /// delivered.
@@ -474,25 +759,21 @@ impl From<RpcError<TransportErrorKind>> for PodSendError {
 #[cfg(test)]
 mod send_tests {
     use super::*;
-    use alloy_json_rpc::ErrorPayload;
+    use crate::test_utils::rpc_error;
 
-    fn rpc_error(code: i64, message: &str, data: Option<&str>) -> RpcError<TransportErrorKind> {
-        RpcError::ErrorResp(ErrorPayload {
-            code,
-            message: message.to_string().into(),
-            data: data.map(|d| serde_json::value::RawValue::from_string(d.to_string()).unwrap()),
-        })
+    fn send_error(code: i64, message: &str, data: Option<&str>) -> PodSendError {
+        RpcError::ErrorResp(rpc_error(code, message, data)).into()
     }
 
     #[test]
     fn classifies_account_locked() {
-        let err = PodSendError::from(rpc_error(
+        let err = send_error(
             ACCOUNT_LOCKED_CODE,
             "Account locked",
             Some(
                 r#"{"recovery_target":"0x596a7bd66762e52a914565f707d0fc2a479e818b3e7587ea9a6615c9290be13d","recovery_target_nonce":22}"#,
             ),
-        ));
+        );
         match err {
             PodSendError::AccountLocked {
                 recovery_target_nonce,
@@ -502,18 +783,18 @@ mod send_tests {
         }
         // Unreadable `data` degrades to `Transport`, i.e. not terminal, so the
         // caller resends rather than replaces. The node always sends `data`.
-        let no_data = PodSendError::from(rpc_error(ACCOUNT_LOCKED_CODE, "Account locked", None));
+        let no_data = send_error(ACCOUNT_LOCKED_CODE, "Account locked", None);
         assert!(matches!(no_data, PodSendError::Transport(_)));
         assert!(!no_data.is_terminal());
     }
 
     #[test]
     fn classifies_rejected_with_and_without_data() {
-        let with_data = PodSendError::from(rpc_error(
+        let with_data = send_error(
             REJECTED_CODE,
             "Transaction rejected",
             Some(r#"[{"error":"insufficient balance"},{"error":"bad nonce"}]"#),
-        ));
+        );
         match with_data {
             PodSendError::Rejected { errors } => {
                 assert_eq!(errors, vec!["insufficient balance", "bad nonce"]);
@@ -522,7 +803,7 @@ mod send_tests {
         }
 
         // The message alone is meaningful, so a missing `data` still classifies.
-        match PodSendError::from(rpc_error(REJECTED_CODE, "Transaction rejected: nope", None)) {
+        match send_error(REJECTED_CODE, "Transaction rejected: nope", None) {
             PodSendError::Rejected { errors } => {
                 assert_eq!(errors, vec!["Transaction rejected: nope"]);
             }
@@ -532,11 +813,11 @@ mod send_tests {
 
     #[test]
     fn classifies_empty_tx_required() {
-        match PodSendError::from(rpc_error(
+        match send_error(
             EMPTY_TX_REQUIRED_CODE,
             "Empty transaction required to make progress",
             Some(r#"{"nonce":23,"errors":["split"]}"#),
-        )) {
+        ) {
             PodSendError::EmptyTxRequired { nonce, errors } => {
                 assert_eq!(nonce, 23);
                 assert_eq!(errors, vec!["split"]);
@@ -548,7 +829,7 @@ mod send_tests {
     /// Must not be terminal: replacing here could put a second tx at the nonce.
     #[test]
     fn unknown_codes_are_transport_and_not_terminal() {
-        let err = PodSendError::from(rpc_error(-32000, "something else", None));
+        let err = send_error(-32000, "something else", None);
         assert!(matches!(err, PodSendError::Transport(_)));
         assert!(!err.is_terminal());
     }
@@ -0,0 +1,212 @@
+use std::future::Future;
+
+use alloy_primitives::Address;
+use alloy_provider::Provider;
+use alloy_transport::TransportResult;
+use pod_types::{
+    pagination::CursorPage,
+    rpc::orderbook::{
+        AccountPageQuery, BackstopTransfer, BackstopTransfersPage, Candle, CandlesQuery, Fill,
+        FillsQuery, FillsResponse, FundingRate, Market, OraclePrice, Order, OrderbookSnapshot,
+        OrdersPage, OrdersQuery, PositionsResponse, RankedPositionsResponse, TriggerOrder,
+        TriggersPage,
+    },
+    Hash,
+};
+
+use super::PodProvider;
+
+/// Orderbook market data, served by the `ob_*` methods.
+impl PodProvider {
+    /// Every market with its metadata and 24-hour statistics.
+    pub async fn get_markets(&self) -> TransportResult<Vec<Market>> {
+        self.client().request_noparams("ob_getMarkets").await
+    }
+
+    /// Price levels of a market, up to `depth` per side.
+    pub async fn get_orderbook(
+        &self,
+        orderbook_id: Hash,
+        depth: Option<u64>,
+    ) -> TransportResult<OrderbookSnapshot> {
+        match depth {
+            Some(depth) => {
+                self.client()
+                    .request("ob_getOrderbook", (orderbook_id, depth))
+                    .await
+            }
+            None => {
+                self.client()
+                    .request("ob_getOrderbook", (orderbook_id,))
+                    .await
+            }
+        }
+    }
+
+    /// Candles of a market, newest first.
+    pub async fn get_candles(
+        &self,
+        orderbook_id: Hash,
+        query: &CandlesQuery,
+    ) -> TransportResult<Vec<Candle>> {
+        self.client()
+            .request("ob_getCandles", (orderbook_id, query))
+            .await
+    }
+
+    /// One page of `account`'s orders, newest first.
+    pub async fn get_orders(
+        &self,
+        account: Address,
+        query: &OrdersQuery,
+    ) -> TransportResult<OrdersPage> {
+        self.client()
+            .request("ob_getOrders", (account, query))
+            .await
+    }
+
+    /// All of `account`'s orders matching `query`, following the cursor from
+    /// `query.cursor` to the last page.
+    pub async fn get_all_orders(
+        &self,
+        account: Address,
+        query: OrdersQuery,
+    ) -> TransportResult<Vec<Order>> {
+        collect_pages(query.cursor.clone(), |cursor| {
+            let query = OrdersQuery {
+                cursor,
+                ..query.clone()
+            };
+            async move { self.get_orders(account, &query).await }
+        })
+        .await
+    }
+
+    pub async fn get_positions(&self, account: Address) -> TransportResult<PositionsResponse> {
+        self.client().request("ob_getPositions", (account,)).await
+    }
+
+    /// `account`'s fills, newest first.
+    pub async fn get_fills(
+        &self,
+        account: Address,
+        query: &FillsQuery,
+    ) -> TransportResult<Vec<Fill>> {
+        let response: FillsResponse = self
+            .client()
+            .request("ob_getFills", (account, query))
+            .await?;
+        Ok(response.fills)
+    }
+
+    /// One page of the triggers `account` has armed.
+    pub async fn get_triggers(
+        &self,
+        account: Address,
+        query: &AccountPageQuery,
+    ) -> TransportResult<TriggersPage> {
+        self.client()
+            .request("ob_getTriggers", (account, query))
+            .await
+    }
+
+    pub async fn get_all_triggers(
+        &self,
+        account: Address,
+        query: AccountPageQuery,
+    ) -> TransportResult<Vec<TriggerOrder>> {
+        collect_pages(query.cursor.clone(), |cursor| {
+            let query = AccountPageQuery {
+                cursor,
+                ..query.clone()
+            };
+            async move { self.get_triggers(account, &query).await }
+        })
+        .await
+    }
+
+    /// One page of `account`'s positions swept to the backstop, newest first.
+    pub async fn get_backstop_transfers(
+        &self,
+        account: Address,
+        query: &AccountPageQuery,
+    ) -> TransportResult<BackstopTransfersPage> {
+        self.client()
+            .request("ob_getBackstopTransfers", (account, query))
+            .await
+    }
+
+    pub async fn get_all_backstop_transfers(
+        &self,
+        account: Address,
+        query: AccountPageQuery,
+    ) -> TransportResult<Vec<BackstopTransfer>> {
+        collect_pages(query.cursor.clone(), |cursor| {
+            let query = AccountPageQuery {
+                cursor,
+                ..query.clone()
+            };
+            async move { self.get_backstop_transfers(account, &query).await }
+        })
+        .await
+    }
+
+    /// Oracle and mark prices of one perp market, or of all of them.
+    pub async fn get_oracle_prices(
+        &self,
+        orderbook_id: Option<Hash>,
+    ) -> TransportResult<Vec<OraclePrice>> {
+        match orderbook_id {
+            Some(id) => self.client().request("ob_getOraclePrices", (id,)).await,
+            None => self.client().request_noparams("ob_getOraclePrices").await,
+        }
+    }
+
+    /// Funding state of one perp market, or of all of them.
+    pub async fn get_funding_rates(
+        &self,
+        orderbook_id: Option<Hash>,
+    ) -> TransportResult<Vec<FundingRate>> {
+        match orderbook_id {
+            Some(id) => self.client().request("ob_getFundingRates", (id,)).await,
+            None => self.client().request_noparams("ob_getFundingRates").await,
+        }
+    }
+
+    /// The PnL leaderboard window `[offset, offset + limit)`, plus the rank of
+    /// `account` when given.
+    pub async fn get_ranked_positions(
+        &self,
+        limit: Option<u64>,
+        offset: Option<u64>,
+        account: Option<Address>,
+    ) -> TransportResult<RankedPositionsResponse> {
+        self.client()
+            .request("ob_getRankedPositions", (limit, offset, account))
+            .await
+    }
+}
+
+async fn collect_pages<P, F, Fut>(
+    mut cursor: Option<String>,
+    mut fetch: F,
+) -> TransportResult<Vec<P::Item>>
+where
+    P: CursorPage,
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = TransportResult<P>>,
+{
+    let mut items = Vec::new();
+    loop {
+        let page = fetch(cursor.take()).await?;
+        let next = page.next_cursor().map(str::to_owned);
+        let page_items = page.into_items();
+        let empty = page_items.is_empty();
+        items.extend(page_items);
+        match next {
+            // An empty page with a cursor would otherwise loop forever.
+            Some(next) if !empty => cursor = Some(next),
+            _ => return Ok(items),
+        }
+    }
+}
@@ -0,0 +1,454 @@
+use std::time::Duration;
+
+use alloy_network::TransactionBuilder;
+use alloy_primitives::{Address, Bytes, U256};
+use alloy_provider::Provider;
+use alloy_sol_types::SolCall;
+use alloy_transport::TransportResult;
+use anyhow::Context;
+use pod_types::{rpc::account::RecoveryTarget, Hash};
+
+use super::{sign_transaction_bytes, PodProvider, PodSendError, PodSendResponse};
+use crate::{network::PodTransactionRequest, precompiles::RECOVERY_PRECOMPILE_ADDRESS};
+
+alloy_sol_types::sol! {
+    /// The recovery precompile at
+    /// [`RECOVERY_PRECOMPILE_ADDRESS`](crate::precompiles::RECOVERY_PRECOMPILE_ADDRESS).
+    #[derive(Debug, PartialEq, Eq)]
+    interface IRecovery {
+        function recover(bytes32 txHash, uint64 nonce) external;
+    }
+}
+
+/// A `recover` of the sender's account to `target`.
+pub fn recover_request(target: RecoveryTarget) -> PodTransactionRequest {
+    let call = IRecovery::recoverCall {
+        txHash: target.hash,
+        nonce: target.nonce,
+    };
+    PodTransactionRequest::default()
+        .with_to(RECOVERY_PRECOMPILE_ADDRESS)
+        .with_input(Bytes::from(call.abi_encode()))
+}
+
+/// Gas of a plain value transfer.
+const TRANSFER_GAS: u64 = 21_000;
+
+/// The empty self-transfer from `account` at `nonce` that forces a certificate
+/// for a nonce stuck with votes but none, as asked for by
+/// [`PodSendError::EmptyTxRequired`]. Fees are those of
+/// [`PodTransactionRequest::default`].
+pub fn empty_tx_request(account: Address, nonce: u64) -> PodTransactionRequest {
+    PodTransactionRequest::default()
+        .with_from(account)
+        .with_to(account)
+        .with_value(U256::ZERO)
+        .with_nonce(nonce)
+        .with_gas_limit(TRANSFER_GAS)
+}
+
+/// How hard to try unblocking an account.
+#[derive(Debug, Clone)]
+pub struct RecoveryPolicy {
+    /// Recovery transactions to send before giving up. A recovery can itself
+    /// end up locked, in which case a new one is sent against the updated
+    /// target.
+    pub max_attempts: u32,
+    /// How often `pod_getLastFinalizedNonce` is polled after sending one.
+    pub poll_interval: Duration,
+    /// How long to wait for a recovery to finalize.
+    pub timeout: Duration,
+    /// Whether [`PodProvider::send_with_recovery`] answers
+    /// [`PodSendError::EmptyTxRequired`] with an empty self-transfer.
+    pub send_empty_tx: bool,
+}
+
+impl Default for RecoveryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            poll_interval: Duration::from_millis(500),
+            timeout: Duration::from_secs(30),
+            send_empty_tx: true,
+        }
+    }
+}
+
+impl PodProvider {
+    /// The transaction `account` has to recover to, or `None` when it is not
+    /// locked.
+    pub async fn get_recovery_target_tx(
+        &self,
+        account: Address,
+    ) -> TransportResult<Option<RecoveryTarget>> {
+        self.client()
+            .request("pod_getRecoveryTargetTx", (account,))
+            .await
+    }
+
+    /// `account`'s last finalized nonce, or `None` when it never finalized a
+    /// transaction.
+    pub async fn get_last_finalized_nonce(&self, account: Address) -> TransportResult<Option<u64>> {
+        self.client()
+            .request("pod_getLastFinalizedNonce", (account,))
+            .await
+    }
+
+    /// Unlock the account of `key` through the recovery precompile, and wait
+    /// until its finalized nonce has advanced. Returns the hash of the
+    /// recovery transaction, or `None` when the account was not locked.
+    #[tracing::instrument(skip(self, key))]
+    pub async fn recover_account(
+        &self,
+        key: crate::SigningKey,
+        policy: &RecoveryPolicy,
+    ) -> anyhow::Result<Option<Hash>> {
+        let account = crate::PrivateKeySigner::from_signing_key(key.clone()).address();
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let Some(target) = self
+                .get_recovery_target_tx(account)
+                .await
+                .context("getting recovery target")?
+            else {
+                return Ok(None);
+            };
+            let finalized = self
+                .get_last_finalized_nonce(account)
+                .await
+                .context("getting last finalized nonce")?;
+
+            let request = self
+                .prepare_transaction(recover_request(target), account)
+                .await
+                .context("preparing recovery TX")?;
+            let raw = sign_transaction_bytes(request, key.clone()).await?;
+            let tx_hash = match self.pod_send_raw_transaction(&raw, None).await {
+                Ok(response) => response.tx_hash,
+                Err(PodSendError::AccountLocked { .. }) if attempt < policy.max_attempts => {
+                    tracing::warn!(attempt, "recovery TX locked the account again");
+                    continue;
+                }
+                Err(err) => return Err(err).context("sending recovery TX"),
+            };
+
+            self.wait_for_finalized_nonce(account, finalized, policy)
+                .await?;
+            return Ok(Some(tx_hash));
+        }
+    }
+
+    /// Send the empty self-transfer at `nonce` that
+    /// [`PodSendError::EmptyTxRequired`] asks for, and wait until `nonce` is
+    /// finalized, after which the account can progress.
+    #[tracing::instrument(skip(self, key))]
+    pub async fn send_empty_tx(
+        &self,
+        key: crate::SigningKey,
+        nonce: u64,
+        policy: &RecoveryPolicy,
+    ) -> anyhow::Result<Hash> {
+        let account = crate::PrivateKeySigner::from_signing_key(key.clone()).address();
+        let request = self
+            .prepare_transaction(empty_tx_request(account, nonce), account)
+            .await
+            .context("preparing empty TX")?;
+        let raw = sign_transaction_bytes(request, key).await?;
+        let response = self
+            .pod_send_raw_transaction(&raw, None)
+            .await
+            .context("sending empty TX")?;
+        self.wait_for_finalized_nonce(account, nonce.checked_sub(1), policy)
+            .await?;
+        Ok(response.tx_hash)
+    }
+
+    /// Sign `tx` with `key` and send it through `pod_sendRawTransaction`. If
+    /// the account turns out to be locked, it is recovered, and if its head
+    /// nonce needs an empty transaction that is sent when the policy allows;
+    /// either way `tx` is then re-signed at the next nonce. At most
+    /// `policy.max_attempts` times.
+    #[tracing::instrument(skip(self, tx, key))]
+    pub async fn send_with_recovery(
+        &self,
+        mut tx: PodTransactionRequest,
+        key: crate::SigningKey,
+        policy: &RecoveryPolicy,
+    ) -> anyhow::Result<PodSendResponse> {
+        let from = crate::PrivateKeySigner::from_signing_key(key.clone()).address();
+        let mut attempts = 0;
+        loop {
+            let request = self
+                .prepare_transaction(tx.clone(), from)
+                .await
+                .context("preparing TX")?;
+            let raw = sign_transaction_bytes(request, key.clone()).await?;
+            match self.pod_send_raw_transaction(&raw, None).await {
+                Err(PodSendError::AccountLocked { .. }) if attempts < policy.max_attempts => {
+                    self.recover_account(key.clone(), policy).await?;
+                }
+                Err(PodSendError::EmptyTxRequired { nonce, .. })
+                    if policy.send_empty_tx && attempts < policy.max_attempts =>
+                {
+                    self.send_empty_tx(key.clone(), nonce, policy).await?;
+                }
+                result => return result.context("sending TX"),
+            }
+            attempts += 1;
+            // The nonce it was signed at may have been spent meanwhile.
+            tx.nonce = None;
+        }
+    }
+
+    /// Wait until `account`'s last finalized nonce is above `finalized`.
+    async fn wait_for_finalized_nonce(
+        &self,
+        account: Address,
+        finalized: Option<u64>,
+        policy: &RecoveryPolicy,
+    ) -> anyhow::Result<()> {
+        tokio::time::timeout(policy.timeout, async {
+            loop {
+                let current = self.get_last_finalized_nonce(account).await?;
+                if current.is_some_and(|current| finalized.is_none_or(|f| current > f)) {
+                    return TransportResult::Ok(());
+                }
+                tokio::time::sleep(policy.poll_interval).await;
+            }
+        })
+        .await
+        .context("timed out waiting for the finalized nonce to advance")?
+        .context("getting last finalized nonce")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::U64;
+    use alloy_transport::mock::Asserter;
+
+    use super::*;
+    use crate::{
+        provider::{ACCOUNT_LOCKED_CODE, EMPTY_TX_REQUIRED_CODE},
+        test_utils::{accepted, key, provider, rpc_error},
+    };
+
+    const TX: Hash = Hash::repeat_byte(0x11);
+    const RECOVERY: Hash = Hash::repeat_byte(0x22);
+
+    fn policy(max_attempts: u32) -> RecoveryPolicy {
+        RecoveryPolicy {
+            max_attempts,
+            poll_interval: Duration::from_secs(1),
+            timeout: Duration::from_millis(2_500),
+            send_empty_tx: true,
+        }
+    }
+
+    fn account() -> Address {
+        crate::PrivateKeySigner::from_signing_key(key()).address()
+    }
+
+    /// Answer [`PodProvider::prepare_transaction`] for a request without
+    /// chain id, nonce or gas limit.
+    fn push_prepare(asserter: &Asserter, nonce: u64) {
+        asserter.push_success(&U64::from(0x50d));
+        asserter.push_success(&U64::from(nonce));
+        asserter.push_success(&U64::from(TRANSFER_GAS));
+    }
+
+    fn push_target(asserter: &Asserter, nonce: u64) {
+        asserter.push_success(&RecoveryTarget { hash: TX, nonce });
+    }
+
+    fn push_locked(asserter: &Asserter, nonce: u64) {
+        asserter.push_failure(rpc_error(
+            ACCOUNT_LOCKED_CODE,
+            "Account locked",
+            Some(&format!(
+                r#"{{"recovery_target":"{TX}","recovery_target_nonce":{nonce}}}"#
+            )),
+        ));
+    }
+
+    fn push_empty_tx_required(asserter: &Asserter, nonce: u64) {
+        asserter.push_failure(rpc_error(
+            EMPTY_TX_REQUIRED_CODE,
+            "Empty transaction required to make progress",
+            Some(&format!(r#"{{"nonce":{nonce},"errors":[]}}"#)),
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn recovery_is_resent_when_it_locks_again() {
+        let asserter = Asserter::new();
+        push_target(&asserter, 4);
+        asserter.push_success(&Some(3u64));
+        push_prepare(&asserter, 5);
+        push_locked(&asserter, 5);
+        // Against the updated target.
+        push_target(&asserter, 5);
+        asserter.push_success(&Some(3u64));
+        push_prepare(&asserter, 6);
+        asserter.push_success(&accepted(RECOVERY));
+        asserter.push_success(&Some(3u64));
+        asserter.push_success(&Some(6u64));
+
+        let recovered = provider(&asserter)
+            .recover_account(key(), &policy(2))
+            .await
+            .unwrap();
+        assert_eq!(recovered, Some(RECOVERY));
+        assert!(asserter.read_q().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn recovery_gives_up_after_max_attempts() {
+        let asserter = Asserter::new();
+        push_target(&asserter, 4);
+        asserter.push_success(&Some(3u64));
+        push_prepare(&asserter, 5);
+        push_locked(&asserter, 5);
+
+        let err = provider(&asserter)
+            .recover_account(key(), &policy(1))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<PodSendError>(),
+            Some(PodSendError::AccountLocked {
+                recovery_target_nonce: 5,
+                ..
+            })
+        ));
+        assert!(asserter.read_q().is_empty());
+
+        // Nothing to do for an account that is not locked.
+        asserter.push_success(&None::<RecoveryTarget>);
+        let recovered = provider(&asserter)
+            .recover_account(key(), &policy(1))
+            .await
+            .unwrap();
+        assert_eq!(recovered, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn send_is_retried_after_recovering() {
+        let asserter = Asserter::new();
+        push_prepare(&asserter, 5);
+        push_locked(&asserter, 4);
+        // `recover_account`.
+        push_target(&asserter, 4);
+        asserter.push_success(&Some(3u64));
+        push_prepare(&asserter, 5);
+        asserter.push_success(&accepted(RECOVERY));
+        asserter.push_success(&Some(5u64));
+        // Re-signed at the next nonce.
+        push_prepare(&asserter, 6);
+        asserter.push_success(&accepted(TX));
+
+        let tx = PodTransactionRequest::default()
+            .with_to(Address::repeat_byte(0x33))
+            .with_value(U256::from(1));
+        let response = provider(&asserter)
+            .send_with_recovery(tx, key(), &policy(1))
+            .await
+            .unwrap();
+        assert_eq!(response.tx_hash, TX);
+        assert!(asserter.read_q().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn send_stops_retrying_after_max_attempts() {
+        let asserter = Asserter::new();
+        push_prepare(&asserter, 5);
+        push_empty_tx_required(&asserter, 5);
+        // `send_empty_tx`: only the chain id is left to fill.
+        asserter.push_success(&U64::from(0x50d));
+        asserter.push_success(&accepted(RECOVERY));
+        asserter.push_success(&Some(5u64));
+        // The one retry the policy allows.
+        push_prepare(&asserter, 6);
+        push_empty_tx_required(&asserter, 6);
+
+        let tx = PodTransactionRequest::default()
+            .with_to(Address::repeat_byte(0x33))
+            .with_value(U256::from(1));
+        let err = provider(&asserter)
+            .send_with_recovery(tx.clone(), key(), &policy(1))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<PodSendError>(),
+            Some(PodSendError::EmptyTxRequired { nonce: 6, .. })
+        ));
+        assert!(asserter.read_q().is_empty());
+
+        // Not retried at all when the policy doesn't send empty TXs.
+        push_prepare(&asserter, 5);
+        push_empty_tx_required(&asserter, 5);
+        let policy = RecoveryPolicy {
+            send_empty_tx: false,
+            ..policy(3)
+        };
+        let err = provider(&asserter)
+            .send_with_recovery(tx, key(), &policy)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<PodSendError>(),
+            Some(PodSendError::EmptyTxRequired { nonce: 5, .. })
+        ));
+        assert!(asserter.read_q().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn waiting_for_the_finalized_nonce_times_out() {
+        let asserter = Asserter::new();
+        // Polled at 0s, 1s and 2s; the 2.5s timeout hits before the next.
+        for _ in 0..3 {
+            asserter.push_success(&Some(3u64));
+        }
+
+        let err = provider(&asserter)
+            .wait_for_finalized_nonce(account(), Some(3), &policy(1))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{err}");
+        assert!(asserter.read_q().is_empty());
+    }
+
+    #[test]
+    fn recover_request_targets_precompile() {
+        let target: RecoveryTarget = serde_json::from_str(
+            r#"{"hash":"0x596a7bd66762e52a914565f707d0fc2a479e818b3e7587ea9a6615c9290be13d","nonce":22}"#,
+        )
+        .unwrap();
+        let request = recover_request(target);
+
+        assert_eq!(request.to, Some(RECOVERY_PRECOMPILE_ADDRESS.into()));
+        let input = request.input.input().unwrap();
+        assert_eq!(input[..4], IRecovery::recoverCall::SELECTOR);
+        let call = IRecovery::recoverCall::abi_decode(input).unwrap();
+        assert_eq!((call.txHash, call.nonce), (target.hash, 22));
+    }
+
+    #[test]
+    fn empty_tx_is_zero_value_self_transfer() {
+        let account = Address::repeat_byte(0x11);
+        let request = empty_tx_request(account, 7);
+        let defaults = PodTransactionRequest::default();
+
+        assert_eq!(request.from, Some(account));
+        assert_eq!(request.to, Some(account.into()));
+        assert_eq!(request.value, Some(U256::ZERO));
+        assert_eq!(request.nonce, Some(7));
+        assert!(request.input.input().is_none());
+        assert_eq!(request.max_fee_per_gas, defaults.max_fee_per_gas);
+        assert_eq!(
+            request.max_priority_fee_per_gas,
+            defaults.max_priority_fee_per_gas
+        );
+    }
+}
@@ -0,0 +1,254 @@
+use std::time::Duration;
+
+use alloy_provider::Provider;
+use alloy_transport::{TransportError, TransportResult};
+use futures::{Stream, StreamExt};
+use pod_types::{rpc::account::TxStatus, Hash};
+
+use super::PodProvider;
+use crate::network::PodReceiptResponse;
+
+/// How long and how often a transaction is polled for with
+/// `pod_getTxStatus`.
+#[derive(Debug, Clone)]
+pub struct WatchPolicy {
+    /// Give up once the transaction has not finalized for this long.
+    pub timeout: Duration,
+    /// Wait before the first poll after an unchanged status. Doubles while the
+    /// status stays the same, and starts over when it changes.
+    pub initial_interval: Duration,
+    pub max_interval: Duration,
+}
+
+impl Default for WatchPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(60),
+            initial_interval: Duration::from_millis(100),
+            max_interval: Duration::from_secs(2),
+        }
+    }
+}
+
+/// Why a watched transaction did not produce a successful receipt.
+#[derive(Debug)]
+pub enum WatchError {
+    /// Executed, but reverted.
+    Reverted(Box<PodReceiptResponse>),
+    /// Another transaction was finalized at its nonce, so it never will be.
+    Superseded(TxStatus),
+    /// Not finalized, or finalized without a receipt, within the timeout.
+    /// Carries the last status seen, if any; the transaction may still
+    /// finalize later.
+    Timeout(Option<TxStatus>),
+    Transport(TransportError),
+}
+
+impl std::fmt::Display for WatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Reverted(receipt) => {
+                write!(f, "TX {} reverted", receipt.transaction_hash)
+            }
+            Self::Superseded(_) => {
+                write!(f, "another TX was finalized at the same nonce")
+            }
+            Self::Timeout(Some(TxStatus::Pending { votes, quorum, .. })) => write!(
+                f,
+                "TX not finalized in time with {} of {quorum} votes",
+                votes.total_tx_votes
+            ),
+            Self::Timeout(Some(TxStatus::Finalized { .. })) => {
+                write!(f, "TX finalized, but its receipt did not arrive in time")
+            }
+            Self::Timeout(_) => write!(f, "TX not finalized in time"),
+            Self::Transport(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for WatchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Transport(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<TransportError> for WatchError {
+    fn from(err: TransportError) -> Self {
+        Self::Transport(err)
+    }
+}
+
+impl PodProvider {
+    /// Every status `tx_hash` goes through, polled with backoff as set by
+    /// `policy`. Ends after the finalized status, or at the first error.
+    pub fn watch_tx_status(
+        &self,
+        tx_hash: Hash,
+        policy: &WatchPolicy,
+    ) -> impl Stream<Item = TransportResult<TxStatus>> {
+        let this = self.clone();
+        let policy = policy.clone();
+        let start = (None::<TxStatus>, Duration::ZERO, false);
+        futures::stream::unfold(start, move |(last, mut interval, done)| {
+            let this = this.clone();
+            let policy = policy.clone();
+            async move {
+                if done {
+                    return None;
+                }
+                loop {
+                    tokio::time::sleep(interval).await;
+                    let status = match this.get_tx_status(tx_hash).await {
+                        Ok(status) => status,
+                        Err(err) => return Some((Err(err), (last, interval, true))),
+                    };
+                    if last.as_ref() == Some(&status) {
+                        interval = (interval * 2)
+                            .max(policy.initial_interval)
+                            .min(policy.max_interval);
+                        continue;
+                    }
+                    let done = status.is_finalized();
+                    let next = (Some(status.clone()), policy.initial_interval, done);
+                    return Some((Ok(status), next));
+                }
+            }
+        })
+    }
+
+    /// Wait for `tx_hash` to finalize and return its receipt, which must be
+    /// successful. Both must arrive within `policy.timeout`.
+    pub async fn wait_for_tx(
+        &self,
+        tx_hash: Hash,
+        policy: &WatchPolicy,
+    ) -> Result<PodReceiptResponse, WatchError> {
+        let mut last = None;
+        let receipt = tokio::time::timeout(policy.timeout, async {
+            let mut statuses = std::pin::pin!(self.watch_tx_status(tx_hash, policy));
+            while let Some(status) = statuses.next().await {
+                let status = status?;
+                if status.is_superseded() {
+                    return Err(WatchError::Superseded(status));
+                }
+                let finalized = status.is_finalized();
+                last = Some(status);
+                if finalized {
+                    break;
+                }
+            }
+
+            // The receipt can lag the status by a moment on a node.
+            let mut interval = policy.initial_interval;
+            loop {
+                if let Some(receipt) = self.get_transaction_receipt(tx_hash).await? {
+                    return Ok(receipt);
+                }
+                tokio::time::sleep(interval).await;
+                interval = (interval * 2).min(policy.max_interval);
+            }
+        })
+        .await;
+        let receipt = match receipt {
+            Ok(receipt) => receipt?,
+            Err(_) => return Err(WatchError::Timeout(last)),
+        };
+        if !receipt.status() {
+            return Err(WatchError::Reverted(Box::new(receipt)));
+        }
+        Ok(receipt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_transport::mock::Asserter;
+    use pod_types::{rpc::receipt::PodMetadata, AttestedTx, Receipt};
+
+    use super::*;
+
+    use crate::test_utils::{finalized, pending, provider};
+
+    const TX: Hash = Hash::repeat_byte(0x11);
+
+    fn policy() -> WatchPolicy {
+        WatchPolicy {
+            timeout: Duration::from_millis(50),
+            initial_interval: Duration::from_millis(1),
+            max_interval: Duration::from_millis(5),
+        }
+    }
+
+    fn receipt(status: bool) -> PodReceiptResponse {
+        let receipt = Receipt {
+            status,
+            actual_gas_used: 21_000,
+            max_fee_per_gas: 1,
+            logs: vec![],
+            logs_root: Hash::default(),
+            tx_hash: TX,
+            attested_tx: AttestedTx::new(TX, 0),
+            signer: Default::default(),
+            to: None,
+            contract_address: None,
+        };
+        PodReceiptResponse {
+            receipt: receipt.into(),
+            pod_metadata: PodMetadata {
+                attestations: vec![],
+                committee_epoch: 0,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn ends_at_superseded_or_reverted() {
+        let asserter = Asserter::new();
+        asserter.push_success(&pending(0, 0));
+        asserter.push_success(&pending(0, 1));
+        assert!(matches!(
+            provider(&asserter).wait_for_tx(TX, &policy()).await,
+            Err(WatchError::Superseded(status)) if status == pending(0, 1)
+        ));
+
+        let asserter = Asserter::new();
+        asserter.push_success(&finalized(0, false));
+        asserter.push_success(&None::<PodReceiptResponse>);
+        asserter.push_success(&receipt(false));
+        assert!(matches!(
+            provider(&asserter).wait_for_tx(TX, &policy()).await,
+            Err(WatchError::Reverted(_))
+        ));
+
+        let asserter = Asserter::new();
+        asserter.push_success(&finalized(0, true));
+        asserter.push_success(&receipt(true));
+        assert!(provider(&asserter).wait_for_tx(TX, &policy()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn times_out_waiting_for_status_or_receipt() {
+        let asserter = Asserter::new();
+        for _ in 0..100 {
+            asserter.push_success(&pending(0, 0));
+        }
+        assert!(matches!(
+            provider(&asserter).wait_for_tx(TX, &policy()).await,
+            Err(WatchError::Timeout(Some(status))) if status == pending(0, 0)
+        ));
+
+        let asserter = Asserter::new();
+        asserter.push_success(&finalized(0, true));
+        for _ in 0..100 {
+            asserter.push_success(&None::<PodReceiptResponse>);
+        }
+        assert!(matches!(
+            provider(&asserter).wait_for_tx(TX, &policy()).await,
+            Err(WatchError::Timeout(Some(status))) if status.is_finalized()
+        ));
+    }
+}
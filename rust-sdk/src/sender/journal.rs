@@ -0,0 +1,208 @@
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use alloy_primitives::{keccak256, Bytes};
+use pod_types::Hash;
+use serde::{Deserialize, Serialize};
+
+/// A transaction as it was signed, the only bytes ever sent at its nonce.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub nonce: u64,
+    pub tx_hash: Hash,
+    /// EIP-2718 encoding, as passed to `pod_sendRawTransaction`.
+    pub raw: Bytes,
+}
+
+impl JournalEntry {
+    pub fn new(nonce: u64, raw: Bytes) -> Self {
+        Self {
+            nonce,
+            tx_hash: keccak256(&raw),
+            raw,
+        }
+    }
+}
+
+/// Where a [`SafeSender`](super::SafeSender) keeps the transactions it signed
+/// and that are not finalized yet, one per nonce of a single account.
+///
+/// An entry must be durable once [`Self::insert`] returns: it is written
+/// before the transaction is first sent, so that after a crash the same bytes
+/// are resent rather than a different transaction signed at that nonce.
+pub trait TxJournal: Send + Sync {
+    /// Every entry, by ascending nonce.
+    fn entries(&self) -> Vec<JournalEntry>;
+
+    /// Record `entry`, replacing any entry at its nonce.
+    fn insert(&self, entry: JournalEntry) -> std::io::Result<()>;
+
+    /// Drop the entries at `nonce` and above.
+    fn truncate(&self, nonce: u64) -> std::io::Result<()>;
+
+    /// Drop the entries below `nonce`.
+    fn prune(&self, nonce: u64) -> std::io::Result<()>;
+}
+
+/// A journal lost on drop, for processes that resync from the node on start.
+#[derive(Debug, Default)]
+pub struct MemoryJournal {
+    entries: Mutex<BTreeMap<u64, JournalEntry>>,
+}
+
+impl MemoryJournal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn update(&self, update: impl FnOnce(&mut BTreeMap<u64, JournalEntry>)) -> Vec<JournalEntry> {
+        let mut entries = self.entries.lock().expect("poisoned");
+        update(&mut entries);
+        entries.values().cloned().collect()
+    }
+}
+
+impl TxJournal for MemoryJournal {
+    fn entries(&self) -> Vec<JournalEntry> {
+        self.update(|_| ())
+    }
+
+    fn insert(&self, entry: JournalEntry) -> std::io::Result<()> {
+        self.update(|entries| {
+            entries.insert(entry.nonce, entry);
+        });
+        Ok(())
+    }
+
+    fn truncate(&self, nonce: u64) -> std::io::Result<()> {
+        self.update(|entries| {
+            entries.split_off(&nonce);
+        });
+        Ok(())
+    }
+
+    fn prune(&self, nonce: u64) -> std::io::Result<()> {
+        self.update(|entries| *entries = entries.split_off(&nonce));
+        Ok(())
+    }
+}
+
+/// A journal persisted as JSON, rewritten on every change.
+#[derive(Debug)]
+pub struct FileJournal {
+    memory: MemoryJournal,
+    path: PathBuf,
+}
+
+impl FileJournal {
+    /// Open the journal at `path`, loading what it holds if it exists.
+    pub fn open(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+        let entries: Vec<JournalEntry> = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        let memory = MemoryJournal {
+            entries: Mutex::new(
+                entries
+                    .into_iter()
+                    .map(|entry| (entry.nonce, entry))
+                    .collect(),
+            ),
+        };
+        Ok(Self { memory, path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Write to a sibling file first so a crash never leaves a truncated
+    // journal, and sync both it and the rename before returning: an entry
+    // must survive power loss, not just a crash of the process.
+    fn persist(&self, entries: &[JournalEntry]) -> std::io::Result<()> {
+        let mut tmp = self.path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(&serde_json::to_vec_pretty(entries)?)?;
+        file.sync_all()?;
+        drop(file);
+        std::fs::rename(&tmp, &self.path)?;
+        sync_dir(&self.path)
+    }
+}
+
+/// Make a rename to `path` durable by syncing its directory.
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> std::io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    std::fs::File::open(dir)?.sync_all()
+}
+
+// Directories cannot be opened for syncing here; the rename is as durable as
+// the platform makes it.
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+impl TxJournal for FileJournal {
+    fn entries(&self) -> Vec<JournalEntry> {
+        self.memory.entries()
+    }
+
+    fn insert(&self, entry: JournalEntry) -> std::io::Result<()> {
+        self.persist(&self.memory.update(|entries| {
+            entries.insert(entry.nonce, entry);
+        }))
+    }
+
+    fn truncate(&self, nonce: u64) -> std::io::Result<()> {
+        self.persist(&self.memory.update(|entries| {
+            entries.split_off(&nonce);
+        }))
+    }
+
+    fn prune(&self, nonce: u64) -> std::io::Result<()> {
+        self.persist(
+            &self
+                .memory
+                .update(|entries| *entries = entries.split_off(&nonce)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_journal_survives_reopen() {
+        let path = std::env::temp_dir().join(format!("pod-tx-journal-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let journal = FileJournal::open(&path).unwrap();
+        for nonce in 3..7 {
+            journal
+                .insert(JournalEntry::new(nonce, Bytes::from(vec![nonce as u8])))
+                .unwrap();
+        }
+        journal.prune(4).unwrap();
+        journal.truncate(6).unwrap();
+        drop(journal);
+
+        let reopened = FileJournal::open(&path).unwrap();
+        let nonces: Vec<u64> = reopened.entries().iter().map(|e| e.nonce).collect();
+        assert_eq!(nonces, vec![4, 5]);
+        assert_eq!(reopened.entries()[0].tx_hash, keccak256([4u8]));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
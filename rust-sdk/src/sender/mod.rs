@@ -0,0 +1,307 @@
+//! Sending transactions from an account without ever signing two different
+//! ones at the same nonce.
+//!
+//! Two transactions at one nonce split the validators' votes so that neither
+//! reaches quorum, which locks the account until it is recovered. That
+//! happens easily by accident: a send that timed out is signed again with new
+//! fees, or a restarted process re-signs what it had sent before crashing.
+
+pub mod journal;
+
+use std::time::Duration;
+
+use alloy_network::TransactionBuilder;
+use alloy_primitives::Address;
+use alloy_provider::Provider;
+use anyhow::Context;
+
+pub use journal::{FileJournal, JournalEntry, MemoryJournal, TxJournal};
+
+use crate::{
+    network::PodTransactionRequest,
+    provider::{sign_transaction_bytes, PodProvider, PodSendError, PodSendResponse},
+};
+
+/// Sends the transactions of one account, owning its nonce.
+///
+/// Each transaction is signed once and its bytes journaled before they are
+/// first sent. After a [`PodSendError::Transport`] failure only those bytes
+/// are ever sent again. A nonce is only reused for a different transaction
+/// once the node said the one signed at it can never execute
+/// ([`PodSendError::is_terminal`]).
+///
+/// Nothing else may send from the account while a sender owns it. After
+/// recovering a locked account, call [`Self::reconcile`].
+pub struct SafeSender<J> {
+    provider: PodProvider,
+    key: crate::SigningKey,
+    account: Address,
+    journal: J,
+    next_nonce: u64,
+    max_resends: u32,
+    resend_interval: Duration,
+}
+
+impl<J: TxJournal> SafeSender<J> {
+    /// Take over the account of `key`, reconciling `journal` with the node.
+    pub async fn open(
+        provider: PodProvider,
+        key: crate::SigningKey,
+        journal: J,
+    ) -> anyhow::Result<Self> {
+        let account = crate::PrivateKeySigner::from_signing_key(key.clone()).address();
+        let mut sender = Self {
+            provider,
+            key,
+            account,
+            journal,
+            next_nonce: 0,
+            max_resends: 3,
+            resend_interval: Duration::from_secs(1),
+        };
+        sender.reconcile().await?;
+        Ok(sender)
+    }
+
+    /// Resend the same bytes up to `max_resends` times, `interval` apart, when
+    /// sending fails in transport.
+    pub fn with_resend_policy(mut self, max_resends: u32, interval: Duration) -> Self {
+        self.max_resends = max_resends;
+        self.resend_interval = interval;
+        self
+    }
+
+    pub fn account(&self) -> Address {
+        self.account
+    }
+
+    /// The nonce the next [`Self::send`] signs at.
+    pub fn next_nonce(&self) -> u64 {
+        self.next_nonce
+    }
+
+    pub fn journal(&self) -> &J {
+        &self.journal
+    }
+
+    /// Sign `tx` at the next nonce, journal it and send it. Any nonce already
+    /// set on `tx` is replaced.
+    ///
+    /// A [`PodSendError`] the sending ends in can be taken out of the error
+    /// with `downcast_ref`. If it is terminal, the nonce is freed for the next
+    /// send.
+    #[tracing::instrument(skip(self, tx), fields(account = %self.account))]
+    pub async fn send(&mut self, tx: PodTransactionRequest) -> anyhow::Result<PodSendResponse> {
+        self.prune().await?;
+
+        let nonce = self.next_nonce;
+        let request = self
+            .provider
+            .prepare_transaction(tx.with_nonce(nonce), self.account)
+            .await
+            .context("preparing TX")?;
+        let raw = sign_transaction_bytes(request, self.key.clone()).await?;
+        let entry = JournalEntry::new(nonce, raw.into());
+        self.journal
+            .insert(entry.clone())
+            .context("journaling signed TX")?;
+        self.next_nonce = nonce + 1;
+
+        let mut resends = 0;
+        loop {
+            match self
+                .provider
+                .pod_send_raw_transaction(&entry.raw, None)
+                .await
+            {
+                Ok(response) => return Ok(response),
+                Err(PodSendError::Transport(err)) if resends < self.max_resends => {
+                    resends += 1;
+                    tracing::warn!(nonce, %err, "resending signed TX");
+                    tokio::time::sleep(self.resend_interval).await;
+                }
+                Err(err) => {
+                    if err.is_terminal() {
+                        self.journal
+                            .truncate(nonce)
+                            .context("dropping TX from journal")?;
+                        self.next_nonce = nonce;
+                    }
+                    return Err(err).context("sending TX");
+                }
+            }
+        }
+    }
+
+    /// Bring the journal in line with the node, as on restart: drop what
+    /// finalized, resend the exact bytes of what did not, and continue from
+    /// the account's expected nonce or after the last journaled transaction,
+    /// whichever is higher.
+    ///
+    /// A journaled transaction the node refuses for good is dropped, along
+    /// with every later one, which cannot execute without it.
+    pub async fn reconcile(&mut self) -> anyhow::Result<()> {
+        self.prune().await?;
+
+        for entry in self.journal.entries() {
+            let status = self
+                .provider
+                .get_tx_status(entry.tx_hash)
+                .await
+                .context("getting TX status")?;
+            if status.is_finalized() {
+                self.journal
+                    .prune(entry.nonce + 1)
+                    .context("pruning journal")?;
+                continue;
+            }
+            match self
+                .provider
+                .pod_send_raw_transaction(&entry.raw, None)
+                .await
+            {
+                Ok(_) => {}
+                Err(err) if err.is_terminal() => {
+                    tracing::warn!(nonce = entry.nonce, %err, "dropping journaled TX");
+                    self.journal
+                        .truncate(entry.nonce)
+                        .context("dropping TX from journal")?;
+                    break;
+                }
+                Err(err) => tracing::warn!(nonce = entry.nonce, %err, "resending journaled TX"),
+            }
+        }
+
+        let expected = self
+            .provider
+            .get_transaction_count(self.account)
+            .await
+            .context("fetching nonce")?;
+        let journaled = self.journal.entries().last().map(|entry| entry.nonce + 1);
+        self.next_nonce = journaled.map_or(expected, |journaled| journaled.max(expected));
+        Ok(())
+    }
+
+    /// Drop the journaled transactions at finalized nonces.
+    async fn prune(&mut self) -> anyhow::Result<()> {
+        let finalized = self
+            .provider
+            .get_last_finalized_nonce(self.account)
+            .await
+            .context("getting last finalized nonce")?;
+        if let Some(finalized) = finalized {
+            self.journal
+                .prune(finalized + 1)
+                .context("pruning journal")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Bytes, U64};
+    use alloy_transport::mock::Asserter;
+    use pod_types::{rpc::account::TxStatus, Hash};
+
+    use super::*;
+    use crate::test_utils::{accepted, finalized, key, pending, provider, rpc_error};
+
+    fn entry(nonce: u64) -> JournalEntry {
+        JournalEntry::new(nonce, Bytes::from(vec![nonce as u8; 4]))
+    }
+
+    #[tokio::test]
+    async fn reconcile_resends_pending_and_prunes_finalized() {
+        let journal = MemoryJournal::new();
+        for nonce in 3..8 {
+            journal.insert(entry(nonce)).unwrap();
+        }
+
+        let asserter = Asserter::new();
+        // Nonces up to 3 finalized.
+        asserter.push_success(&Some(3u64));
+        // 4 finalized since.
+        asserter.push_success(&finalized(4, true));
+        // 5 is resent and accepted, 6 resent and lost in transport.
+        asserter.push_success(&pending(5, 5));
+        asserter.push_success(&accepted(entry(5).tx_hash));
+        asserter.push_success(&TxStatus::NotFound);
+        asserter.push_failure(rpc_error(-32000, "connection reset", None));
+        // 7 can never execute.
+        asserter.push_success(&pending(7, 7));
+        asserter.push_failure(rpc_error(
+            -32003,
+            "Transaction rejected: nonce too low",
+            None,
+        ));
+        asserter.push_success(&U64::from(5));
+
+        let sender = SafeSender::open(provider(&asserter), key(), journal)
+            .await
+            .unwrap();
+        assert!(asserter.read_q().is_empty());
+        // The resent transactions are kept as they were signed.
+        assert_eq!(sender.journal().entries(), vec![entry(5), entry(6)]);
+        assert_eq!(sender.next_nonce(), 7);
+    }
+
+    #[tokio::test]
+    async fn send_keeps_the_nonce_until_rejected() {
+        let asserter = Asserter::new();
+        asserter.push_success(&None::<u64>);
+        asserter.push_success(&U64::ZERO);
+        let mut sender = SafeSender::open(provider(&asserter), key(), MemoryJournal::new())
+            .await
+            .unwrap()
+            .with_resend_policy(2, Duration::from_millis(1));
+
+        let tx = PodTransactionRequest::default()
+            .with_to(Address::repeat_byte(0x01))
+            .with_chain_id(1293)
+            .with_gas_limit(21_000)
+            .with_max_fee_per_gas(1_000_000_000)
+            .with_max_priority_fee_per_gas(0);
+
+        asserter.push_success(&None::<u64>);
+        asserter.push_failure(rpc_error(-32000, "connection reset", None));
+        asserter.push_failure(rpc_error(-32000, "connection reset", None));
+        asserter.push_success(&accepted(Hash::repeat_byte(0xaa)));
+        let response = sender.send(tx.clone()).await.unwrap();
+        assert_eq!(response.tx_hash, Hash::repeat_byte(0xaa));
+        let entries = sender.journal().entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].nonce, 0);
+        assert_eq!(sender.next_nonce(), 1);
+
+        // Out of resends: the TX stays journaled and its nonce taken.
+        asserter.push_success(&None::<u64>);
+        for _ in 0..3 {
+            asserter.push_failure(rpc_error(-32000, "connection reset", None));
+        }
+        let err = sender.send(tx.clone()).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<PodSendError>(),
+            Some(PodSendError::Transport(_))
+        ));
+        assert_eq!(sender.journal().entries().len(), 2);
+        assert_eq!(sender.next_nonce(), 2);
+
+        // Rejected for good: the nonce is freed and nothing is journaled at it.
+        asserter.push_success(&Some(0u64));
+        asserter.push_failure(rpc_error(-32003, "Transaction rejected: nope", None));
+        let err = sender.send(tx).await.unwrap_err();
+        assert!(err.downcast_ref::<PodSendError>().unwrap().is_terminal());
+        assert_eq!(
+            sender
+                .journal()
+                .entries()
+                .iter()
+                .map(|e| e.nonce)
+                .collect::<Vec<_>>(),
+            vec![1]
+        );
+        assert_eq!(sender.next_nonce(), 2);
+        assert!(asserter.read_q().is_empty());
+    }
+}
@@ -0,0 +1,92 @@
+use alloy_primitives::Address;
+use pod_types::rpc::{
+    bridge::Withdrawal,
+    orderbook::OrderbookSnapshot,
+    subscription::{
+        CandleTick, MarketUpdate, OrderUpdate, OrdersFrame, PositionsUpdate, SubscriptionParams,
+        TriggersUpdate,
+    },
+};
+
+use super::{PodSubscriber, PodSubscription, SubscriptionError};
+
+type SubscriptionResult<T> = Result<PodSubscription<T>, SubscriptionError>;
+
+impl PodSubscriber {
+    /// The price levels of each orderbook in `params.orderbook_ids` after
+    /// every batch, down to `params.depth` levels per side.
+    pub async fn subscribe_orderbook(
+        &self,
+        params: SubscriptionParams,
+    ) -> SubscriptionResult<OrderbookSnapshot> {
+        self.subscribe("pod_orderbook", params).await
+    }
+
+    /// What each batch changed of the orders, optionally of one bidder.
+    /// Prefer [`Self::subscribe_orders_v2`].
+    pub async fn subscribe_orders(
+        &self,
+        params: SubscriptionParams,
+    ) -> SubscriptionResult<Vec<OrderUpdate>> {
+        self.subscribe("pod_orders", params).await
+    }
+
+    /// What each batch did to the orders of one orderbook, optionally of
+    /// some bidders, one frame per orderbook and batch.
+    ///
+    /// A close in the middle of a batch resumes from the last frame
+    /// delivered, so none is missed or repeated.
+    pub async fn subscribe_orders_v2(
+        &self,
+        params: SubscriptionParams,
+    ) -> SubscriptionResult<OrdersFrame> {
+        self.subscribe("pod_orders_v2", params).await
+    }
+
+    /// The clearing price and volume of each orderbook in each batch.
+    pub async fn subscribe_candles(
+        &self,
+        params: SubscriptionParams,
+    ) -> SubscriptionResult<CandleTick> {
+        self.subscribe("pod_candles", params).await
+    }
+
+    /// Live statistics of each orderbook, starting with the current ones.
+    pub async fn subscribe_markets(
+        &self,
+        params: SubscriptionParams,
+    ) -> SubscriptionResult<MarketUpdate> {
+        self.subscribe("pod_markets", params).await
+    }
+
+    /// The positions of `account`, now and after every batch that touches
+    /// it.
+    pub async fn subscribe_positions(
+        &self,
+        account: Address,
+        params: SubscriptionParams,
+    ) -> SubscriptionResult<PositionsUpdate> {
+        self.subscribe("pod_positions", params.with_account(account))
+            .await
+    }
+
+    /// The armed triggers of `account`, now and after every batch that
+    /// touches it.
+    pub async fn subscribe_triggers(
+        &self,
+        account: Address,
+        params: SubscriptionParams,
+    ) -> SubscriptionResult<TriggersUpdate> {
+        self.subscribe("pod_triggers", params.with_account(account))
+            .await
+    }
+
+    /// The outcome of every orderbook withdrawal, optionally of the
+    /// withdrawer `params.bidder`, as each batch settles them.
+    pub async fn subscribe_withdrawals(
+        &self,
+        params: SubscriptionParams,
+    ) -> SubscriptionResult<Vec<Withdrawal>> {
+        self.subscribe("pod_withdrawals", params).await
+    }
+}
@@ -0,0 +1,133 @@
+use pod_types::{rpc::subscription::SubscriptionParams, Hash};
+use serde_json::Value;
+
+/// The subscriber fell behind and the node dropped ticks for it.
+pub const LAGGED: i64 = -32020;
+/// The node is going away. Only a connection to another node resumes.
+pub const SHUTTING_DOWN: i64 = -32021;
+/// The connection did not take a notification within the node's send
+/// timeout.
+pub const SEND_TIMEOUT: i64 = -32022;
+/// A node bug that resubscribing will likely reproduce.
+pub const SERIALIZATION_FAILED: i64 = -32023;
+
+/// A subscription ended by the node, as carried by the `error` of its
+/// `eth_subscription` notification. The connection and every other
+/// subscription on it are unaffected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionClosed {
+    pub code: i64,
+    pub message: String,
+    /// Whether resubscribing recovers the stream. Only set when the node
+    /// explicitly said so.
+    pub resumable: bool,
+    /// Ticks the node dropped, for [`LAGGED`].
+    pub missed: Option<u64>,
+    /// Solution time in microseconds of the newest tick fully delivered, to
+    /// resubscribe with as `since`. `None` when no tick was, in which case the
+    /// original `since` still applies.
+    pub resume_since: Option<u64>,
+    /// `pod_orders_v2` only: the last `book` delivered of a batch that was cut
+    /// short, to resubscribe with as `since_book`.
+    pub resume_since_book: Option<Hash>,
+}
+
+impl SubscriptionClosed {
+    /// Read the `error` of a close notification. Fields that are missing or
+    /// malformed are left unset, so an unexpected close is never resumed.
+    pub fn from_error(error: &Value) -> Self {
+        let data = &error["data"];
+        Self {
+            code: error["code"].as_i64().unwrap_or_default(),
+            message: error["message"].as_str().unwrap_or_default().to_owned(),
+            resumable: data["resumable"].as_bool() == Some(true),
+            missed: data["missed"].as_u64(),
+            resume_since: data["resume_since"].as_u64(),
+            resume_since_book: data["resume_since_book"]
+                .as_str()
+                .and_then(|book| book.parse().ok()),
+        }
+    }
+
+    /// Whether resubscribing on the same connection recovers the stream.
+    /// Never for [`SERIALIZATION_FAILED`], which would close it again, nor
+    /// [`SHUTTING_DOWN`], whose connection is about to go.
+    pub fn resumes_in_place(&self) -> bool {
+        self.resumable && matches!(self.code, LAGGED | SEND_TIMEOUT)
+    }
+
+    /// Move the cursor of `params` to where the stream stopped.
+    pub fn resume(&self, params: &mut SubscriptionParams) {
+        if let Some(since) = self.resume_since {
+            params.since = Some(since);
+            // A book left over from an earlier batch would skip books of
+            // this one.
+            params.since_book = self.resume_since_book;
+        }
+    }
+}
+
+impl std::fmt::Display for SubscriptionClosed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "subscription closed by node ({}): {}",
+            self.code, self.message
+        )?;
+        if let Some(missed) = self.missed {
+            write!(f, ", {missed} ticks missed")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SubscriptionClosed {}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn resumes_from_the_close_cursor() {
+        let book = Hash::repeat_byte(0x0b);
+        let closed = SubscriptionClosed::from_error(&json!({
+            "code": -32020,
+            "message": "subscription lagged behind the tick broadcast",
+            "data": { "resumable": true, "missed": 42, "resume_since": 1718900000000000u64 }
+        }));
+        assert!(closed.resumes_in_place());
+        assert_eq!(closed.missed, Some(42));
+
+        let mut params = SubscriptionParams {
+            since: Some(5),
+            since_book: Some(book),
+            ..Default::default()
+        };
+        closed.resume(&mut params);
+        assert_eq!(params.since, Some(1718900000000000));
+        assert_eq!(params.since_book, None);
+
+        let closed = SubscriptionClosed::from_error(&json!({
+            "code": -32022,
+            "message": "subscriber did not accept a notification in time",
+            "data": { "resumable": true, "resume_since": 9, "resume_since_book": book }
+        }));
+        closed.resume(&mut params);
+        assert_eq!((params.since, params.since_book), (Some(9), Some(book)));
+
+        // Without a cursor, the original `since` still applies.
+        SubscriptionClosed::from_error(&json!({ "code": -32020, "data": { "resumable": true } }))
+            .resume(&mut params);
+        assert_eq!((params.since, params.since_book), (Some(9), Some(book)));
+
+        for error in [
+            json!({ "code": -32023, "message": "failed to serialize", "data": { "resumable": false } }),
+            json!({ "code": -32021, "message": "node is shutting down", "data": { "resumable": true } }),
+            json!({ "code": -32020, "data": { "resumable": "yes" } }),
+        ] {
+            assert!(!SubscriptionClosed::from_error(&error).resumes_in_place());
+        }
+    }
+}
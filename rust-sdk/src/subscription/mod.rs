@@ -0,0 +1,654 @@
+//! Subscriptions to the pod channels that survive the node closing them.
+//!
+//! The node ends a subscription it cannot serve without a gap, most often
+//! because the subscriber fell behind, with an `eth_subscription` notification
+//! carrying `error` instead of `result`. Generic clients drop that
+//! notification or the whole connection. [`PodSubscriber`] reads it as a
+//! [`SubscriptionClosed`] and, where the node says the stream resumes,
+//! resubscribes from the cursor it gives, so the stream carries on without a
+//! gap and the other subscriptions on the connection are left alone.
+
+mod channels;
+mod close;
+
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use alloy_json_rpc::ErrorPayload;
+use alloy_transport::{TransportErrorKind, TransportResult};
+use futures::{future::BoxFuture, stream::FuturesUnordered, SinkExt, Stream, StreamExt};
+use pod_types::rpc::subscription::SubscriptionParams;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use tokio::sync::{mpsc, oneshot};
+use tokio_tungstenite::{
+    tungstenite::{self, Message},
+    MaybeTlsStream, WebSocketStream,
+};
+
+pub use close::{SubscriptionClosed, LAGGED, SEND_TIMEOUT, SERIALIZATION_FAILED, SHUTTING_DOWN};
+
+type Socket = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+type Frame = Result<Value, SubscriptionError>;
+
+/// Frames held for a subscription that is not read. One more frame ends it
+/// with [`SubscriptionError::Lagged`], which has a slot of its own.
+const FRAME_BUFFER: usize = 256;
+const COMMAND_BUFFER: usize = 64;
+/// Resubscribes in a row, with no frame delivered in between, before a close
+/// ends the stream.
+const MAX_RESUMES: u32 = 5;
+/// Doubled on each resubscribe in a row, up to [`MAX_RESUME_DELAY`].
+const RESUME_DELAY: Duration = Duration::from_millis(500);
+const MAX_RESUME_DELAY: Duration = Duration::from_secs(30);
+
+/// Why a [`PodSubscription`] yielded an error. All but [`Self::Decode`] end
+/// the stream.
+#[derive(Debug)]
+pub enum SubscriptionError {
+    /// Closed by the node in a way resubscribing on this connection does not
+    /// recover from. [`SubscriptionClosed::resume`] gives the params to
+    /// subscribe again with, on another connection if the node is shutting
+    /// down.
+    Closed(SubscriptionClosed),
+    /// `eth_subscribe` was refused, as when `since` predates what the node
+    /// retains.
+    Rejected(ErrorPayload),
+    /// A frame did not decode as the channel's item.
+    Decode(serde_json::Error),
+    /// The stream was not read while a full buffer of frames queued up, and
+    /// was ended here rather than hold up the connection.
+    Lagged,
+    /// The connection was lost, ending every subscription on it.
+    Disconnected,
+}
+
+impl std::fmt::Display for SubscriptionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Closed(closed) => write!(f, "{closed}"),
+            Self::Rejected(e) => write!(f, "subscription rejected: {e}"),
+            Self::Decode(e) => write!(f, "decoding subscription frame: {e}"),
+            Self::Lagged => write!(f, "subscription not read fast enough"),
+            Self::Disconnected => write!(f, "subscription connection lost"),
+        }
+    }
+}
+
+impl std::error::Error for SubscriptionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Closed(e) => Some(e),
+            Self::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// A websocket connection to a pod node carrying any number of channel
+/// subscriptions.
+///
+/// A subscription the node closes with [`LAGGED`] or [`SEND_TIMEOUT`] is
+/// resubscribed from the cursor of the close, and its stream goes on as if
+/// nothing happened. Resubscribes are backed off, and after five
+/// closes in a row with no frame in between the last one ends the stream, as
+/// does any other close, with [`SubscriptionError::Closed`]. The connection is
+/// not reopened once lost.
+///
+/// Each subscription buffers a bounded number of frames. One that is not read
+/// while its buffer fills up is unsubscribed and ends with
+/// [`SubscriptionError::Lagged`]; the others on the connection are not held
+/// up by it.
+#[derive(Debug, Clone)]
+pub struct PodSubscriber {
+    commands: mpsc::Sender<Command>,
+}
+
+impl PodSubscriber {
+    /// Connect to the websocket endpoint of a node at `url`.
+    pub async fn connect(url: &str) -> TransportResult<Self> {
+        let (socket, _) = tokio_tungstenite::connect_async(url)
+            .await
+            .map_err(TransportErrorKind::custom)?;
+        let (commands, receiver) = mpsc::channel(COMMAND_BUFFER);
+        tokio::spawn(Connection::new(socket, receiver).run());
+        Ok(Self { commands })
+    }
+
+    /// Subscribe to `channel`, such as `pod_orderbook`, decoding each frame
+    /// as `T`. Returns once the node accepted the subscription.
+    pub async fn subscribe<T: DeserializeOwned>(
+        &self,
+        channel: &str,
+        params: SubscriptionParams,
+    ) -> Result<PodSubscription<T>, SubscriptionError> {
+        let (frames_sender, frames) = mpsc::channel(FRAME_BUFFER + 1);
+        let (ack, accepted) = oneshot::channel();
+        self.commands
+            .send(Command::Subscribe {
+                channel: channel.to_owned(),
+                params,
+                frames: frames_sender,
+                ack,
+            })
+            .await
+            .map_err(|_| SubscriptionError::Disconnected)?;
+        let key = accepted
+            .await
+            .map_err(|_| SubscriptionError::Disconnected)??;
+        Ok(PodSubscription {
+            key,
+            frames,
+            commands: self.commands.clone(),
+            _item: PhantomData,
+        })
+    }
+}
+
+/// The frames of one channel subscription. Unsubscribes on drop.
+#[derive(Debug)]
+pub struct PodSubscription<T> {
+    key: u64,
+    frames: mpsc::Receiver<Frame>,
+    commands: mpsc::Sender<Command>,
+    _item: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> Stream for PodSubscription<T> {
+    type Item = Result<T, SubscriptionError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.frames.poll_recv(cx).map(|frame| {
+            frame.map(|frame| {
+                frame.and_then(|value| {
+                    serde_json::from_value(value).map_err(SubscriptionError::Decode)
+                })
+            })
+        })
+    }
+}
+
+impl<T> Drop for PodSubscription<T> {
+    fn drop(&mut self) {
+        // With the queue full, the connection unsubscribes once a frame
+        // finds the stream gone.
+        let _ = self.commands.try_send(Command::Unsubscribe(self.key));
+    }
+}
+
+#[derive(Debug)]
+enum Command {
+    Subscribe {
+        channel: String,
+        params: SubscriptionParams,
+        frames: mpsc::Sender<Frame>,
+        ack: oneshot::Sender<Result<u64, SubscriptionError>>,
+    },
+    Unsubscribe(u64),
+}
+
+struct Subscription {
+    channel: String,
+    /// Moved forward on every resumed close, so resubscribing continues where
+    /// the stream stopped.
+    params: SubscriptionParams,
+    frames: mpsc::Sender<Frame>,
+    /// Until the node first accepts the subscription.
+    ack: Option<oneshot::Sender<Result<u64, SubscriptionError>>>,
+    server_id: Option<String>,
+    /// Resubscribes since the last frame delivered.
+    resumes: u32,
+}
+
+impl Subscription {
+    /// Queue `frame` unless the buffer is full, keeping the last slot for the
+    /// error that ends the stream.
+    fn deliver(&mut self, frame: Value) -> Result<(), Delivery> {
+        if self.frames.is_closed() {
+            return Err(Delivery::Dropped);
+        }
+        if self.frames.capacity() <= 1 {
+            return Err(Delivery::Full);
+        }
+        self.frames
+            .try_send(Ok(frame))
+            .map_err(|_| Delivery::Dropped)?;
+        self.resumes = 0;
+        Ok(())
+    }
+
+    /// Never waits: every frame before it left the error its slot.
+    fn fail(self, err: SubscriptionError) {
+        match self.ack {
+            Some(ack) => {
+                let _ = ack.send(Err(err));
+            }
+            None => {
+                let _ = self.frames.try_send(Err(err));
+            }
+        }
+    }
+}
+
+enum Delivery {
+    /// The stream was dropped.
+    Dropped,
+    /// The stream is not being read.
+    Full,
+}
+
+enum Request {
+    Subscribe(u64),
+    Unsubscribe,
+}
+
+#[derive(Deserialize)]
+struct Incoming {
+    #[serde(default)]
+    id: Option<u64>,
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    params: Option<Notification>,
+    #[serde(default)]
+    result: Option<Value>,
+    #[serde(default)]
+    error: Option<ErrorPayload>,
+}
+
+#[derive(Deserialize)]
+struct Notification {
+    subscription: String,
+    #[serde(default)]
+    result: Option<Value>,
+    #[serde(default)]
+    error: Option<Value>,
+}
+
+/// Owns the socket. Subscriptions are keyed by the id of the request that
+/// created them, which outlives the node's ids across resubscriptions.
+struct Connection {
+    socket: Socket,
+    commands: mpsc::Receiver<Command>,
+    next_id: u64,
+    subscriptions: HashMap<u64, Subscription>,
+    by_server_id: HashMap<String, u64>,
+    requests: HashMap<u64, Request>,
+    /// Keys of closed subscriptions, each ready once its backoff is over.
+    resubscribes: FuturesUnordered<BoxFuture<'static, u64>>,
+}
+
+impl Connection {
+    fn new(socket: Socket, commands: mpsc::Receiver<Command>) -> Self {
+        Self {
+            socket,
+            commands,
+            next_id: 1,
+            subscriptions: HashMap::new(),
+            by_server_id: HashMap::new(),
+            requests: HashMap::new(),
+            resubscribes: FuturesUnordered::new(),
+        }
+    }
+
+    async fn run(mut self) {
+        let reason = loop {
+            let handled = tokio::select! {
+                command = self.commands.recv() => match command {
+                    Some(command) => self.command(command).await,
+                    None => {
+                        let _ = self.socket.close(None).await;
+                        return;
+                    }
+                },
+                message = self.socket.next() => match message {
+                    Some(Ok(Message::Text(text))) => self.message(text.as_bytes()).await,
+                    Some(Ok(Message::Binary(bytes))) => self.message(&bytes).await,
+                    Some(Ok(Message::Close(frame))) => {
+                        break frame.map_or("closed by node".to_owned(), |f| f.to_string())
+                    }
+                    Some(Ok(_)) => Ok(()),
+                    Some(Err(e)) => Err(e),
+                    None => break "closed by node".to_owned(),
+                },
+                Some(key) = self.resubscribes.next(), if !self.resubscribes.is_empty() => {
+                    self.subscribe(key).await
+                }
+            };
+            if let Err(e) = handled {
+                break e.to_string();
+            }
+        };
+
+        tracing::warn!(%reason, "pod subscription connection lost");
+        for subscription in self.subscriptions.into_values() {
+            subscription.fail(SubscriptionError::Disconnected);
+        }
+    }
+
+    async fn command(&mut self, command: Command) -> Result<(), tungstenite::Error> {
+        match command {
+            Command::Subscribe {
+                channel,
+                params,
+                frames,
+                ack,
+            } => {
+                let key = self.next_id;
+                self.subscriptions.insert(
+                    key,
+                    Subscription {
+                        channel,
+                        params,
+                        frames,
+                        ack: Some(ack),
+                        server_id: None,
+                        resumes: 0,
+                    },
+                );
+                self.subscribe(key).await
+            }
+            Command::Unsubscribe(key) => match self.subscriptions.remove(&key) {
+                Some(subscription) => self.unsubscribe(subscription.server_id).await,
+                None => Ok(()),
+            },
+        }
+    }
+
+    async fn message(&mut self, bytes: &[u8]) -> Result<(), tungstenite::Error> {
+        let incoming: Incoming = match serde_json::from_slice(bytes) {
+            Ok(incoming) => incoming,
+            Err(e) => {
+                tracing::debug!(%e, "ignoring undecodable message");
+                return Ok(());
+            }
+        };
+
+        if let (Some("eth_subscription"), Some(notification)) =
+            (incoming.method.as_deref(), incoming.params)
+        {
+            return self.notification(notification).await;
+        }
+        let Some(Request::Subscribe(key)) = incoming.id.and_then(|id| self.requests.remove(&id))
+        else {
+            return Ok(());
+        };
+        let server_id = incoming.result.and_then(|result| match result {
+            Value::String(id) => Some(id),
+            _ => None,
+        });
+
+        let Some(subscription) = self.subscriptions.get_mut(&key) else {
+            // Unsubscribed while the subscribe was in flight.
+            return self.unsubscribe(server_id).await;
+        };
+        match server_id {
+            Some(server_id) => {
+                subscription.server_id = Some(server_id.clone());
+                if let Some(ack) = subscription.ack.take() {
+                    let _ = ack.send(Ok(key));
+                }
+                self.by_server_id.insert(server_id, key);
+            }
+            None => {
+                let error = incoming.error.unwrap_or_else(|| {
+                    ErrorPayload::internal_error_message("no subscription ID returned".into())
+                });
+                if let Some(subscription) = self.subscriptions.remove(&key) {
+                    subscription.fail(SubscriptionError::Rejected(error));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn notification(&mut self, notification: Notification) -> Result<(), tungstenite::Error> {
+        let Some(&key) = self.by_server_id.get(&notification.subscription) else {
+            return Ok(());
+        };
+
+        if let Some(error) = notification.error {
+            // The node has already dropped it, so there is nothing to
+            // unsubscribe.
+            self.by_server_id.remove(&notification.subscription);
+            let closed = SubscriptionClosed::from_error(&error);
+            let Some(subscription) = self.subscriptions.get_mut(&key) else {
+                return Ok(());
+            };
+            subscription.server_id = None;
+            if !closed.resumes_in_place() || subscription.resumes >= MAX_RESUMES {
+                if let Some(subscription) = self.subscriptions.remove(&key) {
+                    subscription.fail(SubscriptionError::Closed(closed));
+                }
+                return Ok(());
+            }
+            subscription.resumes += 1;
+            let delay = RESUME_DELAY
+                .saturating_mul(1 << (subscription.resumes - 1))
+                .min(MAX_RESUME_DELAY);
+            tracing::warn!(
+                channel = %subscription.channel,
+                code = closed.code,
+                missed = ?closed.missed,
+                resume_since = ?closed.resume_since,
+                attempt = subscription.resumes,
+                ?delay,
+                "resubscribing after node closed subscription"
+            );
+            closed.resume(&mut subscription.params);
+            self.resubscribes.push(Box::pin(async move {
+                tokio::time::sleep(delay).await;
+                key
+            }));
+            return Ok(());
+        }
+
+        let frame = notification.result.unwrap_or_default();
+        let Some(subscription) = self.subscriptions.get_mut(&key) else {
+            return Ok(());
+        };
+        let Err(failed) = subscription.deliver(frame) else {
+            return Ok(());
+        };
+        let Some(subscription) = self.subscriptions.remove(&key) else {
+            return Ok(());
+        };
+        let server_id = subscription.server_id.clone();
+        match failed {
+            // Dropped before the unsubscribe command got here.
+            Delivery::Dropped => {}
+            Delivery::Full => {
+                tracing::warn!(
+                    channel = %subscription.channel,
+                    "ending subscription that is not read"
+                );
+                subscription.fail(SubscriptionError::Lagged);
+            }
+        }
+        self.unsubscribe(server_id).await
+    }
+
+    async fn subscribe(&mut self, key: u64) -> Result<(), tungstenite::Error> {
+        let Some(subscription) = self.subscriptions.get(&key) else {
+            return Ok(());
+        };
+        let params = json!([subscription.channel, subscription.params]);
+        self.request(Request::Subscribe(key), "eth_subscribe", params)
+            .await
+    }
+
+    async fn unsubscribe(&mut self, server_id: Option<String>) -> Result<(), tungstenite::Error> {
+        let Some(server_id) = server_id else {
+            return Ok(());
+        };
+        self.by_server_id.remove(&server_id);
+        self.request(Request::Unsubscribe, "eth_unsubscribe", json!([server_id]))
+            .await
+    }
+
+    async fn request(
+        &mut self,
+        request: Request,
+        method: &str,
+        params: Value,
+    ) -> Result<(), tungstenite::Error> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.requests.insert(id, request);
+        let body = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        self.socket.send(Message::text(body.to_string())).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{net::TcpListener, time::Instant};
+
+    use super::*;
+
+    type Server = WebSocketStream<tokio::net::TcpStream>;
+
+    async fn connect() -> (PodSubscriber, Server) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let (subscriber, server) = tokio::join!(PodSubscriber::connect(&url), async {
+            let (stream, _) = listener.accept().await.unwrap();
+            tokio_tungstenite::accept_async(stream).await.unwrap()
+        });
+        (subscriber.unwrap(), server)
+    }
+
+    async fn send(server: &mut Server, message: Value) {
+        server
+            .send(Message::text(message.to_string()))
+            .await
+            .unwrap();
+    }
+
+    /// Answer the next `eth_subscribe` with `server_id`, returning its params.
+    async fn accept(server: &mut Server, server_id: &str) -> Value {
+        let request = loop {
+            if let Message::Text(text) = server.next().await.unwrap().unwrap() {
+                break serde_json::from_str::<Value>(&text).unwrap();
+            }
+        };
+        assert_eq!(request["method"], "eth_subscribe");
+        send(
+            server,
+            json!({ "jsonrpc": "2.0", "id": request["id"], "result": server_id }),
+        )
+        .await;
+        request["params"][1].clone()
+    }
+
+    fn notification(server_id: &str, body: Value) -> Value {
+        let mut params = json!({ "subscription": server_id });
+        params
+            .as_object_mut()
+            .unwrap()
+            .extend(body.as_object().unwrap().clone());
+        json!({ "jsonrpc": "2.0", "method": "eth_subscription", "params": params })
+    }
+
+    fn frame(server_id: &str, value: u64) -> Value {
+        notification(server_id, json!({ "result": value }))
+    }
+
+    fn lagged(server_id: &str, resume_since: u64) -> Value {
+        notification(
+            server_id,
+            json!({ "error": {
+                "code": LAGGED,
+                "message": "subscription lagged behind the tick broadcast",
+                "data": { "resumable": true, "missed": 1, "resume_since": resume_since },
+            }}),
+        )
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn resumes_with_backoff_until_the_limit() {
+        let (subscriber, mut server) = connect().await;
+        let (subscription, params) = tokio::join!(
+            subscriber.subscribe::<u64>("pod_candles", SubscriptionParams::default().with_since(1)),
+            accept(&mut server, "0x1"),
+        );
+        let mut subscription = subscription.unwrap();
+        assert_eq!(params["since"], 1);
+        send(&mut server, frame("0x1", 10)).await;
+        assert_eq!(subscription.next().await.unwrap().unwrap(), 10);
+
+        // Resubscribed from the cursor of the close, and frames of the old
+        // subscription are no longer routed.
+        let start = Instant::now();
+        send(&mut server, lagged("0x1", 20)).await;
+        assert_eq!(accept(&mut server, "0x2").await["since"], 20);
+        assert!(start.elapsed() >= RESUME_DELAY);
+        send(&mut server, frame("0x1", 21)).await;
+        send(&mut server, frame("0x2", 30)).await;
+        assert_eq!(subscription.next().await.unwrap().unwrap(), 30);
+
+        // The delivered frame reset the backoff, which doubles on every close
+        // in a row.
+        let mut server_id = "0x2".to_owned();
+        for attempt in 0..MAX_RESUMES {
+            let start = Instant::now();
+            send(&mut server, lagged(&server_id, 40 + u64::from(attempt))).await;
+            server_id = format!("0x{}", attempt + 3);
+            assert_eq!(
+                accept(&mut server, &server_id).await["since"],
+                40 + u64::from(attempt)
+            );
+            let delay = RESUME_DELAY * 2u32.pow(attempt);
+            assert!(start.elapsed() >= delay && start.elapsed() < delay * 2);
+        }
+
+        send(&mut server, lagged(&server_id, 50)).await;
+        match subscription.next().await {
+            Some(Err(SubscriptionError::Closed(closed))) => assert_eq!(closed.code, LAGGED),
+            other => panic!("expected the close, got {other:?}"),
+        }
+        assert!(subscription.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn ends_an_unread_subscription_alone() {
+        let (subscriber, mut server) = connect().await;
+        let (unread, _) = tokio::join!(
+            subscriber.subscribe::<u64>("pod_candles", SubscriptionParams::default()),
+            accept(&mut server, "0x1"),
+        );
+        let (read, _) = tokio::join!(
+            subscriber.subscribe::<u64>("pod_markets", SubscriptionParams::default()),
+            accept(&mut server, "0x2"),
+        );
+        let (mut unread, mut read) = (unread.unwrap(), read.unwrap());
+
+        for value in 0..=FRAME_BUFFER as u64 {
+            send(&mut server, frame("0x1", value)).await;
+        }
+        send(&mut server, frame("0x2", 7)).await;
+        assert_eq!(read.next().await.unwrap().unwrap(), 7);
+
+        let request = loop {
+            if let Message::Text(text) = server.next().await.unwrap().unwrap() {
+                break serde_json::from_str::<Value>(&text).unwrap();
+            }
+        };
+        assert_eq!(request["method"], "eth_unsubscribe");
+        assert_eq!(request["params"][0], "0x1");
+
+        for value in 0..FRAME_BUFFER as u64 {
+            assert_eq!(unread.next().await.unwrap().unwrap(), value);
+        }
+        assert!(matches!(
+            unread.next().await,
+            Some(Err(SubscriptionError::Lagged))
+        ));
+        assert!(unread.next().await.is_none());
+    }
+}
@@ -0,0 +1,70 @@
+//! Fixtures shared by the unit tests.
+
+use alloy_json_rpc::ErrorPayload;
+use alloy_primitives::Address;
+use alloy_provider::{Identity, ProviderBuilder};
+use alloy_transport::mock::Asserter;
+use pod_types::{
+    rpc::account::{NonceVotes, TxStatus},
+    Hash,
+};
+
+use crate::{network::PodNetwork, provider::PodProvider};
+
+/// A provider answering from `asserter`'s queue, in order.
+pub fn provider(asserter: &Asserter) -> PodProvider {
+    PodProvider::new(
+        ProviderBuilder::<Identity, Identity, PodNetwork>::default()
+            .connect_mocked_client(asserter.clone()),
+    )
+}
+
+/// The key the tests sign with.
+pub fn key() -> crate::SigningKey {
+    crate::SigningKey::from_slice(&[0x42; 32]).unwrap()
+}
+
+/// A `pod_sendRawTransaction` result accepting `tx_hash`.
+pub fn accepted(tx_hash: Hash) -> serde_json::Value {
+    serde_json::json!({ "tx_hash": tx_hash, "successes": 3, "errors": [] })
+}
+
+/// A JSON-RPC error response, with `data` as raw JSON.
+pub fn rpc_error(code: i64, message: &str, data: Option<&str>) -> ErrorPayload {
+    ErrorPayload {
+        code,
+        message: message.to_string().into(),
+        data: data.map(|d| serde_json::value::RawValue::from_string(d.to_string()).unwrap()),
+    }
+}
+
+/// A transaction at `nonce` still collecting votes.
+pub fn pending(nonce: u64, next_finalized: u64) -> TxStatus {
+    TxStatus::Pending {
+        tx_hash: Hash::ZERO,
+        account: Address::ZERO,
+        nonce,
+        account_next_finalized_nonce: next_finalized,
+        account_expected_nonce: nonce,
+        quorum: 3,
+        votes: NonceVotes {
+            account: Address::ZERO,
+            nonce,
+            txs: vec![],
+            bot_voters: vec![],
+            total_tx_votes: 1,
+            quorum: 3,
+        },
+    }
+}
+
+/// A transaction finalized at `nonce`.
+pub fn finalized(nonce: u64, success: bool) -> TxStatus {
+    TxStatus::Finalized {
+        tx_hash: Hash::ZERO,
+        account: Address::ZERO,
+        nonce,
+        success,
+        gas_used: 21_000,
+    }
+}
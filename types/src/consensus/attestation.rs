@@ -156,3 +156,10 @@ impl Merkleizable for AttestedTx {
         );
     }
 }
+
+/// What validators sign when attesting a transaction.
+impl Hashable for AttestedTx {
+    fn hash_custom(&self) -> Hash {
+        self.to_merkle_tree().hash_custom()
+    }
+}
@@ -10,6 +10,8 @@ pub enum CommitteeError {
     InsufficientQuorum { got: usize, required: usize },
     #[error("validator {0} not in committee")]
     ValidatorNotInCommittee(Address),
+    #[error("attested under committee epoch {got}, verifying against epoch {expected}")]
+    EpochMismatch { expected: u64, got: u64 },
     #[error(transparent)]
     SignatureError(#[from] alloy_primitives::SignatureError),
 }
@@ -18,6 +20,10 @@ pub enum CommitteeError {
 pub struct Committee {
     pub validators: BTreeSet<Address>,
     pub quorum_size: usize,
+    /// Epoch this validator set was active in. Nodes that predate epochs
+    /// omit it, which reads as epoch 0.
+    #[serde(default)]
+    pub epoch: u64,
 }
 
 impl Committee {
@@ -26,9 +32,15 @@ impl Committee {
         Committee {
             validators: validator_set,
             quorum_size,
+            epoch: 0,
         }
     }
 
+    pub fn with_epoch(mut self, epoch: u64) -> Self {
+        self.epoch = epoch;
+        self
+    }
+
     pub fn size(&self) -> usize {
         self.validators.len()
     }
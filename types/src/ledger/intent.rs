@@ -0,0 +1,125 @@
+//! Identifiers of orderbook intents.
+//!
+//! Every intent accepted by the orderbook precompile is keyed by
+//! `keccak256(abi.encode(address signer, uint64 nonce, uint32 sequence))`,
+//! where `sequence` is the intent's position inside a `submitBatch` envelope
+//! and `0` for a standalone call. Orders and withdrawals share the scheme, and
+//! `cancel`, `update` and `cancelTrigger` take the resulting id rather than the
+//! transaction hash, so it can be computed as soon as the transaction is signed.
+
+use alloy_consensus::Transaction as _;
+use alloy_primitives::Address;
+use alloy_sol_types::{SolCall, SolValue};
+
+use crate::{
+    Transaction,
+    cryptography::{
+        hash::{Hash, hash},
+        signer::Signed,
+    },
+};
+
+mod sol {
+    alloy_sol_types::sol! {
+        function submitBatch(bytes[] inner);
+        function delegated(address master, uint64 validUntil, bytes signature, bytes inner);
+    }
+}
+
+/// Id of the intent at `sequence` in a transaction from `signer` with `nonce`.
+pub fn intent_id(signer: Address, nonce: u64, sequence: u32) -> Hash {
+    hash((signer, nonce, sequence).abi_encode_params())
+}
+
+/// Id of the order placed at `sequence` by `tx`.
+///
+/// For a delegated call the id keys on the delegate that signed `tx`, not on
+/// the master that owns the order.
+pub fn order_id(tx: &Signed<Transaction>, sequence: u32) -> Hash {
+    intent_id(tx.signer, tx.signed.nonce(), sequence)
+}
+
+/// Id of the withdrawal requested at `sequence` by `tx`, as reported by
+/// `pod_withdrawals` and the bridge withdrawal endpoints.
+pub fn withdrawal_id(tx: &Signed<Transaction>, sequence: u32) -> Hash {
+    intent_id(tx.signer, tx.signed.nonce(), sequence)
+}
+
+/// Ids of every intent in `tx`, in sequence order.
+///
+/// A `submitBatch` yields one id per inner call, also when wrapped in
+/// `delegated`; any other call yields the single id at sequence `0`.
+pub fn intent_ids(tx: &Signed<Transaction>) -> Vec<Hash> {
+    let intents = intent_count(tx.signed.input());
+    (0..intents)
+        .map(|sequence| intent_id(tx.signer, tx.signed.nonce(), sequence))
+        .collect()
+}
+
+fn intent_count(calldata: &[u8]) -> u32 {
+    if let Ok(call) = sol::delegatedCall::abi_decode(calldata) {
+        return intent_count(&call.inner);
+    }
+    match sol::submitBatchCall::abi_decode(calldata) {
+        Ok(call) => call.inner.len() as u32,
+        Err(_) => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TxSigner;
+    use alloy_consensus::TxEip1559;
+    use alloy_primitives::Bytes;
+    use alloy_signer_local::PrivateKeySigner;
+
+    fn signed(nonce: u64, input: Vec<u8>) -> Signed<Transaction> {
+        PrivateKeySigner::random()
+            .sign_tx(Transaction::Eip1559(TxEip1559 {
+                nonce,
+                input: input.into(),
+                ..Default::default()
+            }))
+            .unwrap()
+    }
+
+    #[test]
+    fn intent_id_matches_abi_encode() {
+        let signer = Address::repeat_byte(0xab);
+        let mut encoded = [0u8; 96];
+        encoded[12..32].copy_from_slice(signer.as_slice());
+        encoded[56..64].copy_from_slice(&7u64.to_be_bytes());
+        encoded[92..96].copy_from_slice(&3u32.to_be_bytes());
+
+        assert_eq!(intent_id(signer, 7, 3), hash(encoded));
+    }
+
+    #[test]
+    fn standalone_call_has_one_intent() {
+        let tx = signed(4, vec![0x1e, 0x41, 0x62, 0x75]);
+        assert_eq!(intent_ids(&tx), vec![order_id(&tx, 0)]);
+        assert_eq!(order_id(&tx, 0), intent_id(tx.signer, 4, 0));
+    }
+
+    #[test]
+    fn batch_intents_get_own_sequence() {
+        let inner = vec![Bytes::from(vec![1u8; 36]), Bytes::from(vec![2u8; 36])];
+        let batch = sol::submitBatchCall { inner }.abi_encode();
+
+        let tx = signed(9, batch.clone());
+        let ids = intent_ids(&tx);
+        assert_eq!(ids, vec![order_id(&tx, 0), withdrawal_id(&tx, 1)]);
+
+        let delegated = sol::delegatedCall {
+            master: Address::repeat_byte(1),
+            validUntil: u64::MAX,
+            signature: Bytes::from(vec![0u8; 65]),
+            inner: batch.into(),
+        }
+        .abi_encode();
+        let tx = signed(9, delegated);
+        assert_eq!(intent_ids(&tx).len(), 2);
+        assert_eq!(intent_ids(&tx)[1], intent_id(tx.signer, 9, 1));
+    }
+}
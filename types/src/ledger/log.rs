@@ -112,13 +112,26 @@ impl VerifiableLog {
         )
     }
 
-    pub fn aggregate_signatures(&self) -> Vec<u8> {
+    /// Attestation signatures over `claim_hash` concatenated in strictly
+    /// ascending signer order, the order `ProofLib.computeTxWeight` requires.
+    ///
+    /// Signers are recovered rather than taken from the claimed `public_key`;
+    /// signatures that don't recover to a `committee` member, and repeats of a
+    /// signer, are dropped.
+    pub fn aggregate_signatures(&self, claim_hash: Hash, committee: &Committee) -> Vec<u8> {
         self.pod_metadata
             .attestations
             .iter()
-            .map(|a| a.signature.as_bytes())
-            .fold(Vec::new(), |mut acc, sig| {
-                acc.extend_from_slice(&sig);
+            .filter_map(|a| {
+                let signer = a.signature.recover_address_from_prehash(&claim_hash).ok()?;
+                committee
+                    .is_in_committee(&signer)
+                    .then_some((signer, a.signature))
+            })
+            .sorted_by_key(|(signer, _)| *signer)
+            .dedup_by(|a, b| a.0 == b.0)
+            .fold(Vec::new(), |mut acc, (_, sig)| {
+                acc.extend_from_slice(&sig.as_bytes());
                 acc
             })
     }
@@ -167,6 +180,68 @@ mod test {
 
     use crate::{AttestedTx, Hashable, Merkleizable, Transaction};
 
+    #[test]
+    fn aggregate_signatures_orders_by_recovered_signer() {
+        use alloy_primitives::Signature;
+        use alloy_signer::SignerSync;
+
+        use crate::consensus::attestation::TimestampedHeadlessAttestation;
+
+        let claim_hash = Hash::repeat_byte(0x42);
+        let members: Vec<PrivateKeySigner> = (0..3).map(|_| PrivateKeySigner::random()).collect();
+        let outsider = PrivateKeySigner::random();
+        let committee = Committee::new(members.iter().map(|m| m.address()), 2);
+
+        let attest = |signer: &PrivateKeySigner, claimed: Address| TimestampedHeadlessAttestation {
+            timestamp: Timestamp::from_micros(1),
+            public_key: claimed,
+            signature: signer.sign_hash_sync(&claim_hash).unwrap(),
+        };
+        // The outsider claims a member's key, and members[2] claims the
+        // outsider's: only the recovered signer counts.
+        let attestations = vec![
+            attest(&members[2], outsider.address()),
+            attest(&outsider, members[0].address()),
+            attest(&members[1], members[1].address()),
+            attest(&members[0], members[0].address()),
+            attest(&members[1], members[1].address()),
+        ];
+
+        let tx_hash = Hash::repeat_byte(0x11);
+        let log = VerifiableLog {
+            inner: to_rpc_format(Log::default(), tx_hash),
+            pod_metadata: PodLogMetadata {
+                attestations,
+                receipt: Receipt {
+                    status: true,
+                    actual_gas_used: 21_000,
+                    max_fee_per_gas: 1,
+                    logs: vec![],
+                    logs_root: Hash::default(),
+                    tx_hash,
+                    attested_tx: AttestedTx::new(tx_hash, 0),
+                    signer: Address::repeat_byte(0x22),
+                    to: None,
+                    contract_address: None,
+                },
+            },
+        };
+
+        let aggregated = log.aggregate_signatures(claim_hash, &committee);
+        let signers: Vec<Address> = aggregated
+            .chunks_exact(65)
+            .map(|chunk| {
+                Signature::try_from(chunk)
+                    .unwrap()
+                    .recover_address_from_prehash(&claim_hash)
+                    .unwrap()
+            })
+            .collect();
+        let mut expected: Vec<Address> = members.iter().map(|m| m.address()).collect();
+        expected.sort();
+        assert_eq!(signers, expected);
+    }
+
     #[tokio::test]
     async fn test_verifiable_log_hash_proof_single_log() {
         let log = Log {
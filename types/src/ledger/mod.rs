@@ -1,4 +1,5 @@
 pub mod calldata;
+pub mod intent;
 pub mod log;
 pub mod receipt;
 pub mod transaction;
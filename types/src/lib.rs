@@ -4,6 +4,7 @@ pub mod ledger;
 pub mod metadata;
 pub mod rpc;
 pub mod time;
+pub mod wad;
 
 pub mod pagination;
 
@@ -20,4 +21,5 @@ pub use crate::{
     ledger::{CallData, Receipt, Transaction},
     rpc::filter::{LogFilter, LogFilterBuilder},
     time::{Clock, Timestamp},
+    wad::{SignedWad, Wad},
 };
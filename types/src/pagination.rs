@@ -30,6 +30,16 @@ pub struct ApiPaginatedResult<T: Serialize> {
     pub cursor: Option<(String, String)>,
 }
 
+/// A page of a method that pages with an opaque `next_cursor`, which is
+/// passed back as the next request's `cursor` until it comes back empty.
+pub trait CursorPage {
+    type Item;
+
+    fn next_cursor(&self) -> Option<&str>;
+
+    fn into_items(self) -> Vec<Self::Item>;
+}
+
 pub fn serialize_cursor<S>(
     cursor: &Option<(String, String)>,
     serializer: S,
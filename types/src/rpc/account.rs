@@ -0,0 +1,200 @@
+//! Responses describing an account's nonce state and its transactions.
+
+use alloy_primitives::Address;
+use serde::{Deserialize, Serialize};
+
+use crate::Hash;
+
+/// `pod_getRecoveryTargetTx` result: the transaction a locked account recovers
+/// to. Recovery finalizes the chain of transactions ending at it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecoveryTarget {
+    pub hash: Hash,
+    pub nonce: u64,
+}
+
+/// A validator that voted, as resolved from the committee.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Voter {
+    pub validator_index: usize,
+    pub validator_address: Address,
+}
+
+/// The validators that voted for one transaction at an account's nonce.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxVotes {
+    pub tx_hash: Hash,
+    pub voters: Vec<Voter>,
+}
+
+/// Votes seen by the node at one nonce of an account.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NonceVotes {
+    pub account: Address,
+    pub nonce: u64,
+    /// One entry per transaction voted for. More than one means the votes
+    /// are split.
+    pub txs: Vec<TxVotes>,
+    /// Validators that voted for no transaction at this nonce.
+    pub bot_voters: Vec<Voter>,
+    /// Validators that voted for any transaction, each counted once.
+    pub total_tx_votes: usize,
+    /// Votes a certificate needs.
+    pub quorum: usize,
+}
+
+/// What one validator voted at one nonce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ValidatorVote {
+    Tx {
+        tx_hash: Hash,
+    },
+    /// Voted for no transaction.
+    Bot,
+    /// No vote seen from it.
+    NotSeen,
+}
+
+/// One validator's vote state for an account, part of [`AccountDiagnostics`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorDiagnostics {
+    pub validator_index: usize,
+    pub validator_address: Address,
+    /// Highest pending nonce it voted at.
+    #[serde(default)]
+    pub highest_attested_nonce: Option<u64>,
+    /// Its vote at the latest pending nonce.
+    pub current_vote: ValidatorVote,
+    /// Voted for two different transactions at one nonce.
+    pub equivocating: bool,
+}
+
+/// `pod_getAccountDiagnostics` result.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountDiagnostics {
+    pub account: Address,
+    pub next_finalized_nonce: u64,
+    pub expected_nonce: u64,
+    pub last_finalized_tx: Hash,
+    /// A transaction is pending decision.
+    pub is_locked: bool,
+    pub quorum: usize,
+    pub validators: Vec<ValidatorDiagnostics>,
+    pub needs_recovery: bool,
+    #[serde(default)]
+    pub recovery_target: Option<RecoveryTarget>,
+    /// Some order of the account is waiting for the orderbook's next solution.
+    pub has_clob_order_pending_solution: bool,
+    /// Two transactions at one nonce both reached the certificate threshold.
+    pub equivocating: bool,
+}
+
+/// A validator's vote at one pending nonce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorNonceVote {
+    pub nonce: u64,
+    pub vote: ValidatorVote,
+}
+
+/// `pod_getValidatorAccountStatus` result: how one validator voted across an
+/// account's pending nonces.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorAccountStatus {
+    pub validator_index: usize,
+    pub validator_address: Address,
+    pub account: Address,
+    pub account_next_finalized_nonce: u64,
+    pub account_expected_nonce: u64,
+    pub quorum: usize,
+    /// At most the newest 256 pending nonces.
+    pub pending_nonces: Vec<ValidatorNonceVote>,
+}
+
+/// `pod_getTxStatus` result.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TxStatus {
+    /// The node knows neither the transaction nor a receipt for it.
+    NotFound,
+    /// Still gathering votes.
+    Pending {
+        tx_hash: Hash,
+        account: Address,
+        nonce: u64,
+        /// Every lower nonce of the account is finalized.
+        account_next_finalized_nonce: u64,
+        account_expected_nonce: u64,
+        quorum: usize,
+        votes: NonceVotes,
+    },
+    /// Executed, successfully or not.
+    Finalized {
+        tx_hash: Hash,
+        account: Address,
+        nonce: u64,
+        success: bool,
+        gas_used: u64,
+    },
+}
+
+impl TxStatus {
+    pub fn is_finalized(&self) -> bool {
+        matches!(self, Self::Finalized { .. })
+    }
+
+    /// Whether a different transaction, such as a recovery, was finalized at
+    /// this one's nonce, so it can never execute.
+    pub fn is_superseded(&self) -> bool {
+        matches!(
+            self,
+            Self::Pending {
+                nonce,
+                account_next_finalized_nonce,
+                ..
+            } if account_next_finalized_nonce > nonce
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_tx_status_variants() {
+        let pending: TxStatus = serde_json::from_str(
+            r#"{
+                "status": "pending",
+                "tx_hash": "0xf74e07ff80dc54c7e894396954326fe13f07d176746a6a29d0ea34922b856402",
+                "account": "0x742d35cc6634c0532925a3b844bc9e7595f2bd28",
+                "nonce": 4,
+                "account_next_finalized_nonce": 4,
+                "account_expected_nonce": 5,
+                "quorum": 3,
+                "votes": {
+                    "account": "0x742d35cc6634c0532925a3b844bc9e7595f2bd28",
+                    "nonce": 4,
+                    "txs": [{
+                        "tx_hash": "0xf74e07ff80dc54c7e894396954326fe13f07d176746a6a29d0ea34922b856402",
+                        "voters": [{"validator_index": 0, "validator_address": "0x1111111111111111111111111111111111111111"}]
+                    }],
+                    "bot_voters": [],
+                    "total_tx_votes": 1,
+                    "quorum": 3
+                }
+            }"#,
+        )
+        .unwrap();
+        assert!(!pending.is_superseded());
+        let TxStatus::Pending { nonce, votes, .. } = pending else {
+            panic!("expected pending, got {pending:?}");
+        };
+        assert_eq!(nonce, 4);
+        assert_eq!(votes.txs[0].voters[0].validator_index, 0);
+
+        let not_found: TxStatus = serde_json::from_str(r#"{"status":"not_found"}"#).unwrap();
+        assert_eq!(not_found, TxStatus::NotFound);
+        assert!(!not_found.is_finalized());
+    }
+}
@@ -0,0 +1,170 @@
+//! Responses of the bridge methods and the bridge REST endpoints.
+
+use alloy_primitives::{Address, Bytes, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::Hash;
+
+/// `pod_getBridgeClaimProof` result for a bridge precompile withdrawal.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BridgeClaimProof {
+    /// 65-byte `(r, s, v)` validator signatures over the claim hash,
+    /// concatenated in no particular order.
+    pub signatures: Bytes,
+    pub committee_epoch: u64,
+    /// Hash of the withdrawal transaction the proof is for.
+    pub proof: Hash,
+}
+
+/// `pod_getProcessedDeposits` result: the ids of source-chain deposits pod has
+/// already credited.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessedDeposits {
+    /// Every id below it has been processed.
+    pub watermark: U256,
+    /// Processed ids at or above `watermark`, ascending.
+    pub above_watermark: Vec<U256>,
+}
+
+impl ProcessedDeposits {
+    pub fn contains(&self, id: U256) -> bool {
+        id < self.watermark || self.above_watermark.binary_search(&id).is_ok()
+    }
+}
+
+/// What became of one orderbook withdrawal when its tick executed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Withdrawal {
+    /// `keccak256(abi.encode(signer, nonce, sequence))`, computable before
+    /// submitting.
+    pub withdrawal_id: Hash,
+    /// The debited account: the master for a delegated withdrawal.
+    pub withdrawer: Address,
+    /// Recipient on the claim chain.
+    pub to: Address,
+    /// Pod-side token.
+    pub token: Address,
+    /// In pod's 18 decimals, as signed. Not the amount the claim pays out.
+    pub amount: U256,
+    /// Why nothing was debited. `None` when the withdrawal went through.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<WithdrawalFailure>,
+    pub timestamp_us: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WithdrawalFailure {
+    /// The balance did not cover it at execution.
+    InsufficientBalance,
+    /// The solver omitted it before its deadline passed.
+    NotIncluded,
+    /// A reason this build doesn't know. Still a failure.
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WithdrawalStatus {
+    Claimable,
+    Pending,
+    Refused,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct WithdrawalProof {
+    /// The `txHash` the claim chain's `Claim` event is indexed by.
+    #[serde(default)]
+    pub claim_hash: Option<Hash>,
+}
+
+/// `GET /v1/bridge/withdrawals/by-id/{id}` result.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WithdrawalDetail {
+    pub withdrawal: Withdrawal,
+    pub status: WithdrawalStatus,
+    #[serde(default)]
+    pub proof: Option<WithdrawalProof>,
+}
+
+/// Where a withdrawal has got to on its way to the claim chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ClaimStatus {
+    /// Not claimable yet, or not known to the node yet. Ask again.
+    Pending,
+    Claimable {
+        claim_hash: Hash,
+    },
+    /// Refused at execution with nothing debited. No claim will ever exist.
+    Refused,
+}
+
+impl WithdrawalDetail {
+    /// A claim hash in the proof makes the withdrawal claimable whatever
+    /// `status` says, so a status this build doesn't know can't hide it. Only
+    /// then is `refused` terminal; anything else is pending.
+    pub fn claim_status(&self) -> ClaimStatus {
+        if let Some(claim_hash) = self.proof.as_ref().and_then(|proof| proof.claim_hash) {
+            return ClaimStatus::Claimable { claim_hash };
+        }
+        match self.status {
+            WithdrawalStatus::Refused => ClaimStatus::Refused,
+            _ => ClaimStatus::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn processed_deposits_membership() {
+        let processed: ProcessedDeposits =
+            serde_json::from_str(r#"{"watermark":"0xa","above_watermark":["0xc","0xf"]}"#).unwrap();
+
+        assert!(processed.contains(U256::from(9)));
+        assert!(!processed.contains(U256::from(10)));
+        assert!(processed.contains(U256::from(12)));
+        assert!(!processed.contains(U256::from(13)));
+    }
+
+    #[test]
+    fn claim_hash_takes_precedence_over_status() {
+        let withdrawal = r#"{
+            "withdrawal_id": "0x1111111111111111111111111111111111111111111111111111111111111111",
+            "withdrawer": "0x2222222222222222222222222222222222222222",
+            "to": "0x3333333333333333333333333333333333333333",
+            "token": "0x4444444444444444444444444444444444444444",
+            "amount": "0xde0b6b3a7640000",
+            "timestamp_us": 1700000000000000
+        }"#;
+        let claim_hash = Hash::repeat_byte(0xcc);
+        let detail = |status: &str, proof: &str| -> WithdrawalDetail {
+            serde_json::from_str(&format!(
+                r#"{{"withdrawal":{withdrawal},"status":"{status}"{proof}}}"#
+            ))
+            .unwrap()
+        };
+        let with_proof = format!(r#","proof":{{"claim_hash":"{claim_hash}"}}"#);
+
+        assert_eq!(
+            detail("settling", &with_proof).claim_status(),
+            ClaimStatus::Claimable { claim_hash }
+        );
+        assert_eq!(
+            detail("refused", &with_proof).claim_status(),
+            ClaimStatus::Claimable { claim_hash }
+        );
+        assert_eq!(detail("refused", "").claim_status(), ClaimStatus::Refused);
+        assert_eq!(
+            detail("claimable", r#","proof":{"claim_hash":null}"#).claim_status(),
+            ClaimStatus::Pending
+        );
+        assert_eq!(detail("settling", "").claim_status(), ClaimStatus::Pending);
+    }
+}
@@ -1,2 +1,6 @@
+pub mod account;
+pub mod bridge;
 pub mod filter;
+pub mod orderbook;
 pub mod receipt;
+pub mod subscription;
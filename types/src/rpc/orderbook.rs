@@ -0,0 +1,729 @@
+//! Requests and responses of the `ob_*` orderbook data methods.
+//!
+//! Prices and amounts are 1e18 fixed-point. Unsigned values travel as hex
+//! strings and signed ones as decimal strings; both deserialize from either.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use alloy_primitives::{Address, I256, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::{Hash, Timestamp, pagination::CursorPage};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketType {
+    Spot,
+    Perpetual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderType {
+    #[default]
+    Limit,
+    Market,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    /// Submitted but not yet included in the orderbook.
+    Pending,
+    Active,
+    Filled,
+    /// Its TTL ran out.
+    Expired,
+    /// Canceled by the owner.
+    Canceled,
+    /// Removed by the engine because the owner's margin was exhausted.
+    MarginCanceled,
+    /// A post-only order that would have taken liquidity.
+    PostOnlyRefused,
+    /// Rejected at execution; never entered the book.
+    Invalid,
+}
+
+impl OrderStatus {
+    /// Whether the order can no longer change.
+    pub fn is_terminal(&self) -> bool {
+        !matches!(self, Self::Pending | Self::Active)
+    }
+}
+
+/// Where an order came from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderKind {
+    #[default]
+    UserSigned,
+    Liquidation,
+    /// The synthetic order of a fired trigger.
+    Triggered,
+    /// Auto-deleveraging of a profitable position, and the order it closed
+    /// against.
+    Adl,
+    AdlCounterparty,
+    /// A liquidated position moved to the backstop.
+    BackstopTransfer,
+}
+
+/// Position effect of an order. Spot orders use `Buy` and `Sell`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderDirection {
+    Buy,
+    Sell,
+    OpenLong,
+    AddLong,
+    ReduceLong,
+    CloseLong,
+    OpenShort,
+    AddShort,
+    ReduceShort,
+    CloseShort,
+    LongToShort,
+    ShortToLong,
+    Liquidation,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerType {
+    TakeProfit,
+    StopLoss,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerGrouping {
+    #[default]
+    None,
+    /// Bound to the bidder's exposure on the pair. Older nodes call it
+    /// `position`.
+    #[serde(alias = "position")]
+    Asset,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PerpPositionSide {
+    Long,
+    Short,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum CandleResolution {
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "15m")]
+    FifteenMinutes,
+    #[serde(rename = "30m")]
+    ThirtyMinutes,
+    #[serde(rename = "1h")]
+    OneHour,
+    #[serde(rename = "4h")]
+    FourHours,
+    #[serde(rename = "1d")]
+    OneDay,
+    #[serde(rename = "1W")]
+    OneWeek,
+    /// Calendar months; see [`CandleResolution::as_duration`].
+    #[serde(rename = "1M")]
+    OneMonth,
+}
+
+impl CandleResolution {
+    /// Width of a bar. Months are taken as a nominal 30 days.
+    pub fn as_duration(&self) -> Duration {
+        Duration::from_secs(match self {
+            Self::OneMinute => 60,
+            Self::FiveMinutes => 5 * 60,
+            Self::FifteenMinutes => 15 * 60,
+            Self::ThirtyMinutes => 30 * 60,
+            Self::OneHour => 60 * 60,
+            Self::FourHours => 4 * 60 * 60,
+            Self::OneDay => 24 * 60 * 60,
+            Self::OneWeek => 7 * 24 * 60 * 60,
+            Self::OneMonth => 30 * 24 * 60 * 60,
+        })
+    }
+
+    /// Bars in a canonical page of `ob_getCandles`, at most the node's cap of
+    /// 500. Pages are anchored at the epoch, so every client asks for the same
+    /// windows and gets settled pages that never change.
+    pub fn page_buckets(&self) -> u64 {
+        match self {
+            Self::OneMinute => 360,
+            Self::FiveMinutes => 288,
+            Self::FifteenMinutes => 96,
+            Self::ThirtyMinutes => 336,
+            Self::OneHour => 168,
+            Self::FourHours => 180,
+            Self::OneDay => 365,
+            Self::OneWeek => 260,
+            Self::OneMonth => 120,
+        }
+    }
+}
+
+/// A market and its 24-hour statistics, from `ob_getMarkets`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Market {
+    pub id: Hash,
+    pub name: String,
+    pub base_token_address: Address,
+    pub quote_token_address: Address,
+    pub base_token_symbol: String,
+    pub quote_token_symbol: String,
+    pub base_token_name: String,
+    pub quote_token_name: String,
+    pub market_type: MarketType,
+    pub last_clearing_price: U256,
+    /// Length of a matching round in microseconds; deadlines must be
+    /// multiples of it.
+    pub auction_interval: u64,
+    pub volume_24h: U256,
+    pub high_24h: U256,
+    pub low_24h: U256,
+    pub price_change_24h: i64,
+    pub maker_fee: U256,
+    pub taker_fee: U256,
+    pub tick_precision: U256,
+    pub lot_size: U256,
+    pub max_leverage: u64,
+    // Perp markets only.
+    pub oracle_price: Option<U256>,
+    pub mark_price: Option<U256>,
+    pub funding_rate: Option<I256>,
+    pub funding_index: Option<I256>,
+    pub funding_last_updated: Option<Timestamp>,
+    pub open_interest: Option<U256>,
+}
+
+impl Market {
+    pub fn auction_interval(&self) -> Duration {
+        Duration::from_micros(self.auction_interval)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TickSnapshot {
+    pub volume: U256,
+}
+
+/// Price levels of an orderbook, from `ob_getOrderbook`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderbookSnapshot {
+    #[serde(alias = "clob_id")]
+    pub orderbook_id: Hash,
+    /// Volume resting at each price.
+    pub buys: BTreeMap<U256, TickSnapshot>,
+    pub sells: BTreeMap<U256, TickSnapshot>,
+    pub clearing_price: U256,
+    pub grouping_precision: U256,
+    pub timestamp: Timestamp,
+    pub new_orders_count: u64,
+    /// Orders on each side, regardless of the requested depth.
+    pub buys_count: u64,
+    pub sells_count: u64,
+    // Perp orderbooks only.
+    pub oracle_price: Option<U256>,
+    pub funding_rate: Option<I256>,
+    pub funding_index: Option<I256>,
+    pub funding_last_updated: Option<Timestamp>,
+}
+
+impl OrderbookSnapshot {
+    /// Highest bid and its volume.
+    pub fn best_bid(&self) -> Option<(U256, U256)> {
+        self.buys
+            .iter()
+            .next_back()
+            .map(|(price, tick)| (*price, tick.volume))
+    }
+
+    /// Lowest ask and its volume.
+    pub fn best_ask(&self) -> Option<(U256, U256)> {
+        self.sells
+            .iter()
+            .next()
+            .map(|(price, tick)| (*price, tick.volume))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CandlesQuery {
+    pub resolution: CandleResolution,
+    pub from_ts: Timestamp,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_ts: Option<Timestamp>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+}
+
+/// An OHLCV bar, from `ob_getCandles`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Candle {
+    /// Start of the bar.
+    pub timestamp: Timestamp,
+    pub open: U256,
+    pub close: U256,
+    pub high: U256,
+    pub low: U256,
+    /// Base volume.
+    pub volume: U256,
+    /// Quote notional of the volume.
+    #[serde(default)]
+    pub quote_volume: U256,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrdersQuery {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orderbook_id: Option<Hash>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<OrderStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub with_fills: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub with_total: Option<bool>,
+}
+
+/// One batch's fill of an order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartialFill {
+    pub base_amount: U256,
+    pub quote_amount: U256,
+    pub timestamp: Timestamp,
+    pub price: U256,
+}
+
+/// An order, from `ob_getOrders`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Order {
+    pub orderbook_id: Hash,
+    pub market_type: MarketType,
+    pub kind: OrderKind,
+    /// `keccak256(abi.encode(signer, nonce, sequence))`, which cancels and
+    /// updates refer to.
+    pub order_id: Hash,
+    /// Zero for engine-generated orders.
+    pub tx_hash: Hash,
+    pub bidder: Address,
+    pub nonce: u64,
+    pub order_type: OrderType,
+    pub status: OrderStatus,
+    pub side: Side,
+    pub price: U256,
+    /// Positive to buy, negative to sell.
+    pub initial_size: I256,
+    pub filled_base_amount: U256,
+    pub filled_quote_amount: U256,
+    pub fee: U256,
+    /// The auction deadline the order was signed for. Zero when unknown, as
+    /// for an order first seen on a `pod_orders_v2` frame.
+    pub deadline: Timestamp,
+    pub end: Timestamp,
+    /// When the order entered the book. Every source that has it agrees on
+    /// it, so orders are best ordered by it rather than by `deadline`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub included_batch: Option<Timestamp>,
+    pub effective_price: U256,
+    #[serde(default)]
+    pub fills: Vec<PartialFill>,
+    // Perp orders only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reduce_only: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ioc: Option<bool>,
+    #[serde(default)]
+    pub post_only: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direction: Option<OrderDirection>,
+    #[serde(default)]
+    pub grouping: TriggerGrouping,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger_type: Option<TriggerType>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrdersPage {
+    pub orders: Vec<Order>,
+    /// Only when the query set `with_total`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_count: Option<u64>,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FillsQuery {
+    pub from_ts: Timestamp,
+    /// Exclusive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_ts: Option<Timestamp>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orderbook_id: Option<Hash>,
+    /// At most 500, the default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+}
+
+/// One batch's fill of an order, from `ob_getFills`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fill {
+    pub orderbook_id: Hash,
+    pub base_token: Address,
+    pub quote_token: Address,
+    pub order_id: Hash,
+    pub order_type: OrderType,
+    pub initial_size: I256,
+    pub fee: U256,
+    pub base_amount: U256,
+    pub quote_amount: U256,
+    pub timestamp: Timestamp,
+    pub price: U256,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FillsResponse {
+    pub fills: Vec<Fill>,
+}
+
+/// Filters of `ob_getTriggers` and `ob_getBackstopTransfers`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountPageQuery {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orderbook_id: Option<Hash>,
+    /// Clamped to `[1, 200]`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+/// An armed TP/SL trigger, from `ob_getTriggers`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TriggerOrder {
+    pub orderbook_id: Hash,
+    pub order_id: Hash,
+    pub tx_hash: Hash,
+    pub bidder: Address,
+    pub nonce: u64,
+    pub size: I256,
+    pub limit_price: U256,
+    pub trigger_price: U256,
+    pub trigger_type: TriggerType,
+    pub grouping: TriggerGrouping,
+    pub reduce_only: bool,
+    pub ioc: bool,
+    #[serde(with = "micros_or_string")]
+    pub deadline: Timestamp,
+    #[serde(with = "micros_or_string")]
+    pub end: Timestamp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TriggersPage {
+    pub triggers: Vec<TriggerOrder>,
+    pub total_count: u64,
+    pub next_cursor: Option<String>,
+}
+
+/// A perp position swept to the backstop in a liquidation, from
+/// `ob_getBackstopTransfers`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackstopTransfer {
+    pub user: Address,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orderbook_id: Option<Hash>,
+    pub size: I256,
+    pub cash: I256,
+    pub mark_price: U256,
+    pub equity: I256,
+    pub timestamp: Timestamp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackstopTransfersPage {
+    pub transfers: Vec<BackstopTransfer>,
+    pub total_count: u64,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OraclePrice {
+    pub orderbook_id: Hash,
+    pub oracle_price: U256,
+    pub mark_price: U256,
+    pub as_of: Timestamp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FundingRate {
+    pub orderbook_id: Hash,
+    /// Per funding window.
+    pub funding_rate: I256,
+    pub funding_index: I256,
+    pub mark_price: U256,
+    pub oracle_price: U256,
+    pub as_of: Timestamp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpotHolding {
+    pub orderbook_id: Hash,
+    pub base_symbol: String,
+    pub quote_symbol: String,
+    pub balance: U256,
+    pub free_balance: U256,
+    pub locked_balance: U256,
+    pub cost_basis: U256,
+    pub mark_price: U256,
+    pub unrealized_pnl: I256,
+    pub realized_pnl: I256,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PerpPosition {
+    pub orderbook_id: Hash,
+    pub base_symbol: String,
+    pub quote_symbol: String,
+    pub side: PerpPositionSide,
+    pub size: I256,
+    pub notional: U256,
+    pub entry_price: U256,
+    pub mark_price: U256,
+    pub margin: U256,
+    pub leverage: U256,
+    pub funding_accrued: I256,
+    pub liquidation_price: U256,
+    pub unrealized_pnl: I256,
+    pub realized_pnl: I256,
+    #[serde(default)]
+    pub stop_loss: Option<U256>,
+    #[serde(default)]
+    pub take_profit: Option<U256>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Position {
+    Spot(SpotHolding),
+    Perp(PerpPosition),
+}
+
+/// An account's holdings and PnL, from `ob_getPositions`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PositionsResponse {
+    pub positions: Vec<Position>,
+    pub total_unrealized_pnl: I256,
+    pub total_realized_pnl: I256,
+    pub perps_equity: I256,
+    pub account_value: I256,
+    pub cash: I256,
+    pub withdrawable_cash: U256,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub net_deposits: Option<I256>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RankedPosition {
+    pub account: Address,
+    pub positions: PositionsResponse,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RankedAccount {
+    pub account: Address,
+    /// Zero-based, in the full ordering.
+    pub rank: u64,
+    pub positions: PositionsResponse,
+}
+
+/// Accounts by combined realized and unrealized PnL, from
+/// `ob_getRankedPositions`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RankedPositionsResponse {
+    pub ranked: Vec<RankedPosition>,
+    pub total: u64,
+    #[serde(default)]
+    pub queried: Option<RankedAccount>,
+}
+
+impl CursorPage for OrdersPage {
+    type Item = Order;
+
+    fn next_cursor(&self) -> Option<&str> {
+        self.next_cursor.as_deref()
+    }
+
+    fn into_items(self) -> Vec<Order> {
+        self.orders
+    }
+}
+
+impl CursorPage for TriggersPage {
+    type Item = TriggerOrder;
+
+    fn next_cursor(&self) -> Option<&str> {
+        self.next_cursor.as_deref()
+    }
+
+    fn into_items(self) -> Vec<TriggerOrder> {
+        self.triggers
+    }
+}
+
+impl CursorPage for BackstopTransfersPage {
+    type Item = BackstopTransfer;
+
+    fn next_cursor(&self) -> Option<&str> {
+        self.next_cursor.as_deref()
+    }
+
+    fn into_items(self) -> Vec<BackstopTransfer> {
+        self.transfers
+    }
+}
+
+// Some responses send microsecond timestamps as decimal strings.
+mod micros_or_string {
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    use crate::Timestamp;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Micros {
+        Number(u128),
+        String(String),
+    }
+
+    pub fn serialize<S: Serializer>(ts: &Timestamp, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&ts.as_micros().to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Timestamp, D::Error> {
+        let micros = match Micros::deserialize(deserializer)? {
+            Micros::Number(micros) => micros,
+            Micros::String(s) => s.parse().map_err(D::Error::custom)?,
+        };
+        Ok(Timestamp::from_micros(micros))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_documented_examples() {
+        let snapshot: OrderbookSnapshot = serde_json::from_str(
+            r#"{
+                "orderbook_id": "0x0000000000000000000000000000000000000000000000000000000000000001",
+                "buys": {
+                    "5000000000000000000": { "volume": "0xde0b6b3a7640000" },
+                    "4900000000000000000": { "volume": "0x1bc16d674ec80000" }
+                },
+                "sells": { "5100000000000000000": { "volume": "0x14d1120d7b160000" } },
+                "clearing_price": "0x4563918244f40000",
+                "grouping_precision": "0x1",
+                "timestamp": 1704153600000000,
+                "new_orders_count": 5,
+                "buys_count": 2,
+                "sells_count": 1,
+                "oracle_price": null,
+                "funding_rate": null,
+                "funding_index": null,
+                "funding_last_updated": null
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            snapshot.best_bid(),
+            Some((
+                U256::from(5_000_000_000_000_000_000u128),
+                U256::from(1_000_000_000_000_000_000u128)
+            ))
+        );
+        assert_eq!(
+            snapshot.best_ask().unwrap().0,
+            U256::from(5_100_000_000_000_000_000u128)
+        );
+
+        let page: TriggersPage = serde_json::from_str(
+            r#"{
+                "triggers": [{
+                    "orderbook_id": "0x0000000000000000000000000000000000000000000000000000000000000007",
+                    "order_id": "0x1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef",
+                    "tx_hash": "0xabcdef1234567890abcdef1234567890abcdef1234567890abcdef1234567890",
+                    "bidder": "0x742d35Cc6634C0532925a3b844Bc9e7595f2bD28",
+                    "nonce": 7,
+                    "size": "-10000000000000000",
+                    "limit_price": "0x115ad84a8ad03c00000",
+                    "trigger_price": "0x120a871cc0020a00000",
+                    "trigger_type": "take_profit",
+                    "grouping": "position",
+                    "reduce_only": true,
+                    "ioc": false,
+                    "deadline": "1704153600000000",
+                    "end": "1704240000000000"
+                }],
+                "total_count": 1,
+                "next_cursor": null
+            }"#,
+        )
+        .unwrap();
+        let trigger = &page.triggers[0];
+        assert!(trigger.size.is_negative());
+        assert_eq!(trigger.grouping, TriggerGrouping::Asset);
+        assert_eq!(trigger.deadline, Timestamp::from_micros(1704153600000000));
+        assert!(page.next_cursor().is_none());
+
+        let positions: PositionsResponse = serde_json::from_str(
+            r#"{
+                "positions": [{
+                    "kind": "perp",
+                    "orderbook_id": "0x0000000000000000000000000000000000000000000000000000000000000007",
+                    "base_symbol": "NVDA",
+                    "quote_symbol": "USD",
+                    "side": "long",
+                    "size": "10000000000000000",
+                    "notional": "0x4f0d4f7e7a3e0000",
+                    "entry_price": "0x130ee8e7179044a0000",
+                    "mark_price": "0x130ee8e7179044a0000",
+                    "margin": "0xc7d713b49da00000",
+                    "leverage": "0x4563918244f40000",
+                    "funding_accrued": "0",
+                    "liquidation_price": "0x115ad84a8ad03c00000",
+                    "unrealized_pnl": "0",
+                    "realized_pnl": "0",
+                    "stop_loss": null,
+                    "take_profit": null
+                }],
+                "total_unrealized_pnl": "0",
+                "total_realized_pnl": "0",
+                "perps_equity": "0",
+                "account_value": "0x21e19e0c9bab2400000",
+                "cash": "0",
+                "withdrawable_cash": "0x0"
+            }"#,
+        )
+        .unwrap();
+        assert!(matches!(positions.positions[0], Position::Perp(_)));
+    }
+}
@@ -0,0 +1,491 @@
+//! Options and frames of `eth_subscribe` on the pod channels:
+//! `pod_orderbook`, `pod_orders`, `pod_orders_v2`, `pod_candles`,
+//! `pod_markets`, `pod_positions`, `pod_triggers` and `pod_withdrawals`.
+//!
+//! `pod_orderbook` frames are [`OrderbookSnapshot`]s and `pod_withdrawals`
+//! frames lists of [`Withdrawal`](super::bridge::Withdrawal)s, as over REST.
+//!
+//! [`OrderbookSnapshot`]: super::orderbook::OrderbookSnapshot
+
+use alloy_primitives::{Address, I256, U256};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::orderbook::{
+    Order, OrderKind, OrderStatus, OrderType, PositionsResponse, TriggerGrouping, TriggerType,
+    TriggersPage,
+};
+use crate::{Hash, Timestamp};
+
+/// The second `eth_subscribe` param. Which fields apply depends on the
+/// channel; the node rejects a filter a channel does not take.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubscriptionParams {
+    /// Orderbooks to restrict the stream to. Empty means all.
+    #[serde(default, alias = "clob_ids", skip_serializing_if = "Vec::is_empty")]
+    pub orderbook_ids: Vec<Hash>,
+    /// `pod_orderbook` only: price levels per side in each snapshot.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth: Option<u32>,
+    /// The account to stream. Required by `pod_positions` and `pod_triggers`.
+    #[serde(default, alias = "account", skip_serializing_if = "Option::is_none")]
+    pub bidder: Option<Address>,
+    /// `pod_orders_v2` only: up to 64 accounts, instead of `bidder`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bidders: Vec<Address>,
+    /// Catch up from after this solution time, in microseconds. Omitted
+    /// streams live only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<u64>,
+    /// `pod_orders_v2` only: the `book` of the last frame accepted at
+    /// `since`. Omitted when the whole of `since` arrived.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since_book: Option<Hash>,
+}
+
+impl SubscriptionParams {
+    pub fn with_orderbooks(mut self, orderbook_ids: impl IntoIterator<Item = Hash>) -> Self {
+        self.orderbook_ids = orderbook_ids.into_iter().collect();
+        self
+    }
+
+    pub fn with_depth(mut self, depth: u32) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    pub fn with_account(mut self, account: Address) -> Self {
+        self.bidder = Some(account);
+        self
+    }
+
+    pub fn with_since(mut self, since: u64) -> Self {
+        self.since = Some(since);
+        self
+    }
+}
+
+/// One change to an order in a `pod_orders` frame.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OrderUpdate {
+    New(Order),
+    /// Rejected at execution, having never entered the book.
+    Invalid(Order),
+    Expired {
+        order_id: Hash,
+    },
+    /// Canceled by the owner or removed by the engine, which this channel does
+    /// not tell apart. `pod_orders_v2` does.
+    Canceled {
+        order_id: Hash,
+    },
+    /// Repriced or resized in place.
+    Modified {
+        order_id: Hash,
+        new_price: U256,
+        new_size: U256,
+    },
+    Fill(OrderFill),
+    #[serde(other)]
+    Unknown,
+}
+
+/// A fill of a resting order in one batch, from `pod_orders`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderFill {
+    pub orderbook_id: Hash,
+    pub order_id: Hash,
+    pub tx_hash: Hash,
+    pub bidder: Address,
+    /// `Filled`, or `Active` after a partial fill.
+    pub status: OrderStatus,
+    /// Filled in this batch.
+    pub base_amount: U256,
+    pub quote_amount: U256,
+    /// Filled over the order's life.
+    pub filled_base_amount: U256,
+    pub filled_quote_amount: U256,
+    pub effective_price: U256,
+    pub fee: U256,
+    // Perp orders only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position_before: Option<I256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position_after: Option<I256>,
+}
+
+/// A `pod_orders_v2` frame: what happened to one orderbook in one batch.
+///
+/// Orders created in the batch appear once in `orders`; `events` refer to
+/// them by index, and to orders resting from earlier batches by id.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrdersFrame {
+    pub book: Hash,
+    /// Deadline of the batch the actions landed in, in microseconds. With
+    /// `book`, the cursor to resume from as `since` and `since_book`.
+    pub batch: u64,
+    /// Owners, indexed by the `account` of orders and events. `None` when the
+    /// subscription names a single account, which owns every row.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accts: Option<Vec<Address>>,
+    pub orders: Vec<OrderEntity>,
+    /// In the order they are to be applied.
+    pub events: Vec<OrderEvent>,
+}
+
+impl OrdersFrame {
+    /// The owner at `index` of [`Self::accts`], or `account` when the frame
+    /// has no table.
+    pub fn owner(&self, index: Option<usize>, account: Address) -> Option<Address> {
+        match (&self.accts, index) {
+            (None, _) => Some(account),
+            (Some(accts), Some(index)) => accts.get(index).copied(),
+            (Some(_), None) => None,
+        }
+    }
+}
+
+/// An order as admitted in a `pod_orders_v2` frame. Its status follows from
+/// the events.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderEntity {
+    pub id: Hash,
+    /// Zero for engine-generated orders.
+    #[serde(rename = "tx")]
+    pub tx_hash: Hash,
+    #[serde(rename = "a", default, skip_serializing_if = "Option::is_none")]
+    pub account: Option<usize>,
+    /// Of the creating transaction, shared by the orders of one batch
+    /// envelope.
+    #[serde(rename = "n")]
+    pub nonce: u64,
+    #[serde(rename = "px")]
+    pub price: U256,
+    /// Positive to buy, negative to sell.
+    #[serde(rename = "sz")]
+    pub size: I256,
+    /// `None` for an order that never expires.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<Timestamp>,
+    #[serde(default)]
+    pub kind: OrderKind,
+    #[serde(rename = "type", default)]
+    pub order_type: OrderType,
+    #[serde(default)]
+    pub reduce_only: bool,
+    #[serde(default)]
+    pub ioc: bool,
+    #[serde(default)]
+    pub post_only: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger: Option<TriggerType>,
+    #[serde(default)]
+    pub grouping: TriggerGrouping,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderEventKind {
+    New,
+    /// Dropped during execution, having never rested.
+    Reject,
+    Fill,
+    Cancel,
+    Expire,
+    Modify,
+    /// An amendment the engine refused. The order is unchanged.
+    ModifyReject,
+    /// Added after this version; to be ignored.
+    #[serde(other)]
+    Unknown,
+}
+
+/// Why an amendment was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AmendRejectCode {
+    InsufficientBalance,
+    InvalidPrice,
+    ZeroSize,
+    NotionalBelowMinimum,
+    UnknownMarket,
+    OrderNotFound,
+    NotOrderOwner,
+    StaleNonce,
+    WrongPair,
+    EngineManagedOrder,
+    PriceAboveMaximum,
+    PriceOffTick,
+    MarketOrderMustBeIoc,
+    SizeAboveMaximum,
+    SizeOffLot,
+    NotionalOverflow,
+    NotionalAboveCap,
+    /// Also any code added after this version.
+    #[serde(other)]
+    Unspecified,
+}
+
+/// Which order an [`OrderEvent`] is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OrderRef {
+    /// Index into the frame's `orders`.
+    Created(usize),
+    /// An order resting from an earlier batch.
+    Resting(Hash),
+}
+
+/// One transition in a `pod_orders_v2` frame. Which fields are set depends on
+/// the kind.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderEvent {
+    #[serde(rename = "k")]
+    pub kind: OrderEventKind,
+    #[serde(rename = "o", default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Hash>,
+    /// Owner of the order named by `id`, into the frame's `accts`.
+    #[serde(rename = "a", default, skip_serializing_if = "Option::is_none")]
+    pub account: Option<usize>,
+    /// Why the order was rejected, or detail on an amendment's refusal.
+    #[serde(rename = "why", default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// `ModifyReject`: the account that asked, into the frame's `accts`.
+    #[serde(rename = "by", default, skip_serializing_if = "Option::is_none")]
+    pub requester: Option<usize>,
+    #[serde(rename = "req_px", default, skip_serializing_if = "Option::is_none")]
+    pub requested_price: Option<U256>,
+    #[serde(rename = "req_sz", default, skip_serializing_if = "Option::is_none")]
+    pub requested_size: Option<U256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<AmendRejectCode>,
+    /// `Fill`: filled by this fill alone.
+    #[serde(rename = "b", default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<U256>,
+    #[serde(rename = "q", default, skip_serializing_if = "Option::is_none")]
+    pub quote: Option<U256>,
+    /// Filled over the order's life: so far on `Fill`, final on `Cancel` and
+    /// `Expire`.
+    #[serde(rename = "tb", default, skip_serializing_if = "Option::is_none")]
+    pub total_base: Option<U256>,
+    #[serde(rename = "tq", default, skip_serializing_if = "Option::is_none")]
+    pub total_quote: Option<U256>,
+    #[serde(rename = "tf", default, skip_serializing_if = "Option::is_none")]
+    pub total_fee: Option<U256>,
+    /// The status the event closed the order with. On `Cancel`, set only when
+    /// the engine removed it.
+    #[serde(rename = "st", default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<OrderStatus>,
+    /// `Fill`: the owner's position after it, when it moved one.
+    #[serde(rename = "pa", default, skip_serializing_if = "Option::is_none")]
+    pub position_after: Option<I256>,
+    /// `Modify`: the price and unsigned size after the change.
+    #[serde(rename = "px", default, skip_serializing_if = "Option::is_none")]
+    pub price: Option<U256>,
+    #[serde(rename = "sz", default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<U256>,
+}
+
+impl OrderEvent {
+    pub fn order(&self) -> Option<OrderRef> {
+        self.index
+            .map(OrderRef::Created)
+            .or(self.id.map(OrderRef::Resting))
+    }
+}
+
+/// A `pod_candles` frame: one orderbook's clearing in one batch, to fold
+/// into the forming bar.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CandleTick {
+    pub orderbook: Hash,
+    /// Batch deadline in microseconds.
+    pub timestamp_us: u64,
+    pub price: U256,
+    pub volume: U256,
+}
+
+/// A `pod_markets` frame: live statistics of one orderbook. Unset fields have
+/// no data yet, or are perp-only.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarketUpdate {
+    #[serde(alias = "orderbook")]
+    pub orderbook_id: Hash,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_clearing_price: Option<U256>,
+    pub volume_24h: U256,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub high_24h: Option<U256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub low_24h: Option<U256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price_change_24h: Option<i128>,
+    // Perp markets only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oracle_price: Option<U256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mark_price: Option<U256>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_decimal",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub funding_rate: Option<I256>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_decimal",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub funding_index: Option<I256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub funding_last_updated_us: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub open_interest: Option<U256>,
+}
+
+/// A signed decimal string, as `pod_markets` sends the funding fields. A
+/// fractional part is dropped, as the TypeScript SDK does.
+fn deserialize_decimal<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<I256>, D::Error> {
+    let Some(decimal) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    let integer = decimal
+        .split_once('.')
+        .map_or(decimal.as_str(), |(integer, _)| integer);
+    integer
+        .parse()
+        .map(Some)
+        .map_err(|e| serde::de::Error::custom(format!("invalid decimal {decimal:?}: {e}")))
+}
+
+/// A `pod_positions` frame: the account's positions after a batch that
+/// touched it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PositionsUpdate {
+    pub account: Address,
+    pub data: PositionsResponse,
+}
+
+/// A `pod_triggers` frame: the account's armed triggers after a batch that
+/// touched it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TriggersUpdate {
+    pub account: Address,
+    #[serde(flatten)]
+    pub page: TriggersPage,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn omits_unset_options() {
+        let params = SubscriptionParams::default().with_since(7);
+        assert_eq!(
+            serde_json::to_value(&params).unwrap(),
+            serde_json::json!({ "since": 7 })
+        );
+        assert_eq!(
+            serde_json::from_value::<SubscriptionParams>(
+                serde_json::json!({ "clob_ids": [Hash::repeat_byte(1)], "since": 7 })
+            )
+            .unwrap(),
+            params.with_orderbooks([Hash::repeat_byte(1)])
+        );
+    }
+
+    #[test]
+    fn decodes_market_funding_decimals() {
+        let frame: MarketUpdate = serde_json::from_str(
+            r#"{
+                "orderbook_id": "0x0000000000000000000000000000000000000000000000000000000000000007",
+                "volume_24h": "0",
+                "mark_price": "15000000000000000000",
+                "funding_rate": "-125000000000000",
+                "funding_index": "3.75",
+                "funding_last_updated_us": 1704153600000000
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            frame.funding_rate,
+            Some(I256::try_from(-125_000_000_000_000i64).unwrap())
+        );
+        assert_eq!(frame.funding_index, Some(I256::try_from(3).unwrap()));
+        assert_eq!(
+            serde_json::from_value::<MarketUpdate>(serde_json::to_value(&frame).unwrap()).unwrap(),
+            frame
+        );
+
+        let spot: MarketUpdate = serde_json::from_str(
+            r#"{"orderbook_id": "0x0000000000000000000000000000000000000000000000000000000000000001", "volume_24h": "0", "funding_rate": null}"#,
+        )
+        .unwrap();
+        assert_eq!(spot.funding_rate, None);
+        assert_eq!(spot.funding_index, None);
+    }
+
+    #[test]
+    fn decodes_orders_frames() {
+        let frame: OrdersFrame = serde_json::from_str(
+            r#"{
+                "book": "0x0000000000000000000000000000000000000000000000000000000000000001",
+                "batch": 1704153600000000,
+                "accts": ["0x742d35Cc6634C0532925a3b844Bc9e7595f2bD28"],
+                "orders": [{
+                    "id": "0x00000000000000000000000000000000000000000000000000000000000000aa",
+                    "tx": "0x00000000000000000000000000000000000000000000000000000000000000bb",
+                    "a": 0,
+                    "n": 3,
+                    "px": "5000000000000000000",
+                    "sz": "-1000000000000000000",
+                    "post_only": true
+                }],
+                "events": [
+                    { "k": "new", "o": 0 },
+                    { "k": "fill", "id": "0x00000000000000000000000000000000000000000000000000000000000000cc", "a": 0,
+                      "b": "1", "q": "5", "tb": "1", "tq": "5", "tf": "0", "st": "filled" },
+                    { "k": "split", "o": 0, "ratio": 2 },
+                    { "k": "modify_reject", "o": 0, "code": "reason_from_the_future" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let order = &frame.orders[0];
+        assert!(order.size.is_negative() && order.post_only && order.end.is_none());
+        assert_eq!(
+            (order.kind, order.order_type),
+            (OrderKind::UserSigned, OrderType::Limit)
+        );
+        assert_eq!(
+            frame.owner(order.account, Address::ZERO),
+            Some(frame.accts.clone().unwrap()[0])
+        );
+        assert_eq!(frame.events[0].order(), Some(OrderRef::Created(0)));
+        assert_eq!(frame.events[1].status, Some(OrderStatus::Filled));
+        assert_eq!(frame.events[2].kind, OrderEventKind::Unknown);
+        assert_eq!(frame.events[3].code, Some(AmendRejectCode::Unspecified));
+
+        let updates: Vec<OrderUpdate> = serde_json::from_str(
+            r#"[
+                { "type": "canceled", "order_id": "0x00000000000000000000000000000000000000000000000000000000000000aa" },
+                { "type": "fill", "orderbook_id": "0x0000000000000000000000000000000000000000000000000000000000000001",
+                  "order_id": "0x00000000000000000000000000000000000000000000000000000000000000aa",
+                  "tx_hash": "0x00000000000000000000000000000000000000000000000000000000000000bb",
+                  "bidder": "0x742d35Cc6634C0532925a3b844Bc9e7595f2bD28", "status": "active",
+                  "base_amount": "0x1", "quote_amount": "0x5", "filled_base_amount": "0x1",
+                  "filled_quote_amount": "0x5", "effective_price": "0x5", "fee": "0x0",
+                  "position_before": null, "position_after": null }
+            ]"#,
+        )
+        .unwrap();
+        assert!(matches!(updates[0], OrderUpdate::Canceled { .. }));
+        assert!(
+            matches!(&updates[1], OrderUpdate::Fill(fill) if fill.status == OrderStatus::Active)
+        );
+    }
+}
@@ -63,6 +63,24 @@ impl Timestamp {
         SystemClock.now()
     }
 
+    /// Round up to the next multiple of `interval`; unchanged if already
+    /// aligned or if `interval` is zero.
+    pub fn align_up(&self, interval: Duration) -> Self {
+        match interval.as_micros() {
+            0 => *self,
+            step => Timestamp(self.0.div_ceil(step) * step),
+        }
+    }
+
+    /// Round down to the previous multiple of `interval`; unchanged if already
+    /// aligned or if `interval` is zero.
+    pub fn align_down(&self, interval: Duration) -> Self {
+        match interval.as_micros() {
+            0 => *self,
+            step => Timestamp(self.0 - self.0 % step),
+        }
+    }
+
     pub fn from_hex_seconds_str(s: &str) -> Result<Self, TimestampError> {
         match s {
             "earliest" => Ok(Self::zero()),
@@ -128,6 +146,21 @@ impl Add<Duration> for Timestamp {
         Timestamp(self.0 + rhs.as_micros())
     }
 }
+
+/// Largest lead validators accept between now and an orderbook deadline.
+pub const MAX_DEADLINE_LEAD: Duration = Duration::from_secs(10 * 60);
+
+/// Deadline for an orderbook intent sent at `now`: the first batch boundary of
+/// the market's `auction_interval` at least `lead` away, so the transaction has
+/// time to reach validators before its batch closes. Never later than
+/// [`MAX_DEADLINE_LEAD`] from `now`: past it, the last boundary within the cap.
+pub fn next_batch_deadline(now: Timestamp, interval: Duration, lead: Duration) -> Timestamp {
+    let latest = (now + MAX_DEADLINE_LEAD).align_down(interval);
+    (now + lead.min(MAX_DEADLINE_LEAD))
+        .align_up(interval)
+        .min(latest)
+}
+
 pub trait Clock {
     fn now(&self) -> Timestamp;
 }
@@ -164,3 +197,42 @@ impl Clock for MockClock {
         self.time
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aligns_to_interval() {
+        let interval = Duration::from_millis(500);
+
+        let t = Timestamp::from_micros(1_250_000);
+        assert_eq!(t.align_up(interval), Timestamp::from_micros(1_500_000));
+        assert_eq!(t.align_down(interval), Timestamp::from_micros(1_000_000));
+
+        let aligned = Timestamp::from_micros(2_000_000);
+        assert_eq!(aligned.align_up(interval), aligned);
+        assert_eq!(aligned.align_down(interval), aligned);
+        assert_eq!(t.align_up(Duration::ZERO), t);
+    }
+
+    #[test]
+    fn next_batch_deadline_leaves_lead() {
+        let now = Timestamp::from_micros(1_250_000);
+        let interval = Duration::from_millis(500);
+
+        assert_eq!(
+            next_batch_deadline(now, interval, Duration::from_secs(1)),
+            Timestamp::from_micros(2_500_000)
+        );
+        // 601.25s is past the cap's boundary at 601s.
+        assert_eq!(
+            next_batch_deadline(now, interval, Duration::from_secs(3600)),
+            Timestamp::from_micros(601_000_000)
+        );
+        assert_eq!(
+            next_batch_deadline(now, interval, Duration::from_millis(599_900)),
+            Timestamp::from_micros(601_000_000)
+        );
+    }
+}
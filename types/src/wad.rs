@@ -0,0 +1,616 @@
+//! 18-decimal fixed-point numbers.
+//!
+//! Every orderbook amount, price and funding index is an integer scaled by
+//! 1e18. [`Wad`] and [`SignedWad`] wrap those integers and round exactly the
+//! way the node does, so margin and PnL computed locally match the chain to
+//! the last unit:
+//!
+//! - `*` truncates toward zero,
+//! - `/` truncates toward zero and yields zero for a zero divisor,
+//! - [`SignedWad::mul_floor`] rounds toward negative infinity,
+//! - `mul_div_ceil` rounds positive results up and truncates negative ones.
+//!
+//! On the wire a wad is its raw integer as a decimal or `0x` hex string; see
+//! [`Wad::from_wire`]. [`FromStr`] and [`Display`] instead use the
+//! human-readable form, e.g. `"1.5"`.
+
+use std::{
+    fmt::{self, Display},
+    ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign},
+    str::FromStr,
+};
+
+use alloy_primitives::{I256, Sign, U256, U512};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Debug, thiserror::Error, Clone, PartialEq, Eq)]
+pub enum WadError {
+    #[error(r#"invalid fixed-point number "{0}""#)]
+    Invalid(String),
+    #[error(r#""{0}" has more than 18 decimals"#)]
+    TooManyDecimals(String),
+    #[error(r#""{0}" is negative"#)]
+    Negative(String),
+    #[error(r#""{0}" does not fit in 256 bits"#)]
+    Overflow(String),
+}
+
+/// 1e18, the raw value of one.
+const SCALE: U256 = U256::from_limbs([1_000_000_000_000_000_000, 0, 0, 0]);
+
+/// Unsigned 18-decimal fixed-point number.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Wad(U256);
+
+/// Signed 18-decimal fixed-point number.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SignedWad(I256);
+
+impl Wad {
+    pub const DECIMALS: usize = 18;
+    pub const ZERO: Wad = Wad(U256::ZERO);
+    pub const ONE: Wad = Wad(SCALE);
+
+    pub const fn from_raw(raw: U256) -> Self {
+        Wad(raw)
+    }
+
+    pub const fn raw(self) -> U256 {
+        self.0
+    }
+
+    pub fn from_integer(value: u64) -> Self {
+        Wad(U256::from(value) * SCALE)
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0.is_zero()
+    }
+
+    /// `self * rhs / divisor` with the product kept at full precision and the
+    /// quotient rounded up. `divisor` is a raw integer, not a wad; zero yields
+    /// zero.
+    pub fn mul_div_ceil(self, rhs: Wad, divisor: U256) -> Self {
+        if divisor.is_zero() {
+            return Wad::ZERO;
+        }
+        let (quotient, exact) = mul_div(self.0, rhs.0, divisor);
+        Wad(if exact {
+            quotient
+        } else {
+            quotient + U256::from(1)
+        })
+    }
+
+    /// Parses a wire value: the raw integer as a decimal or `0x` hex string.
+    /// A fractional part is dropped, and the empty string is zero.
+    pub fn from_wire(s: &str) -> Result<Self, WadError> {
+        let (negative, raw) = parse_wire(s)?;
+        if negative && !raw.is_zero() {
+            return Err(WadError::Negative(s.to_owned()));
+        }
+        Ok(Wad(raw))
+    }
+}
+
+impl SignedWad {
+    pub const DECIMALS: usize = 18;
+    pub const ZERO: SignedWad = SignedWad(I256::ZERO);
+    pub const ONE: SignedWad = SignedWad(I256::from_raw(SCALE));
+
+    pub const fn from_raw(raw: I256) -> Self {
+        SignedWad(raw)
+    }
+
+    pub const fn raw(self) -> I256 {
+        self.0
+    }
+
+    pub fn from_integer(value: i64) -> Self {
+        SignedWad(I256::try_from(value).expect("i64 fits in I256") * Self::ONE.0)
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0.is_negative()
+    }
+
+    pub fn abs(self) -> Wad {
+        Wad(self.0.unsigned_abs())
+    }
+
+    /// `self * rhs`, rounded toward negative infinity rather than zero.
+    pub fn mul_floor(self, rhs: SignedWad) -> Self {
+        let (quotient, exact) = mul_div(self.0.unsigned_abs(), rhs.0.unsigned_abs(), SCALE);
+        let sign = product_sign(self, rhs);
+        if sign.is_negative() && !exact {
+            signed(sign, quotient + U256::from(1))
+        } else {
+            signed(sign, quotient)
+        }
+    }
+
+    /// `self * rhs / divisor` with the product kept at full precision. Positive
+    /// results round up, negative ones truncate toward zero. `divisor` is a
+    /// raw integer, not a wad; zero yields zero.
+    pub fn mul_div_ceil(self, rhs: SignedWad, divisor: U256) -> Self {
+        if divisor.is_zero() {
+            return SignedWad::ZERO;
+        }
+        let (quotient, exact) = mul_div(self.0.unsigned_abs(), rhs.0.unsigned_abs(), divisor);
+        let sign = product_sign(self, rhs);
+        if sign.is_positive() && !exact {
+            signed(sign, quotient + U256::from(1))
+        } else {
+            signed(sign, quotient)
+        }
+    }
+
+    /// Parses a wire value: the raw integer as a decimal or `0x` hex string,
+    /// optionally preceded by `-`. A fractional part is dropped, and the empty
+    /// string is zero.
+    pub fn from_wire(s: &str) -> Result<Self, WadError> {
+        let (negative, raw) = parse_wire(s)?;
+        let sign = if negative {
+            Sign::Negative
+        } else {
+            Sign::Positive
+        };
+        I256::checked_from_sign_and_abs(sign, raw)
+            .map(SignedWad)
+            .ok_or_else(|| WadError::Overflow(s.to_owned()))
+    }
+}
+
+/// `a * b / d` over 512 bits, and whether it divided exactly.
+///
+/// # Panics
+///
+/// If the quotient does not fit in 256 bits.
+fn mul_div(a: U256, b: U256, d: U256) -> (U256, bool) {
+    let product: U512 = a.widening_mul(b);
+    let (quotient, remainder) = product.div_rem(U512::from(d));
+    (U256::from(quotient), remainder.is_zero())
+}
+
+fn product_sign(a: SignedWad, b: SignedWad) -> Sign {
+    if a.0.is_negative() == b.0.is_negative() {
+        Sign::Positive
+    } else {
+        Sign::Negative
+    }
+}
+
+/// # Panics
+///
+/// If the magnitude does not fit in `I256`.
+fn signed(sign: Sign, magnitude: U256) -> SignedWad {
+    let sign = if magnitude.is_zero() {
+        Sign::Positive
+    } else {
+        sign
+    };
+    SignedWad(I256::checked_from_sign_and_abs(sign, magnitude).expect("SignedWad overflow"))
+}
+
+impl Mul for Wad {
+    type Output = Wad;
+
+    /// Truncates toward zero.
+    fn mul(self, rhs: Wad) -> Wad {
+        Wad(mul_div(self.0, rhs.0, SCALE).0)
+    }
+}
+
+impl Div for Wad {
+    type Output = Wad;
+
+    /// Truncates toward zero; dividing by zero yields zero.
+    fn div(self, rhs: Wad) -> Wad {
+        if rhs.is_zero() {
+            return Wad::ZERO;
+        }
+        Wad(mul_div(self.0, SCALE, rhs.0).0)
+    }
+}
+
+impl Mul for SignedWad {
+    type Output = SignedWad;
+
+    /// Truncates toward zero.
+    fn mul(self, rhs: SignedWad) -> SignedWad {
+        let (quotient, _) = mul_div(self.0.unsigned_abs(), rhs.0.unsigned_abs(), SCALE);
+        signed(product_sign(self, rhs), quotient)
+    }
+}
+
+impl Div for SignedWad {
+    type Output = SignedWad;
+
+    /// Truncates toward zero; dividing by zero yields zero.
+    fn div(self, rhs: SignedWad) -> SignedWad {
+        if rhs.is_zero() {
+            return SignedWad::ZERO;
+        }
+        let (quotient, _) = mul_div(self.0.unsigned_abs(), SCALE, rhs.0.unsigned_abs());
+        signed(product_sign(self, rhs), quotient)
+    }
+}
+
+macro_rules! additive_ops {
+    ($wad:ident) => {
+        impl $wad {
+            /// `self + rhs`, or `None` if it leaves the representable range.
+            pub fn checked_add(self, rhs: $wad) -> Option<$wad> {
+                self.0.checked_add(rhs.0).map(Self)
+            }
+
+            /// `self - rhs`, or `None` if it leaves the representable range.
+            pub fn checked_sub(self, rhs: $wad) -> Option<$wad> {
+                self.0.checked_sub(rhs.0).map(Self)
+            }
+
+            /// `self - rhs`, clamped to the representable range.
+            pub fn saturating_sub(self, rhs: $wad) -> $wad {
+                Self(self.0.saturating_sub(rhs.0))
+            }
+        }
+
+        impl Add for $wad {
+            type Output = $wad;
+
+            /// # Panics
+            ///
+            /// If the sum leaves the representable range.
+            fn add(self, rhs: $wad) -> $wad {
+                self.checked_add(rhs)
+                    .expect(concat!(stringify!($wad), " overflow"))
+            }
+        }
+
+        impl Sub for $wad {
+            type Output = $wad;
+
+            /// # Panics
+            ///
+            /// If the difference leaves the representable range, e.g. below
+            /// zero for a [`Wad`].
+            fn sub(self, rhs: $wad) -> $wad {
+                self.checked_sub(rhs)
+                    .expect(concat!(stringify!($wad), " underflow"))
+            }
+        }
+
+        impl AddAssign for $wad {
+            fn add_assign(&mut self, rhs: $wad) {
+                *self = *self + rhs;
+            }
+        }
+
+        impl SubAssign for $wad {
+            fn sub_assign(&mut self, rhs: $wad) {
+                *self = *self - rhs;
+            }
+        }
+
+        impl std::iter::Sum for $wad {
+            fn sum<I: Iterator<Item = $wad>>(iter: I) -> $wad {
+                iter.fold(Self::ZERO, Add::add)
+            }
+        }
+    };
+}
+
+additive_ops!(Wad);
+additive_ops!(SignedWad);
+
+impl Neg for SignedWad {
+    type Output = SignedWad;
+
+    fn neg(self) -> SignedWad {
+        SignedWad(-self.0)
+    }
+}
+
+impl From<Wad> for SignedWad {
+    /// # Panics
+    ///
+    /// If `value` does not fit in `I256`.
+    fn from(value: Wad) -> SignedWad {
+        signed(Sign::Positive, value.0)
+    }
+}
+
+impl TryFrom<SignedWad> for Wad {
+    type Error = WadError;
+
+    fn try_from(value: SignedWad) -> Result<Wad, WadError> {
+        if value.is_negative() {
+            return Err(WadError::Negative(value.to_string()));
+        }
+        Ok(value.abs())
+    }
+}
+
+fn parse_wire(s: &str) -> Result<(bool, U256), WadError> {
+    let (negative, body) = match s.strip_prefix('-') {
+        Some(body) => (true, body),
+        None => (false, s),
+    };
+    let raw = if let Some(hex) = body.strip_prefix("0x") {
+        U256::from_str_radix(hex, 16)
+    } else {
+        let integer = body.split_once('.').map_or(body, |(integer, _)| integer);
+        if integer.is_empty() {
+            return Ok((false, U256::ZERO));
+        }
+        U256::from_str_radix(integer, 10)
+    };
+    raw.map(|raw| (negative, raw))
+        .map_err(|_| WadError::Invalid(s.to_owned()))
+}
+
+/// Parses a human-readable decimal such as `"-12.5"` into its sign and raw
+/// 1e18-scaled magnitude.
+fn parse_decimal(s: &str) -> Result<(bool, U256), WadError> {
+    let invalid = || WadError::Invalid(s.to_owned());
+    let (negative, body) = match s.strip_prefix('-') {
+        Some(body) => (true, body),
+        None => (false, s),
+    };
+    let (integer, fraction) = body.split_once('.').unwrap_or((body, ""));
+    let all_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if integer.is_empty() && fraction.is_empty() || !all_digits(integer) || !all_digits(fraction) {
+        return Err(invalid());
+    }
+    if fraction.len() > Wad::DECIMALS {
+        return Err(WadError::TooManyDecimals(s.to_owned()));
+    }
+    let parse = |digits: &str| {
+        if digits.is_empty() {
+            Ok(U256::ZERO)
+        } else {
+            U256::from_str_radix(digits, 10).map_err(|_| WadError::Overflow(s.to_owned()))
+        }
+    };
+    let fraction_scale = U256::from(10).pow(U256::from(Wad::DECIMALS - fraction.len()));
+    let raw = parse(integer)?
+        .checked_mul(SCALE)
+        .and_then(|scaled| scaled.checked_add(parse(fraction).ok()? * fraction_scale))
+        .ok_or_else(|| WadError::Overflow(s.to_owned()))?;
+    Ok((negative, raw))
+}
+
+impl FromStr for Wad {
+    type Err = WadError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, raw) = parse_decimal(s)?;
+        if negative && !raw.is_zero() {
+            return Err(WadError::Negative(s.to_owned()));
+        }
+        Ok(Wad(raw))
+    }
+}
+
+impl FromStr for SignedWad {
+    type Err = WadError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, raw) = parse_decimal(s)?;
+        let sign = if negative {
+            Sign::Negative
+        } else {
+            Sign::Positive
+        };
+        I256::checked_from_sign_and_abs(sign, raw)
+            .map(SignedWad)
+            .ok_or_else(|| WadError::Overflow(s.to_owned()))
+    }
+}
+
+/// Writes `magnitude` as a decimal. With a precision (`{:.2}`) the fraction is
+/// truncated to that many digits; without one, trailing zeros are trimmed.
+fn fmt_decimal(f: &mut fmt::Formatter<'_>, negative: bool, magnitude: U256) -> fmt::Result {
+    let (integer, fraction) = magnitude.div_rem(SCALE);
+    let fraction = format!("{:0>width$}", fraction.to_string(), width = Wad::DECIMALS);
+    let fraction = match f.precision() {
+        Some(precision) => &fraction[..precision.min(Wad::DECIMALS)],
+        None => fraction.trim_end_matches('0'),
+    };
+    let sign = if negative { "-" } else { "" };
+    let padding = f.precision().unwrap_or(0).saturating_sub(Wad::DECIMALS);
+    if fraction.is_empty() && padding == 0 {
+        write!(f, "{sign}{integer}")
+    } else {
+        write!(f, "{sign}{integer}.{fraction}{:0<padding$}", "")
+    }
+}
+
+impl Display for Wad {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_decimal(f, false, self.0)
+    }
+}
+
+impl Display for SignedWad {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_decimal(f, self.0.is_negative(), self.0.unsigned_abs())
+    }
+}
+
+/// Serializes as the raw integer in `0x` hex, the way the node sends unsigned
+/// values.
+impl Serialize for Wad {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+/// Serializes as the raw integer in decimal, the way the node sends signed
+/// values.
+impl Serialize for SignedWad {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.0)
+    }
+}
+
+/// Wire values arrive as strings, but tolerate plain JSON integers too.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WireValue<'a> {
+    Str(&'a str),
+    String(String),
+    Unsigned(u64),
+    Signed(i64),
+}
+
+impl<'de> Deserialize<'de> for Wad {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match WireValue::deserialize(deserializer)? {
+            WireValue::Str(s) => Wad::from_wire(s),
+            WireValue::String(s) => Wad::from_wire(&s),
+            WireValue::Unsigned(n) => Ok(Wad(U256::from(n))),
+            WireValue::Signed(n) => Wad::from_wire(&n.to_string()),
+        }
+        .map_err(serde::de::Error::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for SignedWad {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match WireValue::deserialize(deserializer)? {
+            WireValue::Str(s) => SignedWad::from_wire(s),
+            WireValue::String(s) => SignedWad::from_wire(&s),
+            WireValue::Unsigned(n) => SignedWad::from_wire(&n.to_string()),
+            WireValue::Signed(n) => SignedWad::from_wire(&n.to_string()),
+        }
+        .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn w(s: &str) -> Wad {
+        s.parse().unwrap()
+    }
+
+    fn sw(s: &str) -> SignedWad {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn rounding_matches_the_node() {
+        let tiny = SignedWad::from_raw(I256::ONE);
+        let half = sw("0.5");
+
+        assert_eq!(w("1.5") * w("2"), w("3"));
+        assert_eq!(tiny * half, SignedWad::ZERO);
+        assert_eq!(-tiny * half, SignedWad::ZERO);
+        assert_eq!(tiny.mul_floor(half), SignedWad::ZERO);
+        assert_eq!((-tiny).mul_floor(half), -tiny);
+        assert_eq!(sw("-1") / sw("3"), sw("-0.333333333333333333"));
+        assert_eq!(w("1") / Wad::ZERO, Wad::ZERO);
+
+        // 1 * 1 / 3 in raw units: positive rounds up, negative truncates.
+        let one = SignedWad::from_raw(I256::ONE);
+        assert_eq!(one.mul_div_ceil(one, U256::from(3)), one);
+        assert_eq!((-one).mul_div_ceil(one, U256::from(3)), SignedWad::ZERO);
+        let two = SignedWad::from_raw(I256::try_from(2).unwrap());
+        assert_eq!(two.mul_div_ceil(two, U256::from(3)), two);
+        assert_eq!((-two).mul_div_ceil(two, U256::from(3)), -one);
+        assert_eq!(two.mul_div_ceil(two, U256::ZERO), SignedWad::ZERO);
+        assert_eq!(
+            Wad::from_raw(U256::from(2)).mul_div_ceil(Wad::from_raw(U256::from(2)), U256::from(3)),
+            Wad::from_raw(U256::from(2))
+        );
+    }
+
+    #[test]
+    fn additive_edges() {
+        assert_eq!(Wad::ONE.checked_sub(w("2")), None);
+        assert_eq!(Wad::ONE.saturating_sub(w("2")), Wad::ZERO);
+        assert_eq!(w("3").checked_sub(w("2")), Some(Wad::ONE));
+        assert_eq!(
+            Wad::from_raw(U256::MAX).checked_add(Wad::from_raw(U256::ONE)),
+            None
+        );
+
+        let min = SignedWad::from_raw(I256::MIN);
+        let max = SignedWad::from_raw(I256::MAX);
+        assert_eq!(min.checked_sub(SignedWad::from_raw(I256::ONE)), None);
+        assert_eq!(min.saturating_sub(SignedWad::ONE), min);
+        assert_eq!(max.checked_add(SignedWad::from_raw(I256::ONE)), None);
+        assert_eq!(SignedWad::ONE.checked_sub(sw("2")), Some(-SignedWad::ONE));
+    }
+
+    #[test]
+    #[should_panic(expected = "Wad underflow")]
+    fn wad_sub_below_zero_panics() {
+        let _ = Wad::ONE - w("2");
+    }
+
+    #[test]
+    #[should_panic(expected = "SignedWad overflow")]
+    fn signed_wad_add_past_max_panics() {
+        let _ = SignedWad::from_raw(I256::MAX) + SignedWad::from_raw(I256::ONE);
+    }
+
+    #[test]
+    fn parse_wire_values() {
+        assert_eq!(Wad::from_wire("0xde0b6b3a7640000").unwrap(), Wad::ONE);
+        assert_eq!(Wad::from_wire("1000000000000000000").unwrap(), Wad::ONE);
+        assert_eq!(Wad::from_wire("").unwrap(), Wad::ZERO);
+        assert_eq!(
+            SignedWad::from_wire("-1000000000000000000.9").unwrap(),
+            -SignedWad::ONE
+        );
+        assert_eq!(
+            SignedWad::from_wire("-0xde0b6b3a7640000").unwrap(),
+            -SignedWad::ONE
+        );
+        assert!(matches!(Wad::from_wire("-1"), Err(WadError::Negative(_))));
+        assert!(matches!(Wad::from_wire("0xzz"), Err(WadError::Invalid(_))));
+
+        let unsigned: Wad = serde_json::from_str(r#""0xde0b6b3a7640000""#).unwrap();
+        assert_eq!(unsigned, Wad::ONE);
+        assert_eq!(
+            serde_json::to_string(&unsigned).unwrap(),
+            r#""0xde0b6b3a7640000""#
+        );
+        let signed: SignedWad = serde_json::from_str(r#""-1500000000000000000""#).unwrap();
+        assert_eq!(signed, sw("-1.5"));
+        assert_eq!(
+            serde_json::to_string(&signed).unwrap(),
+            r#""-1500000000000000000""#
+        );
+    }
+
+    #[test]
+    fn display_and_from_str() {
+        assert_eq!(w("1.50").to_string(), "1.5");
+        assert_eq!(w("42").to_string(), "42");
+        assert_eq!(w(".25").to_string(), "0.25");
+        assert_eq!(
+            sw("-0.000000000000000001").to_string(),
+            "-0.000000000000000001"
+        );
+        assert_eq!(format!("{:.2}", sw("-3.14159")), "-3.14");
+        assert_eq!(format!("{:.0}", w("3.99")), "3");
+        assert_eq!(format!("{:.20}", w("1")), "1.00000000000000000000");
+        assert_eq!(sw("-0").to_string(), "0");
+
+        assert!(matches!(
+            "1.0000000000000000001".parse::<Wad>(),
+            Err(WadError::TooManyDecimals(_))
+        ));
+        assert!(matches!("-1".parse::<Wad>(), Err(WadError::Negative(_))));
+        assert!(matches!("1e5".parse::<Wad>(), Err(WadError::Invalid(_))));
+        assert!(matches!(".".parse::<Wad>(), Err(WadError::Invalid(_))));
+    }
+}